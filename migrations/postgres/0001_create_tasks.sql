@@ -0,0 +1,6 @@
+create table if not exists tasks (
+    id serial primary key,
+    name text not null,
+    description text,
+    completed boolean not null default false
+);
@@ -0,0 +1,6 @@
+alter table tasks
+    add column created_at timestamptz not null default now(),
+    add column updated_at timestamptz not null default now(),
+    add column completed_at timestamptz,
+    add column due timestamptz,
+    add column scheduled timestamptz;
@@ -0,0 +1 @@
+alter table tasks add column priority integer not null default 0;
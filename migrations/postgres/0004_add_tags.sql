@@ -0,0 +1,10 @@
+create table tags (
+    id serial primary key,
+    name text not null unique
+);
+
+create table task_tags (
+    task_id integer not null references tasks (id) on delete cascade,
+    tag_id integer not null references tags (id) on delete cascade,
+    primary key (task_id, tag_id)
+);
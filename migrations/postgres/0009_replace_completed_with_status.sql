@@ -0,0 +1,5 @@
+alter table tasks add column status text not null default 'todo';
+
+update tasks set status = 'done' where completed;
+
+alter table tasks drop column completed;
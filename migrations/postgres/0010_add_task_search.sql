@@ -0,0 +1,5 @@
+alter table tasks add column search tsvector generated always as (
+    setweight(to_tsvector('simple', name), 'A') || setweight(to_tsvector('simple', coalesce(description, '')), 'B')
+) stored;
+
+create index tasks_search_index on tasks using gin (search);
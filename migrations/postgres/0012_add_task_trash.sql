@@ -0,0 +1,5 @@
+-- Removed tasks stay in the table, marked with when they were moved to the trash, until
+-- they are restored or purged.
+alter table tasks add column deleted_at timestamptz;
+
+create index tasks_deleted_at_index on tasks (deleted_at);
@@ -0,0 +1,10 @@
+alter table tasks add column created_at text not null default '1970-01-01T00:00:00+00:00';
+alter table tasks add column updated_at text not null default '1970-01-01T00:00:00+00:00';
+alter table tasks add column completed_at text;
+alter table tasks add column due text;
+alter table tasks add column scheduled text;
+
+-- Existing tasks have no recorded creation time, so they start their history now.
+update tasks
+set created_at = strftime('%Y-%m-%dT%H:%M:%S+00:00', 'now'),
+    updated_at = strftime('%Y-%m-%dT%H:%M:%S+00:00', 'now');
@@ -0,0 +1,8 @@
+create table projects (
+    id integer primary key autoincrement,
+    name text not null unique,
+    description text,
+    archived boolean not null default false
+);
+
+alter table tasks add column project_id integer references projects (id) on delete set null;
@@ -0,0 +1,2 @@
+-- Removing a task moves its subtasks up to the top level rather than removing them too.
+alter table tasks add column parent_id integer references tasks (id) on delete set null;
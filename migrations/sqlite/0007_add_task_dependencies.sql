@@ -0,0 +1,5 @@
+create table task_dependencies (
+    task_id integer not null references tasks (id) on delete cascade,
+    depends_on_id integer not null references tasks (id) on delete cascade,
+    primary key (task_id, depends_on_id)
+);
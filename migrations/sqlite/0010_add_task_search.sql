@@ -0,0 +1,22 @@
+create virtual table tasks_search using fts5 (
+    name,
+    description,
+    content = 'tasks',
+    content_rowid = 'id',
+    tokenize = 'unicode61 remove_diacritics 0'
+);
+
+insert into tasks_search (rowid, name, description) select id, name, description from tasks;
+
+create trigger tasks_search_insert after insert on tasks begin
+    insert into tasks_search (rowid, name, description) values (new.id, new.name, new.description);
+end;
+
+create trigger tasks_search_delete after delete on tasks begin
+    insert into tasks_search (tasks_search, rowid, name, description) values ('delete', old.id, old.name, old.description);
+end;
+
+create trigger tasks_search_update after update of name, description on tasks begin
+    insert into tasks_search (tasks_search, rowid, name, description) values ('delete', old.id, old.name, old.description);
+    insert into tasks_search (rowid, name, description) values (new.id, new.name, new.description);
+end;
@@ -0,0 +1,12 @@
+-- Every change made through the repository, so `todo undo` can step back through them.
+-- The tasks before and after each change are kept as JSON, the same form backups use.
+create table journal (
+    id integer primary key autoincrement,
+    batch integer not null,
+    command text not null,
+    created_at text not null,
+    undone boolean not null default false,
+    changes text not null
+);
+
+create index journal_batch_index on journal (batch);
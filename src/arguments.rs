@@ -1,5 +1,12 @@
+use crate::dates::{parse_date_time, parse_due_date_time};
+use crate::output::{Column, OutputFormat};
+use crate::tasks::{normalise_tag, Priority, Recurrence, TagSelector, TaskSort};
+use chrono::{DateTime, FixedOffset, Utc};
 use clap::{Parser, Subcommand};
 use log::LevelFilter;
+use std::path::PathBuf;
+use std::str::FromStr;
+use url::Url;
 
 #[derive(Parser)]
 #[command(about, version, author, propagate_version = true)]
@@ -7,6 +14,12 @@ pub struct Arguments {
     #[arg(short, long, help = "Sets the log level")]
     pub verbosity: Option<LevelFilter>,
 
+    #[arg(short, long, global = true, value_enum, default_value_t, alias = "format", help = "How read commands print their results")]
+    pub output: OutputFormat,
+
+    #[arg(long, global = true, help = "Show what a command would change without saving anything")]
+    pub dry_run: bool,
+
     #[clap(subcommand)]
     pub command: Commands,
 }
@@ -18,18 +31,312 @@ pub enum Commands {
         storage: Option<String>,
     },
 
-    #[clap(about = "Lists all tasks")]
-    List,
+    #[clap(about = "Lists all tasks, or those matching a query")]
+    List {
+        #[arg(help = "Such as \"status:open and (tag:ops or priority>=high) and due<friday\"")]
+        query: Vec<String>,
+
+        #[arg(long, help = "Only list open tasks that are past their due date")]
+        overdue: bool,
+
+        #[arg(long, value_parser = parse_date_time, help = "Only list tasks due before this date")]
+        due_before: Option<DateTime<Utc>>,
+
+        #[arg(long, value_parser = parse_date_time, help = "Only list tasks due after this date")]
+        due_after: Option<DateTime<Utc>>,
+
+        #[arg(long, value_enum, default_value_t, help = "The order to list tasks in")]
+        sort: TaskSort,
+
+        #[arg(short, long = "tag", value_parser = TagSelector::from_str, help = "Only list tasks with this tag, or without it if it starts with \"!\"")]
+        tags: Vec<TagSelector>,
+
+        #[arg(long, help = "Only list tasks in this project")]
+        project: Option<String>,
+
+        #[arg(long, help = "Also list tasks in archived projects")]
+        archived: bool,
+
+        #[arg(long, help = "Only list open tasks that are not waiting for other tasks")]
+        ready: bool,
+
+        #[arg(long, value_delimiter = ',', value_parser = Column::from_str, help = "The columns to show, such as \"id,status,due,name\"")]
+        columns: Vec<Column>,
+    },
+
+    #[clap(about = "Finds tasks by the words in their name or description")]
+    Search {
+        #[arg(required = true, help = "Words the tasks have to contain, such as \"tls cert\"")]
+        terms: Vec<String>,
+
+        #[arg(short = 'n', long, default_value_t = 20, help = "The most results to show")]
+        limit: usize,
+    },
 
     #[clap(about = "Adds a new task")]
     Add {
         name: String,
         description: Option<String>,
+
+        #[arg(long, value_parser = parse_due_date_time, help = "When the task is due, such as \"friday 5pm\" or \"in 3 days\"")]
+        due: Option<DateTime<Utc>>,
+
+        #[arg(long, value_parser = parse_date_time, help = "When to start working on the task")]
+        scheduled: Option<DateTime<Utc>>,
+
+        #[arg(short, long, value_parser = Priority::from_str, default_value_t, help = "none, low, medium, high, urgent, or 0 to 4")]
+        priority: Priority,
+
+        #[arg(short, long = "tag", value_parser = normalise_tag, help = "Tags the task, can be given more than once")]
+        tags: Vec<String>,
+
+        #[arg(long, help = "The project the task belongs to")]
+        project: Option<String>,
+
+        #[arg(long, help = "Makes the task a subtask of the task with this number")]
+        parent: Option<i32>,
+
+        #[arg(short, long, value_parser = Recurrence::from_str, help = "How the task repeats, such as \"weekly\" or \"3 days after completion\"")]
+        repeat: Option<Recurrence>,
+    },
+
+    #[clap(about = "Shows everything about a task")]
+    Show { number: i32 },
+
+    #[clap(about = "Changes a task, in $EDITOR unless fields are given")]
+    Edit {
+        number: i32,
+
+        #[arg(long, help = "The new name of the task")]
+        name: Option<String>,
+
+        #[arg(long, conflicts_with = "clear_description", help = "The new description of the task")]
+        description: Option<String>,
+
+        #[arg(long, help = "Removes the description of the task")]
+        clear_description: bool,
+
+        #[arg(long, value_parser = parse_due_date_time, conflicts_with = "clear_due", help = "When the task is due, such as \"friday 5pm\" or \"in 3 days\"")]
+        due: Option<DateTime<Utc>>,
+
+        #[arg(long, help = "Removes the due date of the task")]
+        clear_due: bool,
+
+        #[arg(long, value_parser = parse_date_time, conflicts_with = "clear_scheduled", help = "When to start working on the task")]
+        scheduled: Option<DateTime<Utc>>,
+
+        #[arg(long, help = "Removes the scheduled date of the task")]
+        clear_scheduled: bool,
     },
 
     #[clap(about = "Removes a task")]
     Remove { number: i32 },
 
     #[clap(about = "Completes a task")]
-    Complete { number: i32 },
+    Complete {
+        number: i32,
+
+        #[arg(long, help = "Also complete every open subtask")]
+        cascade: bool,
+
+        #[arg(long, help = "Complete the task even if it is waiting for other tasks")]
+        force: bool,
+    },
+
+    #[clap(about = "Marks a task as in progress")]
+    Start { number: i32 },
+
+    #[clap(about = "Opens a completed or cancelled task again")]
+    Reopen { number: i32 },
+
+    #[clap(about = "Cancels a task without completing it")]
+    Cancel { number: i32 },
+
+    #[clap(about = "Marks a task as waiting on something outside the task list")]
+    Wait {
+        number: i32,
+
+        #[arg(long, help = "Mark the task as blocked instead")]
+        blocked: bool,
+    },
+
+    #[clap(about = "Sets how a task repeats once it is completed")]
+    Repeat {
+        number: i32,
+
+        #[arg(value_parser = Recurrence::from_str, help = "Such as \"daily\", \"every 2 weeks on mon,thu\" or \"3 days after completion\", leave out to stop repeating")]
+        rule: Option<Recurrence>,
+    },
+
+    #[clap(about = "Makes a task wait for other tasks to be completed")]
+    Depend {
+        number: i32,
+
+        #[arg(long, required = true, help = "The task to wait for, can be given more than once")]
+        on: Vec<i32>,
+
+        #[arg(long, help = "Stop waiting for the given tasks instead")]
+        remove: bool,
+    },
+
+    #[clap(about = "Moves a task below another task, or back to the top level")]
+    Parent {
+        number: i32,
+
+        #[arg(help = "The new parent, leave out to make the task a top level task")]
+        parent: Option<i32>,
+    },
+
+    #[clap(about = "Changes the priority of a task")]
+    Prioritize {
+        number: i32,
+
+        #[arg(value_parser = Priority::from_str, help = "none, low, medium, high, urgent, or 0 to 4")]
+        priority: Priority,
+    },
+
+    #[clap(about = "Adds or removes tags on a task")]
+    Tag {
+        #[clap(subcommand)]
+        command: TagCommands,
+    },
+
+    #[clap(about = "Lists every tag in use and how many tasks have it")]
+    Tags,
+
+    #[clap(about = "Manages projects")]
+    Project {
+        #[clap(subcommand)]
+        command: ProjectCommands,
+    },
+
+    #[clap(about = "Lists, restores and empties removed tasks")]
+    Trash {
+        #[clap(subcommand)]
+        command: TrashCommands,
+    },
+
+    #[clap(about = "Checks the configured storage for problems and repairs them")]
+    Doctor {
+        #[arg(long, help = "Only report problems, do not repair anything")]
+        check: bool,
+    },
+
+    #[clap(about = "Shows how a date expression is understood")]
+    ParseDate {
+        #[arg(allow_hyphen_values = true, help = "A date expression, such as \"next friday 5pm\" (quoted when it has spaces)")]
+        expression: String,
+
+        #[arg(long, value_parser = DateTime::parse_from_rfc3339, help = "Resolve the expression relative to this RFC 3339 timestamp instead of now")]
+        now: Option<DateTime<FixedOffset>>,
+
+        #[arg(long, help = "Read the expression as a due date, where a day without a time of day lasts until its end")]
+        due: bool,
+    },
+
+    #[clap(about = "Copies every task and project from one storage URL to another")]
+    Migrate {
+        #[arg(long, help = "Where to copy from, such as \"file:///home/user/.config/todo/default.todo.ron\"")]
+        from: Url,
+
+        #[arg(long, help = "Where to copy to, such as \"postgresql://user@host/todo\"")]
+        to: Url,
+    },
+
+    #[clap(about = "Writes a compressed snapshot of every task and project to the backup directory")]
+    Backup {
+        #[arg(long, help = "Where to write the snapshot instead of the configured backup directory")]
+        directory: Option<PathBuf>,
+    },
+
+    #[clap(about = "Puts the configured storage back the way it was in a backup")]
+    Restore {
+        #[arg(help = "A snapshot written by `todo backup`, or \"latest\" for the newest in the backup directory")]
+        snapshot: String,
+    },
+
+    #[clap(about = "Takes back the changes made by the last command that changed any tasks")]
+    Undo {
+        #[arg(long, help = "Undo even if the tasks have been changed since by something other than todo, such as a restore")]
+        force: bool,
+    },
+
+    #[clap(about = "Makes the changes taken back by the last undo again")]
+    Redo {
+        #[arg(long, help = "Redo even if the tasks have been changed since by something other than todo")]
+        force: bool,
+    },
+
+    #[clap(about = "Lists the recent commands that changed tasks, newest first")]
+    History {
+        #[arg(short = 'n', long, default_value_t = 10, help = "How many commands to list")]
+        limit: usize,
+    },
+
+    #[clap(about = "Manages the database schema")]
+    Db {
+        #[clap(subcommand)]
+        command: DbCommands,
+    },
+}
+
+#[derive(Subcommand, PartialEq, Debug)]
+pub enum TagCommands {
+    #[clap(about = "Adds tags to a task")]
+    Add {
+        number: i32,
+
+        #[arg(required = true, value_parser = normalise_tag)]
+        tags: Vec<String>,
+    },
+
+    #[clap(about = "Removes tags from a task")]
+    Remove {
+        number: i32,
+
+        #[arg(required = true, value_parser = normalise_tag)]
+        tags: Vec<String>,
+    },
+}
+
+#[derive(Subcommand, PartialEq, Debug)]
+pub enum ProjectCommands {
+    #[clap(about = "Creates a new project")]
+    Add {
+        name: String,
+        description: Option<String>,
+    },
+
+    #[clap(about = "Lists all projects")]
+    List,
+
+    #[clap(about = "Archives a project, hiding its tasks from the default listing")]
+    Archive { name: String },
+
+    #[clap(about = "Brings an archived project back")]
+    Unarchive { name: String },
+
+    #[clap(about = "Shows a project and its tasks")]
+    Show { name: String },
+}
+
+#[derive(Subcommand, PartialEq, Debug)]
+pub enum TrashCommands {
+    #[clap(about = "Lists the tasks in the trash")]
+    List,
+
+    #[clap(about = "Takes a task back out of the trash")]
+    Restore { number: i32 },
+
+    #[clap(about = "Deletes every task in the trash for good")]
+    Empty,
+}
+
+#[derive(Subcommand, PartialEq, Debug)]
+pub enum DbCommands {
+    #[clap(about = "Applies any pending schema migrations")]
+    Migrate,
+
+    #[clap(about = "Shows the current schema version and pending migrations")]
+    Status,
 }
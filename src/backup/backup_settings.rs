@@ -0,0 +1,50 @@
+use crate::config::Config;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Where `todo backup` writes snapshots and how many it keeps.
+///
+/// Read from the `[backups]` table of the config file:
+///
+/// ```toml
+/// [backups]
+/// directory = "/home/user/backups/todo"
+/// keep_last = 10
+/// keep_daily = 7
+/// ```
+#[derive(Debug, Hash, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackupSettings {
+    /// Defaults to `backups` next to the config file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub directory: Option<PathBuf>,
+    /// How many of the newest snapshots are always kept.
+    pub keep_last: usize,
+    /// How many days back the newest snapshot of each day is kept as well.
+    pub keep_daily: usize,
+}
+
+impl Default for BackupSettings {
+    fn default() -> Self {
+        Self {
+            directory: None,
+            keep_last: 10,
+            keep_daily: 7,
+        }
+    }
+}
+
+impl BackupSettings {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn directory(&self) -> Result<PathBuf> {
+        match &self.directory {
+            Some(directory) => Ok(directory.clone()),
+            None => Config::default_path().map(|path| path.join("backups"))
+                                          .ok_or_else(|| anyhow!("Failed to get default backup directory")),
+        }
+    }
+}
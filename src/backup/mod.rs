@@ -0,0 +1,8 @@
+mod backup_settings;
+pub use backup_settings::BackupSettings;
+
+mod retention;
+pub use retention::{list, prune};
+
+mod snapshot;
+pub use snapshot::Snapshot;
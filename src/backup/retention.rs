@@ -0,0 +1,89 @@
+use crate::backup::{BackupSettings, Snapshot};
+use anyhow::Result;
+use chrono::{DateTime, Local, TimeZone, Utc};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Picks the snapshots that fall outside `settings`: everything but the `keep_last` newest
+/// and the newest of each of the `keep_daily` most recent days with a snapshot, with days
+/// counted in `timezone`. The newest snapshot is always kept.
+pub fn expired<Tz: TimeZone>(taken: &[DateTime<Utc>], settings: &BackupSettings, timezone: &Tz) -> Vec<DateTime<Utc>> {
+    let mut newest_first = taken.to_vec();
+    newest_first.sort_by(|a, b| b.cmp(a));
+    newest_first.dedup();
+
+    let mut kept: HashSet<DateTime<Utc>> = newest_first.iter().take(settings.keep_last.max(1)).copied().collect();
+    let mut days = HashSet::new();
+    for taken_at in newest_first.iter() {
+        if days.len() == settings.keep_daily {
+            break;
+        }
+        if days.insert(taken_at.with_timezone(timezone).date_naive()) {
+            kept.insert(*taken_at);
+        }
+    }
+
+    newest_first.into_iter().filter(|taken_at| !kept.contains(taken_at)).collect()
+}
+
+/// Deletes the snapshots in `directory` that fall outside `settings` and returns their
+/// paths. Files that are not named like snapshots are left alone.
+pub fn prune(directory: &Path, settings: &BackupSettings) -> Result<Vec<PathBuf>> {
+    let snapshots = list(directory)?;
+    let taken: Vec<DateTime<Utc>> = snapshots.iter().map(|(taken_at, _)| *taken_at).collect();
+    let expired = expired(&taken, settings, &Local);
+
+    let mut removed = vec![];
+    for (taken_at, path) in snapshots {
+        if expired.contains(&taken_at) {
+            log::debug!("Removing expired backup {}", path.display());
+            std::fs::remove_file(&path)?;
+            removed.push(path);
+        }
+    }
+    Ok(removed)
+}
+
+/// Every snapshot in `directory` with when it was taken, oldest first.
+pub fn list(directory: &Path) -> Result<Vec<(DateTime<Utc>, PathBuf)>> {
+    if !std::fs::exists(directory)? {
+        return Ok(vec![]);
+    }
+
+    let mut snapshots = vec![];
+    for entry in std::fs::read_dir(directory)? {
+        let entry = entry?;
+        if let Some(taken_at) = entry.file_name().to_str().and_then(Snapshot::taken_at) {
+            snapshots.push((taken_at, entry.path()));
+        }
+    }
+    snapshots.sort();
+    Ok(snapshots)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::expired;
+    use crate::backup::BackupSettings;
+    use chrono::{DateTime, TimeZone, Utc};
+
+    fn at(day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, day, hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn the_newest_few_and_one_a_day_are_kept() {
+        let taken = [at(10, 9), at(10, 18), at(12, 9), at(13, 9), at(13, 12), at(15, 9), at(15, 12), at(15, 18)];
+        let settings = BackupSettings { keep_last: 2, keep_daily: 3, ..BackupSettings::default() };
+
+        // 15th at 18:00 and 12:00 are the newest two, then one a day for the 15th, 13th and 12th.
+        assert_eq!(expired(&taken, &settings, &Utc), [at(15, 9), at(13, 9), at(10, 18), at(10, 9)]);
+    }
+
+    #[test]
+    fn the_newest_snapshot_is_never_expired() {
+        let settings = BackupSettings { keep_last: 0, keep_daily: 0, ..BackupSettings::default() };
+
+        assert_eq!(expired(&[at(1, 9), at(2, 9)], &settings, &Utc), [at(1, 9)]);
+    }
+}
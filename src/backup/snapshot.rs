@@ -0,0 +1,214 @@
+use crate::error::BackupChecksumError;
+use crate::persistence::{self, Repository};
+use crate::projects::Project;
+use crate::tasks::Task;
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::OpenOptions;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+const FORMAT: &str = "todo-backup";
+const VERSION: u32 = 1;
+
+const FILE_PREFIX: &str = "todo-";
+const FILE_SUFFIX: &str = ".backup.gz";
+const FILE_TIMESTAMP: &str = "%Y%m%dT%H%M%S%.3fZ";
+
+/// The first line of a snapshot, describing the line after it.
+#[derive(Debug, Serialize, Deserialize)]
+struct Header {
+    format: String,
+    version: u32,
+    created_at: DateTime<Utc>,
+    tasks: usize,
+    projects: usize,
+    /// The SHA-256 of the second line, in hex.
+    sha256: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Contents {
+    projects: Vec<Project>,
+    tasks: Vec<Task>,
+}
+
+/// Everything in a store at one point in time, as written by `todo backup`.
+///
+/// On disk a snapshot is gzipped text of two lines of JSON: a header with a checksum, and
+/// the projects and tasks in the same form the JSON file backend uses, so a snapshot does
+/// not depend on which backend it came from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub created_at: DateTime<Utc>,
+    pub tasks: Vec<Task>,
+    pub projects: Vec<Project>,
+}
+
+impl Snapshot {
+    /// Reads everything out of a repository, the trash included.
+    pub async fn take(repository: &(dyn Repository + Sync)) -> Result<Self> {
+        Ok(Self {
+            created_at: Utc::now(),
+            tasks: persistence::all_tasks(repository).await?,
+            projects: repository.get_projects().await?,
+        })
+    }
+
+    /// The name a snapshot is written under, which sorts in the order they were taken.
+    pub fn file_name(&self) -> String {
+        format!("{}{}{}", FILE_PREFIX, self.created_at.format(FILE_TIMESTAMP), FILE_SUFFIX)
+    }
+
+    /// When the snapshot in a file was taken, or `None` if it is not named like a snapshot.
+    pub fn taken_at(file_name: &str) -> Option<DateTime<Utc>> {
+        let timestamp = file_name.strip_prefix(FILE_PREFIX)?.strip_suffix(FILE_SUFFIX)?;
+        NaiveDateTime::parse_from_str(timestamp, FILE_TIMESTAMP).ok().map(|taken_at| taken_at.and_utc())
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        let contents = serde_json::to_string(&Contents {
+            projects: self.projects.clone(),
+            tasks: self.tasks.clone(),
+        })?;
+        let header = serde_json::to_string(&Header {
+            format: FORMAT.into(),
+            version: VERSION,
+            created_at: self.created_at,
+            tasks: self.tasks.len(),
+            projects: self.projects.len(),
+            sha256: format!("{:x}", Sha256::digest(contents.as_bytes())),
+        })?;
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        writeln!(encoder, "{}", header)?;
+        writeln!(encoder, "{}", contents)?;
+        Ok(encoder.finish()?)
+    }
+
+    /// Reads a snapshot back, refusing it if it is not one or its checksum does not match.
+    /// `path` is only used in errors.
+    pub fn decode(bytes: &[u8], path: &Path) -> Result<Self> {
+        let mut text = String::new();
+        GzDecoder::new(bytes).read_to_string(&mut text)
+                             .with_context(|| format!("{} is not a todo backup", path.display()))?;
+
+        let (header, contents) = text.split_once('\n')
+                                     .ok_or_else(|| anyhow!("{} is not a todo backup", path.display()))?;
+        let header: Header = serde_json::from_str(header)
+            .with_context(|| format!("{} is not a todo backup", path.display()))?;
+        if header.format != FORMAT {
+            bail!("{} is not a todo backup", path.display());
+        }
+        if header.version > VERSION {
+            bail!("{} was written by a newer version of todo (backup format {}, this version reads up to {})",
+                  path.display(), header.version, VERSION);
+        }
+
+        let contents = contents.strip_suffix('\n').unwrap_or(contents);
+        let found = format!("{:x}", Sha256::digest(contents.as_bytes()));
+        if found != header.sha256 {
+            return Err(BackupChecksumError {
+                path: path.into(),
+                expected: header.sha256,
+                found,
+            }.into());
+        }
+
+        let contents: Contents = serde_json::from_str(contents)?;
+        if contents.tasks.len() != header.tasks || contents.projects.len() != header.projects {
+            bail!("{} should hold {} task(s) and {} project(s) but holds {} and {}", path.display(),
+                  header.tasks, header.projects, contents.tasks.len(), contents.projects.len());
+        }
+
+        Ok(Self {
+            created_at: header.created_at,
+            tasks: contents.tasks,
+            projects: contents.projects,
+        })
+    }
+
+    /// Writes the snapshot into `directory`, creating it if needed, and returns its path.
+    /// The file only appears under its final name once it has been written in full.
+    pub fn write(&self, directory: &Path) -> Result<PathBuf> {
+        std::fs::create_dir_all(directory)?;
+        let path = directory.join(self.file_name());
+        if std::fs::exists(&path)? {
+            bail!("A backup named {} already exists", path.display());
+        }
+
+        let partial_path = directory.join(format!("{}.partial", self.file_name()));
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&partial_path)?;
+        file.write_all(&self.encode()?)?;
+        file.sync_all()?;
+        std::fs::rename(&partial_path, &path)?;
+
+        Ok(path)
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let bytes = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::decode(&bytes, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Snapshot;
+    use crate::error::BackupChecksumError;
+    use crate::projects::{NewProject, Project};
+    use crate::tasks::{NewTask, Task};
+    use chrono::{TimeZone, Utc};
+    use flate2::read::GzDecoder;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::{Read, Write};
+    use std::path::Path;
+
+    fn snapshot() -> Snapshot {
+        let created_at = Utc.with_ymd_and_hms(2026, 10, 18, 9, 30, 0).unwrap();
+        let task = NewTask {
+            name: "Fix the door".into(),
+            project_id: Some(1),
+            tags: vec!["home".into()],
+            ..NewTask::default()
+        };
+        Snapshot {
+            created_at,
+            tasks: vec![Task::from_new_task(1, task, created_at)],
+            projects: vec![Project::from_new_project(1, NewProject { name: "house".into(), ..NewProject::default() })],
+        }
+    }
+
+    #[test]
+    fn snapshots_survive_a_round_trip() {
+        let snapshot = snapshot();
+
+        assert_eq!(snapshot.file_name(), "todo-20261018T093000.000Z.backup.gz");
+        assert_eq!(Snapshot::taken_at(&snapshot.file_name()), Some(snapshot.created_at));
+        assert_eq!(Snapshot::taken_at("notes.txt"), None);
+        assert_eq!(Snapshot::decode(&snapshot.encode().unwrap(), Path::new("backup")).unwrap(), snapshot);
+    }
+
+    #[test]
+    fn tampered_snapshots_are_refused() {
+        let mut text = String::new();
+        GzDecoder::new(&snapshot().encode().unwrap()[..]).read_to_string(&mut text).unwrap();
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(text.replace("Fix the door", "Fix the gate").as_bytes()).unwrap();
+
+        let error = Snapshot::decode(&encoder.finish().unwrap(), Path::new("backup")).unwrap_err();
+
+        assert!(error.downcast_ref::<BackupChecksumError>().is_some(), "failed with {:?}", error);
+        assert!(Snapshot::decode(b"not a backup", Path::new("backup")).unwrap_err().to_string().contains("is not a todo backup"));
+    }
+}
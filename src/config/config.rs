@@ -1,5 +1,9 @@
+use crate::backup::BackupSettings;
 use crate::config::storage_format::StorageFormat;
+use crate::config::TrashSettings;
 use crate::error::NoConfigError;
+use crate::output::Column;
+use crate::tasks::StatusTransitions;
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
@@ -12,6 +16,17 @@ use url::Url;
 #[derive(Debug, Hash, Deserialize, Serialize, Clone)]
 pub struct Config {
     storage: Url,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    columns: Option<Vec<Column>>,
+    #[serde(default, skip_serializing_if = "StatusTransitions::is_default")]
+    transitions: StatusTransitions,
+    #[serde(default, skip_serializing_if = "BackupSettings::is_default")]
+    backups: BackupSettings,
+    #[serde(default, skip_serializing_if = "TrashSettings::is_default")]
+    trash: TrashSettings,
+    /// Set by `--dry-run` rather than read from the file.
+    #[serde(skip)]
+    dry_run: bool,
 }
 
 impl Config {
@@ -25,6 +40,11 @@ impl Config {
 
         Ok(Config {
             storage: Url::parse(&storage_url)?,
+            columns: None,
+            transitions: StatusTransitions::default(),
+            backups: BackupSettings::default(),
+            trash: TrashSettings::default(),
+            dry_run: false,
         })
     }
     pub fn default_path() -> Option<PathBuf> {
@@ -55,9 +75,27 @@ impl Config {
         Err(NoConfigError(path.into()).into())
     }
 
+    /// The config file at `path` with its storage replaced, keeping every other setting, or a
+    /// new config with just the storage if there is no file yet.
+    pub fn from_path_with_storage(path: impl AsRef<Path>, storage: Url) -> Result<Self> {
+        match Self::from_path(path) {
+            Ok(mut config) => {
+                config.storage = storage;
+                Ok(config)
+            }
+            Err(e) if e.is::<NoConfigError>() => Ok(Self::new_with_url(storage)),
+            Err(e) => Err(e),
+        }
+    }
+
     pub fn new_with_url(storage: Url) -> Self {
         Self {
             storage,
+            columns: None,
+            transitions: StatusTransitions::default(),
+            backups: BackupSettings::default(),
+            trash: TrashSettings::default(),
+            dry_run: false,
         }
     }
 
@@ -65,6 +103,11 @@ impl Config {
     pub fn new(storage: &str) -> Result<Self> {
         Ok(Self {
             storage: Url::parse(storage)?,
+            columns: None,
+            transitions: StatusTransitions::default(),
+            backups: BackupSettings::default(),
+            trash: TrashSettings::default(),
+            dry_run: false,
         })
     }
 
@@ -90,6 +133,7 @@ impl Config {
             "postgresql" => Ok(StorageFormat::Postgres),
             "sqlite" => Ok(StorageFormat::Sqlite),
             "file" => Ok(StorageFormat::LocalStorage),
+            "mem" => Ok(StorageFormat::Memory),
             _ => Err(anyhow!("Unsupported format")),
         }
     }
@@ -97,11 +141,62 @@ impl Config {
     pub fn storage(&self) -> &Url {
         &self.storage
     }
+
+    /// The columns `todo list` shows unless told otherwise.
+    pub fn columns(&self) -> Vec<Column> {
+        self.columns.clone().unwrap_or_else(|| Column::DEFAULT.to_vec())
+    }
+
+    pub fn transitions(&self) -> &StatusTransitions {
+        &self.transitions
+    }
+
+    pub fn backups(&self) -> &BackupSettings {
+        &self.backups
+    }
+
+    pub fn trash(&self) -> &TrashSettings {
+        &self.trash
+    }
+
+    /// Whether changes should be shown rather than saved.
+    pub fn dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
 }
 
 impl Display for Config {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "Storage: {}", self.storage)
+        writeln!(f, "Storage: {}", self.storage)?;
+
+        let columns: Vec<&str> = self.columns().iter().map(Column::as_str).collect();
+        writeln!(f, "Columns: {}", columns.join(", "))?;
+
+        if self.transitions.is_default() {
+            writeln!(f, "Transitions: default")?;
+        } else {
+            writeln!(f, "Transitions:")?;
+            for (from, to) in self.transitions.configured() {
+                let to: Vec<&str> = to.iter().map(|status| status.as_str()).collect();
+                writeln!(f, "  {} -> [{}]", from.as_str(), to.join(", "))?;
+            }
+        }
+
+        match self.backups.directory() {
+            Ok(directory) => write!(f, "Backups: {}", directory.display())?,
+            Err(_) => write!(f, "Backups: default directory")?,
+        }
+        writeln!(f, ", keeping the newest {} and the newest of each of the last {} day(s)",
+                 self.backups.keep_last, self.backups.keep_daily)?;
+
+        match self.trash.keep_days {
+            0 => writeln!(f, "Trash: kept until emptied"),
+            days => writeln!(f, "Trash: purged after {} day(s)", days),
+        }
     }
 }
 
@@ -112,4 +207,35 @@ mod test {
         let serialized_config = super::ser_fmt::to_string(&config).unwrap();
         println!("{}", serialized_config);
     }
+
+    #[test]
+    fn changing_the_storage_keeps_every_other_setting() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("config.toml");
+        std::fs::write(&path, "storage = \"file:///home/user/tasks.ron\"\n\
+                               columns = [\"id\", \"name\"]\n\
+                               [transitions]\n\
+                               done = []\n\
+                               [backups]\n\
+                               keep_last = 3\n\
+                               keep_daily = 2\n\
+                               [trash]\n\
+                               keep_days = 365\n").unwrap();
+        let before = std::fs::read_to_string(&path).unwrap();
+
+        let storage = url::Url::parse("sqlite:///home/user/tasks.db").unwrap();
+        super::Config::from_path_with_storage(&path, storage.clone()).unwrap().write_to_file(&path).unwrap();
+
+        let config = super::Config::from_path(&path).unwrap();
+        assert_eq!(config.storage(), &storage);
+        let original = super::ser_fmt::from_str::<super::Config>(&before).unwrap();
+        assert_eq!(config.columns(), original.columns());
+        assert_eq!(config.transitions(), original.transitions());
+        assert_eq!((config.backups().keep_last, config.backups().keep_daily), (3, 2));
+        assert_eq!(config.trash().keep_days, 365);
+        assert!(!config.transitions().is_default());
+
+        let missing = directory.path().join("missing.toml");
+        assert_eq!(super::Config::from_path_with_storage(&missing, storage).unwrap().trash().keep_days, 30);
+    }
 }
@@ -1,5 +1,8 @@
+#[allow(clippy::module_inception)]
 mod config;
 mod storage_format;
+mod trash_settings;
 
 pub use config::Config;
 pub use storage_format::StorageFormat;
+pub use trash_settings::TrashSettings;
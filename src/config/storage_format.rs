@@ -1,5 +1,6 @@
 pub enum StorageFormat {
     LocalStorage,
+    Memory,
     Postgres,
     Sqlite,
 }
\ No newline at end of file
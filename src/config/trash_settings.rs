@@ -0,0 +1,34 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+/// How long removed tasks stay in the trash before they are deleted for good.
+///
+/// Read from the `[trash]` table of the config file:
+///
+/// ```toml
+/// [trash]
+/// keep_days = 30
+/// ```
+#[derive(Debug, Hash, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrashSettings {
+    /// 0 keeps removed tasks until `todo trash empty` is run.
+    pub keep_days: u32,
+}
+
+impl Default for TrashSettings {
+    fn default() -> Self {
+        Self { keep_days: 30 }
+    }
+}
+
+impl TrashSettings {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Tasks moved to the trash before this are purged, or `None` if they are kept forever.
+    pub fn purge_before(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        (self.keep_days > 0).then(|| now - Duration::days(self.keep_days as i64))
+    }
+}
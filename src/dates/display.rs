@@ -0,0 +1,12 @@
+use chrono::{DateTime, Local, Timelike, Utc};
+
+/// Formats a timestamp in local time, leaving out the time of day when it is midnight.
+pub fn format_date_time(date_time: &DateTime<Utc>) -> String {
+    let local = date_time.with_timezone(&Local);
+
+    if local.hour() == 0 && local.minute() == 0 {
+        local.format("%Y-%m-%d").to_string()
+    } else {
+        local.format("%Y-%m-%d %H:%M").to_string()
+    }
+}
@@ -0,0 +1,8 @@
+mod display;
+pub use display::format_date_time;
+
+mod parser;
+pub use parser::{
+    parse_date_expression, parse_date_time, parse_due_date_expression, parse_due_date_time, parse_weekday,
+    start_of_local_day,
+};
@@ -0,0 +1,327 @@
+use anyhow::{anyhow, bail, Result};
+use chrono::{
+    DateTime, Datelike, Days, Duration, Local, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc,
+    Weekday,
+};
+
+const DATE_TIME_FORMATS: [&str; 2] = ["%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M"];
+
+/// Parses a date expression given on the command line, relative to the current local time.
+pub fn parse_date_time(input: &str) -> Result<DateTime<Utc>> {
+    parse_date_expression(input, Local::now()).map(|date_time| date_time.with_timezone(&Utc))
+}
+
+/// Parses a due date given on the command line, relative to the current local time.
+pub fn parse_due_date_time(input: &str) -> Result<DateTime<Utc>> {
+    parse_due_date_expression(input, Local::now()).map(|date_time| date_time.with_timezone(&Utc))
+}
+
+/// The local midnight that started the day `now` falls on. Due dates saved before they
+/// lasted until the end of their day point at it.
+pub fn start_of_local_day(now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    at_midnight(&now.with_timezone(&Local)).ok().map(|midnight| midnight.with_timezone(&Utc))
+}
+
+/// Which end of a day an expression naming only a day resolves to.
+#[derive(Clone, Copy)]
+enum DayBoundary {
+    Start,
+    End,
+}
+
+/// Parses a date expression relative to `now`, in the time zone of `now`.
+///
+/// Understands:
+/// - RFC 3339 timestamps and `YYYY-MM-DD [HH:MM]`
+/// - `now`, `today`, `tomorrow`, `yesterday`
+/// - `eod`, `eow`, `eom`, `eoy` for the last second of the day, week, month or year
+/// - weekday names, optionally after `next`, meaning the next one after today
+/// - `next week`, `next month` and `next year`, meaning the start of that period
+/// - offsets such as `in 3 days`, `2 weeks ago`, `+3d` and `-1w`
+///
+/// Expressions naming a day can end in a time of day: `tomorrow 17:00`, `friday at 9am`.
+/// Days without a time of day start at midnight.
+pub fn parse_date_expression<Tz: TimeZone>(input: &str, now: DateTime<Tz>) -> Result<DateTime<Tz>> {
+    parse_expression(input, now, DayBoundary::Start)
+}
+
+/// Parses a date expression like [`parse_date_expression`], except that days without a
+/// time of day last until their final second, so a task due today is not overdue yet.
+pub fn parse_due_date_expression<Tz: TimeZone>(input: &str, now: DateTime<Tz>) -> Result<DateTime<Tz>> {
+    parse_expression(input, now, DayBoundary::End)
+}
+
+fn parse_expression<Tz: TimeZone>(input: &str, now: DateTime<Tz>, boundary: DayBoundary) -> Result<DateTime<Tz>> {
+    let input = input.trim();
+
+    if let Ok(date_time) = DateTime::parse_from_rfc3339(input) {
+        return Ok(date_time.with_timezone(&now.timezone()));
+    }
+
+    if let Some(naive) = DATE_TIME_FORMATS.iter()
+                                          .find_map(|format| NaiveDateTime::parse_from_str(input, format).ok()) {
+        return to_zone(&now, naive);
+    }
+
+    let lowercase = input.to_lowercase();
+    let words: Vec<&str> = lowercase.split_whitespace().collect();
+    if words.is_empty() {
+        bail!("Empty date expression");
+    }
+
+    // A trailing time of day, with an optional "at" in front of it.
+    let (words, time) = match words.split_last() {
+        Some((last, rest)) if parse_time(last).is_some() => {
+            let rest = rest.strip_suffix(&["at"]).unwrap_or(rest);
+            (rest, parse_time(last))
+        }
+        _ => (words.as_slice(), None),
+    };
+
+    let result = if words.is_empty() {
+        // Just a time of day, meaning today.
+        at_midnight(&now)?
+    } else {
+        parse_words(words, &now, boundary).ok_or_else(|| anyhow!("Unrecognised date expression \"{}\"", input))??
+    };
+
+    match time {
+        Some(time) => to_zone(&now, result.date_naive().and_time(time)),
+        None => Ok(result),
+    }
+}
+
+/// Returns `None` when the words are not a date expression at all.
+fn parse_words<Tz: TimeZone>(words: &[&str], now: &DateTime<Tz>, boundary: DayBoundary) -> Option<Result<DateTime<Tz>>> {
+    let today = now.date_naive();
+    let day = |date: NaiveDate| match boundary {
+        DayBoundary::Start => day_start(now, date),
+        DayBoundary::End => day_end(now, date),
+    };
+
+    let result = match words {
+        ["now"] => Ok(now.clone()),
+        ["today"] => day(today),
+        ["tomorrow"] => day(today + Days::new(1)),
+        ["yesterday"] => day(today - Days::new(1)),
+        ["eod"] => day_end(now, today),
+        ["eow"] => {
+            let days_left = 6 - today.weekday().num_days_from_monday();
+            day_end(now, today + Days::new(days_left.into()))
+        }
+        ["eom"] => {
+            let next_month = today.with_day(1)? + Months::new(1);
+            day_end(now, next_month - Days::new(1))
+        }
+        ["eoy"] => day_end(now, NaiveDate::from_ymd_opt(today.year(), 12, 31)?),
+        ["next", "week"] => {
+            let days_until_monday = 7 - today.weekday().num_days_from_monday();
+            day(today + Days::new(days_until_monday.into()))
+        }
+        ["next", "month"] => day(today.with_day(1)? + Months::new(1)),
+        ["next", "year"] => day(NaiveDate::from_ymd_opt(today.year() + 1, 1, 1)?),
+        ["next", weekday] | [weekday] if parse_weekday(weekday).is_some() => {
+            let weekday = parse_weekday(weekday)?;
+            let days_ahead = (weekday.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7;
+            let days_ahead = if days_ahead == 0 { 7 } else { days_ahead };
+            day(today + Days::new(days_ahead.into()))
+        }
+        [date] if NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok() => {
+            day(NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?)
+        }
+        ["in", amount, unit] => offset(now, amount.parse().ok()?, unit),
+        [amount, unit, "ago"] => offset(now, -amount.parse::<i64>().ok()?, unit),
+        [signed, unit] if signed.starts_with(['+', '-']) => offset(now, signed.parse().ok()?, unit),
+        [signed] if signed.starts_with(['+', '-']) => {
+            let split = signed.find(|c: char| c.is_alphabetic())?;
+            let (amount, unit) = signed.split_at(split);
+            offset(now, amount.parse().ok()?, unit)
+        }
+        _ => return None,
+    };
+
+    Some(result)
+}
+
+fn offset<Tz: TimeZone>(now: &DateTime<Tz>, amount: i64, unit: &str) -> Result<DateTime<Tz>> {
+    let shifted = match unit {
+        "min" | "mins" | "minute" | "minutes" => now.clone().checked_add_signed(Duration::minutes(amount)),
+        "h" | "hr" | "hrs" | "hour" | "hours" => now.clone().checked_add_signed(Duration::hours(amount)),
+        "d" | "day" | "days" => now.clone().checked_add_signed(Duration::days(amount)),
+        "w" | "wk" | "wks" | "week" | "weeks" => now.clone().checked_add_signed(Duration::weeks(amount)),
+        "mo" | "month" | "months" => add_months(now, amount),
+        "y" | "yr" | "yrs" | "year" | "years" => add_months(now, amount.checked_mul(12).unwrap_or(i64::MAX)),
+        _ => bail!("Unrecognised time unit \"{}\"", unit),
+    };
+
+    shifted.ok_or_else(|| anyhow!("Date is out of range"))
+}
+
+fn add_months<Tz: TimeZone>(now: &DateTime<Tz>, amount: i64) -> Option<DateTime<Tz>> {
+    let months = Months::new(u32::try_from(amount.unsigned_abs()).ok()?);
+    if amount < 0 {
+        now.clone().checked_sub_months(months)
+    } else {
+        now.clone().checked_add_months(months)
+    }
+}
+
+pub fn parse_weekday(word: &str) -> Option<Weekday> {
+    match word {
+        "monday" | "mon" => Some(Weekday::Mon),
+        "tuesday" | "tue" | "tues" => Some(Weekday::Tue),
+        "wednesday" | "wed" => Some(Weekday::Wed),
+        "thursday" | "thu" | "thur" | "thurs" => Some(Weekday::Thu),
+        "friday" | "fri" => Some(Weekday::Fri),
+        "saturday" | "sat" => Some(Weekday::Sat),
+        "sunday" | "sun" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+/// Parses `17:00`, `5pm` or `5:30pm`.
+fn parse_time(word: &str) -> Option<NaiveTime> {
+    if let Ok(time) = NaiveTime::parse_from_str(word, "%H:%M") {
+        return Some(time);
+    }
+
+    let (clock, afternoon) = if let Some(clock) = word.strip_suffix("am") {
+        (clock, false)
+    } else {
+        (word.strip_suffix("pm")?, true)
+    };
+
+    let (hour, minute) = match clock.split_once(':') {
+        Some((hour, minute)) => (hour.parse::<u32>().ok()?, minute.parse::<u32>().ok()?),
+        None => (clock.parse::<u32>().ok()?, 0),
+    };
+
+    if !(1..=12).contains(&hour) {
+        return None;
+    }
+
+    let hour = (hour % 12) + if afternoon { 12 } else { 0 };
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+fn at_midnight<Tz: TimeZone>(now: &DateTime<Tz>) -> Result<DateTime<Tz>> {
+    day_start(now, now.date_naive())
+}
+
+fn day_start<Tz: TimeZone>(now: &DateTime<Tz>, date: NaiveDate) -> Result<DateTime<Tz>> {
+    to_zone(now, date.and_time(NaiveTime::MIN))
+}
+
+fn day_end<Tz: TimeZone>(now: &DateTime<Tz>, date: NaiveDate) -> Result<DateTime<Tz>> {
+    let last_second = NaiveTime::from_hms_opt(23, 59, 59).unwrap_or(NaiveTime::MIN);
+    to_zone(now, date.and_time(last_second))
+}
+
+fn to_zone<Tz: TimeZone>(now: &DateTime<Tz>, naive: NaiveDateTime) -> Result<DateTime<Tz>> {
+    now.timezone()
+       .from_local_datetime(&naive)
+       .earliest()
+       .ok_or_else(|| anyhow!("{} does not exist in the local time zone", naive))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_date_expression, parse_due_date_expression};
+    use chrono::{DateTime, TimeZone, Utc};
+
+    /// Wednesday 14 October 2026, half past ten in the morning.
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 14, 10, 30, 0).unwrap()
+    }
+
+    #[test]
+    fn expressions_resolve_against_a_fixed_now() {
+        let cases = [
+            ("now", "2026-10-14 10:30:00"),
+            ("today", "2026-10-14 00:00:00"),
+            ("Tomorrow", "2026-10-15 00:00:00"),
+            ("yesterday", "2026-10-13 00:00:00"),
+            ("tomorrow 17:00", "2026-10-15 17:00:00"),
+            ("tomorrow at 5pm", "2026-10-15 17:00:00"),
+            ("17:45", "2026-10-14 17:45:00"),
+            ("at 9:15am", "2026-10-14 09:15:00"),
+            ("12am", "2026-10-14 00:00:00"),
+            ("12pm", "2026-10-14 12:00:00"),
+            ("eod", "2026-10-14 23:59:59"),
+            ("eow", "2026-10-18 23:59:59"),
+            ("eom", "2026-10-31 23:59:59"),
+            ("eoy", "2026-12-31 23:59:59"),
+            ("friday", "2026-10-16 00:00:00"),
+            ("fri", "2026-10-16 00:00:00"),
+            ("next friday", "2026-10-16 00:00:00"),
+            ("next friday at 9am", "2026-10-16 09:00:00"),
+            ("wednesday", "2026-10-21 00:00:00"),
+            ("monday", "2026-10-19 00:00:00"),
+            ("next week", "2026-10-19 00:00:00"),
+            ("next month", "2026-11-01 00:00:00"),
+            ("next year", "2027-01-01 00:00:00"),
+            ("in 3 days", "2026-10-17 10:30:00"),
+            ("in 1 day", "2026-10-15 10:30:00"),
+            ("in 2 hours", "2026-10-14 12:30:00"),
+            ("in 45 minutes", "2026-10-14 11:15:00"),
+            ("in 2 weeks", "2026-10-28 10:30:00"),
+            ("in 1 month", "2026-11-14 10:30:00"),
+            ("in 1 year", "2027-10-14 10:30:00"),
+            ("3 days ago", "2026-10-11 10:30:00"),
+            ("+3d", "2026-10-17 10:30:00"),
+            ("-1w", "2026-10-07 10:30:00"),
+            ("+2h", "2026-10-14 12:30:00"),
+            ("+30min", "2026-10-14 11:00:00"),
+            ("+1mo", "2026-11-14 10:30:00"),
+            ("+4 days", "2026-10-18 10:30:00"),
+            ("2026-11-01", "2026-11-01 00:00:00"),
+            ("2026-11-01 17:00", "2026-11-01 17:00:00"),
+            ("2026-11-01T17:00", "2026-11-01 17:00:00"),
+            ("2026-11-01 at 8am", "2026-11-01 08:00:00"),
+            ("2026-11-01T17:00:00+02:00", "2026-11-01 15:00:00"),
+        ];
+
+        for (input, expected) in cases {
+            let parsed = parse_date_expression(input, now())
+                .unwrap_or_else(|e| panic!("\"{}\" failed to parse: {}", input, e));
+            assert_eq!(parsed.format("%Y-%m-%d %H:%M:%S").to_string(), expected, "for \"{}\"", input);
+        }
+    }
+
+    #[test]
+    fn due_days_without_a_time_last_until_their_end() {
+        let cases = [
+            ("today", "2026-10-14 23:59:59"),
+            ("tomorrow", "2026-10-15 23:59:59"),
+            ("friday", "2026-10-16 23:59:59"),
+            ("next week", "2026-10-19 23:59:59"),
+            ("2026-11-01", "2026-11-01 23:59:59"),
+            ("friday at 9am", "2026-10-16 09:00:00"),
+            ("2026-11-01 17:00", "2026-11-01 17:00:00"),
+            ("in 3 days", "2026-10-17 10:30:00"),
+            ("now", "2026-10-14 10:30:00"),
+        ];
+
+        for (input, expected) in cases {
+            let parsed = parse_due_date_expression(input, now())
+                .unwrap_or_else(|e| panic!("\"{}\" failed to parse: {}", input, e));
+            assert_eq!(parsed.format("%Y-%m-%d %H:%M:%S").to_string(), expected, "for \"{}\"", input);
+        }
+    }
+
+    #[test]
+    fn month_offsets_clamp_to_the_end_of_the_month() {
+        let now = Utc.with_ymd_and_hms(2026, 1, 31, 0, 0, 0).unwrap();
+        let parsed = parse_date_expression("in 1 month", now).unwrap();
+        assert_eq!(parsed, Utc.with_ymd_and_hms(2026, 2, 28, 0, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn nonsense_is_rejected() {
+        let cases = ["", "someday", "next fortnight", "in three days", "+3 parsecs", "13pm", "25:00", "2026-13-01"];
+
+        for input in cases {
+            assert!(parse_date_expression(input, now()).is_err(), "\"{}\" should not parse", input);
+        }
+    }
+}
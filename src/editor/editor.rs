@@ -0,0 +1,116 @@
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const ERROR_PREFIX: &str = "# Error: ";
+
+static EDITS: AtomicUsize = AtomicUsize::new(0);
+
+/// The command to edit files with: `$VISUAL`, then `$EDITOR`, then `vi`.
+pub fn editor_command() -> String {
+    std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .ok()
+        .filter(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string())
+}
+
+/// Opens `contents` in `editor` until `parse` accepts what was saved.
+///
+/// Rejected contents are opened again with the error added as comments at the top.
+/// Returns `None` if the file is left with nothing but comments, which cancels the edit.
+pub fn edit_until_valid<T>(editor: &str, contents: &str, parse: impl Fn(&str) -> Result<T>) -> Result<Option<T>> {
+    let edit = EDITS.fetch_add(1, Ordering::Relaxed);
+    let path = std::env::temp_dir().join(format!("todo-edit-{}-{}.toml", std::process::id(), edit));
+    log::debug!("Editing {} with {}", path.to_string_lossy(), editor);
+
+    let result = edit_loop(editor, &path, contents, parse);
+    if let Err(e) = fs::remove_file(&path) {
+        log::warn!("Failed to remove {}: {}", path.to_string_lossy(), e);
+    }
+    result
+}
+
+fn edit_loop<T>(editor: &str, path: &Path, contents: &str, parse: impl Fn(&str) -> Result<T>) -> Result<Option<T>> {
+    let mut contents = contents.to_string();
+
+    loop {
+        fs::write(path, &contents)?;
+        run_editor(editor, path)?;
+        let edited = fs::read_to_string(path)?;
+
+        let is_empty = edited.lines()
+                             .map(str::trim)
+                             .all(|line| line.is_empty() || line.starts_with('#'));
+        if is_empty {
+            return Ok(None);
+        }
+
+        match parse(&edited) {
+            Ok(value) => return Ok(Some(value)),
+            Err(e) => {
+                log::debug!("Edited contents rejected: {:#}", e);
+                contents = with_error(&edited, &format!("{:#}", e));
+            }
+        }
+    }
+}
+
+fn run_editor(editor: &str, path: &Path) -> Result<()> {
+    let mut words = editor.split_whitespace();
+    let Some(program) = words.next() else {
+        bail!("No editor configured");
+    };
+
+    let status = Command::new(program)
+        .args(words)
+        .arg(path)
+        .status()
+        .with_context(|| format!("Failed to start editor \"{}\"", editor))?;
+
+    if !status.success() {
+        bail!("Editor \"{}\" exited with {}", editor, status);
+    }
+    Ok(())
+}
+
+/// Replaces any previous error at the top of `contents` with `error`.
+fn with_error(contents: &str, error: &str) -> String {
+    let body: Vec<&str> = contents.lines()
+                                  .skip_while(|line| line.starts_with(ERROR_PREFIX))
+                                  .collect();
+    let errors: Vec<String> = error.lines().map(|line| format!("{}{}", ERROR_PREFIX, line)).collect();
+
+    format!("{}\n{}\n", errors.join("\n"), body.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{edit_until_valid, with_error};
+
+    #[test]
+    fn unchanged_contents_are_parsed() {
+        let edited = edit_until_valid("true", "name = \"x\"\n", |contents| Ok(contents.trim().to_string())).unwrap();
+        assert_eq!(edited.as_deref(), Some("name = \"x\""));
+    }
+
+    #[test]
+    fn emptying_the_file_cancels_the_edit() {
+        let edited = edit_until_valid("truncate -s 0", "name = \"x\"\n", |_| Ok(())).unwrap();
+        assert_eq!(edited, None);
+    }
+
+    #[test]
+    fn a_failing_editor_is_an_error() {
+        assert!(edit_until_valid("false", "name = \"x\"\n", |_| Ok(())).is_err());
+    }
+
+    #[test]
+    fn errors_replace_earlier_errors() {
+        let once = with_error("name = 1\n", "first");
+        let twice = with_error(&once, "second\nline");
+        assert_eq!(twice, "# Error: second\n# Error: line\nname = 1\n");
+    }
+}
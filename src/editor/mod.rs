@@ -0,0 +1,4 @@
+#[allow(clippy::module_inception)]
+mod editor;
+
+pub use editor::{edit_until_valid, editor_command};
@@ -0,0 +1,10 @@
+use std::path::PathBuf;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+#[error("Backup {path} is damaged, its checksum is {found} but should be {expected}")]
+pub struct BackupChecksumError {
+    pub path: PathBuf,
+    pub expected: String,
+    pub found: String,
+}
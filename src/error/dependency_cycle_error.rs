@@ -0,0 +1,15 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+#[error("That would create a dependency cycle: {}", describe(.cycle))]
+pub struct DependencyCycleError {
+    /// The tasks around the loop, starting and ending with the same task.
+    pub cycle: Vec<i32>,
+}
+
+fn describe(cycle: &[i32]) -> String {
+    cycle.iter()
+         .map(|id| format!("task {}", id))
+         .collect::<Vec<_>>()
+         .join(" waits for ")
+}
@@ -0,0 +1,9 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+#[error("Task(s) {} changed since `{command}` and would be overwritten", ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(", "))]
+pub struct JournalConflictError {
+    /// The command whose changes were being undone or redone.
+    pub command: String,
+    pub ids: Vec<i32>,
+}
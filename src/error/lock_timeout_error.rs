@@ -0,0 +1,10 @@
+use std::path::PathBuf;
+use std::time::Duration;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+#[error("Timed out after {waited:.1?} waiting for the lock on {path}, another todo process may be stuck")]
+pub struct LockTimeoutError {
+    pub path: PathBuf,
+    pub waited: Duration,
+}
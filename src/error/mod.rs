@@ -1,2 +1,32 @@
 mod no_config_error;
 pub use no_config_error::NoConfigError;
+
+mod schema_too_new_error;
+pub use schema_too_new_error::SchemaTooNewError;
+
+mod lock_timeout_error;
+pub use lock_timeout_error::LockTimeoutError;
+
+mod task_file_parse_error;
+pub use task_file_parse_error::TaskFileParseError;
+
+mod task_cycle_error;
+pub use task_cycle_error::TaskCycleError;
+
+mod dependency_cycle_error;
+pub use dependency_cycle_error::DependencyCycleError;
+
+mod status_transition_error;
+pub use status_transition_error::StatusTransitionError;
+
+mod query_parse_error;
+pub use query_parse_error::QueryParseError;
+
+mod task_not_found_error;
+pub use task_not_found_error::TaskNotFoundError;
+
+mod backup_checksum_error;
+pub use backup_checksum_error::BackupChecksumError;
+
+mod journal_conflict_error;
+pub use journal_conflict_error::JournalConflictError;
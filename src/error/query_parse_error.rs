@@ -0,0 +1,8 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+#[error("Invalid query at column {column}: {message}")]
+pub struct QueryParseError {
+    pub column: usize,
+    pub message: String,
+}
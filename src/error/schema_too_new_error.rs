@@ -0,0 +1,8 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+#[error("Database schema version {found} is newer than the latest version this build supports ({supported}), upgrade todo to use this database")]
+pub struct SchemaTooNewError {
+    pub found: i64,
+    pub supported: i64,
+}
@@ -0,0 +1,10 @@
+use crate::tasks::Status;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+#[error("Task {task} cannot go from {from} to {to}")]
+pub struct StatusTransitionError {
+    pub task: i32,
+    pub from: Status,
+    pub to: Status,
+}
@@ -0,0 +1,8 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+#[error("Task {task} cannot be a subtask of task {parent} without creating a cycle")]
+pub struct TaskCycleError {
+    pub task: i32,
+    pub parent: i32,
+}
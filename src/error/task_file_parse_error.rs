@@ -0,0 +1,11 @@
+use std::path::PathBuf;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+#[error("Failed to parse task file {path} at line {line}, column {column}: {message}")]
+pub struct TaskFileParseError {
+    pub path: PathBuf,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
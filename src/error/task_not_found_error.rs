@@ -0,0 +1,7 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+#[error("No task with id {id} found")]
+pub struct TaskNotFoundError {
+    pub id: i32,
+}
@@ -0,0 +1,117 @@
+use crate::journal::JournalEntry;
+use crate::tasks::Task;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+
+/// How many runs of `todo` the journal remembers; older batches are dropped as new ones
+/// are added.
+pub const KEPT_BATCHES: usize = 50;
+
+/// The journal entries made by one run of `todo`, which are undone and redone together.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Batch {
+    /// Oldest first.
+    pub entries: Vec<JournalEntry>,
+}
+
+impl Batch {
+    /// Groups journal entries into batches, oldest first.
+    pub fn group(mut entries: Vec<JournalEntry>) -> Vec<Self> {
+        entries.sort_by_key(|entry| entry.id);
+        let mut batches = BTreeMap::<i64, Vec<JournalEntry>>::new();
+        for entry in entries {
+            batches.entry(entry.batch).or_default().push(entry);
+        }
+
+        let mut batches: Vec<Self> = batches.into_values().map(|entries| Self { entries }).collect();
+        batches.sort_by_key(|batch| batch.entries[0].id);
+        batches
+    }
+
+    /// The batch `todo undo` would undo next: the newest one that has not been undone.
+    pub fn next_to_undo(batches: &[Self]) -> Option<&Self> {
+        batches.iter().rev().find(|batch| !batch.undone())
+    }
+
+    /// The batch `todo redo` would redo next: the one undone most recently.
+    pub fn next_to_redo(batches: &[Self]) -> Option<&Self> {
+        batches.iter().find(|batch| batch.undone())
+    }
+
+    pub fn command(&self) -> &str {
+        &self.entries[0].command
+    }
+
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.entries[0].created_at
+    }
+
+    pub fn undone(&self) -> bool {
+        self.entries.iter().all(|entry| entry.undone)
+    }
+
+    pub fn entry_ids(&self) -> Vec<i64> {
+        self.entries.iter().map(|entry| entry.id).collect()
+    }
+
+    /// Every task the batch changed, as it was before the first change. `None` means the
+    /// task did not exist yet.
+    pub fn before(&self) -> Vec<(i32, Option<Task>)> {
+        let mut states = BTreeMap::new();
+        for change in self.entries.iter().flat_map(|entry| entry.changes.iter()) {
+            states.entry(change.id).or_insert_with(|| change.before.clone());
+        }
+        states.into_iter().collect()
+    }
+
+    /// Every task the batch changed, as it was after the last change. `None` means the task
+    /// had been removed.
+    pub fn after(&self) -> Vec<(i32, Option<Task>)> {
+        let mut states = BTreeMap::new();
+        for change in self.entries.iter().flat_map(|entry| entry.changes.iter()) {
+            states.insert(change.id, change.after.clone());
+        }
+        states.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Batch;
+    use crate::journal::{JournalEntry, TaskChange};
+    use crate::tasks::{NewTask, Task};
+    use chrono::Utc;
+
+    fn task(id: i32, name: &str) -> Option<Task> {
+        Some(Task::from_new_task(id, NewTask { name: name.into(), ..NewTask::default() }, Utc::now()))
+    }
+
+    fn entry(id: i64, batch: i64, undone: bool, changes: Vec<TaskChange>) -> JournalEntry {
+        JournalEntry { id, batch, command: format!("command {}", batch), created_at: Utc::now(), undone, changes }
+    }
+
+    #[test]
+    fn batches_span_every_change_made_by_one_run() {
+        let entries = vec![
+            entry(1, 10, false, vec![TaskChange { id: 1, before: None, after: task(1, "first") }]),
+            entry(2, 20, false, vec![TaskChange { id: 1, before: task(1, "first"), after: task(1, "renamed") }]),
+            entry(3, 20, false, vec![
+                TaskChange { id: 1, before: task(1, "renamed"), after: None },
+                TaskChange { id: 2, before: None, after: task(2, "second") },
+            ]),
+            entry(4, 30, true, vec![TaskChange { id: 3, before: None, after: task(3, "third") }]),
+        ];
+
+        let batches = Batch::group(entries);
+        let undo = Batch::next_to_undo(&batches).unwrap();
+        let names = |states: Vec<(i32, Option<Task>)>| -> Vec<(i32, Option<String>)> {
+            states.into_iter().map(|(id, task)| (id, task.map(|task| task.name))).collect()
+        };
+
+        assert_eq!(batches.len(), 3);
+        assert_eq!((undo.command(), undo.entry_ids()), ("command 20", vec![2, 3]));
+        assert_eq!(names(undo.before()), [(1, Some("first".into())), (2, None)]);
+        assert_eq!(names(undo.after()), [(1, None), (2, Some("second".into()))]);
+        assert_eq!(Batch::next_to_redo(&batches).unwrap().command(), "command 30");
+    }
+}
@@ -0,0 +1,98 @@
+use crate::tasks::Task;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// What one task looked like before and after a change. A task that was added has no
+/// `before`. One that was removed is recorded as it is in the trash, so undoing and redoing
+/// move it in and out of there rather than deleting it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskChange {
+    pub id: i32,
+    pub before: Option<Task>,
+    pub after: Option<Task>,
+}
+
+impl TaskChange {
+    /// Every task that differs between two copies of the task list, by id.
+    pub fn between(before: &[Task], after: &[Task]) -> Vec<Self> {
+        let mut ids: Vec<i32> = before.iter().chain(after.iter()).map(|task| task.id).collect();
+        ids.sort();
+        ids.dedup();
+
+        ids.into_iter()
+           .filter_map(|id| {
+               let before = before.iter().find(|task| task.id == id);
+               let after = after.iter().find(|task| task.id == id);
+               (before != after).then(|| Self {
+                   id,
+                   before: before.cloned(),
+                   after: after.cloned(),
+               })
+           })
+           .collect()
+    }
+}
+
+/// One call to `add`, `remove` or `update`, with every task it changed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub id: i64,
+    /// Entries made by the same run of `todo` share a batch, and are undone together.
+    pub batch: i64,
+    /// The command line that made the change, such as `remove 12`.
+    pub command: String,
+    pub created_at: DateTime<Utc>,
+    pub undone: bool,
+    pub changes: Vec<TaskChange>,
+}
+
+impl JournalEntry {
+    /// Builds the stored form of a new entry once the backend has picked its id.
+    pub fn from_new_entry(id: i64, entry: NewJournalEntry) -> Self {
+        Self {
+            id,
+            batch: entry.batch,
+            command: entry.command,
+            created_at: entry.created_at,
+            undone: false,
+            changes: entry.changes,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewJournalEntry {
+    pub batch: i64,
+    pub command: String,
+    pub created_at: DateTime<Utc>,
+    pub changes: Vec<TaskChange>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TaskChange;
+    use crate::tasks::{NewTask, Task};
+    use chrono::Utc;
+
+    fn task(id: i32, name: &str) -> Task {
+        Task::from_new_task(id, NewTask { name: name.into(), ..NewTask::default() }, Utc::now())
+    }
+
+    #[test]
+    fn only_tasks_that_differ_are_changes() {
+        let before = [task(1, "Buy milk"), task(2, "Fix the door"), task(3, "Write report")];
+        let mut after = vec![before[0].clone(), task(2, "Fix the gate"), task(4, "Call the plumber")];
+        after.swap(0, 1);
+
+        let changes: Vec<_> = TaskChange::between(&before, &after)
+            .into_iter()
+            .map(|change| (change.id, change.before.map(|task| task.name), change.after.map(|task| task.name)))
+            .collect();
+
+        assert_eq!(changes, [
+            (2, Some("Fix the door".into()), Some("Fix the gate".into())),
+            (3, Some("Write report".into()), None),
+            (4, None, Some("Call the plumber".into())),
+        ]);
+    }
+}
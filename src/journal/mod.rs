@@ -0,0 +1,5 @@
+mod batch;
+pub use batch::{Batch, KEPT_BATCHES};
+
+mod journal_entry;
+pub use journal_entry::{JournalEntry, NewJournalEntry, TaskChange};
@@ -1,14 +1,31 @@
 mod arguments;
+mod backup;
+mod dates;
 mod persistence;
 mod tasks;
+mod projects;
 mod config;
 mod error;
+mod editor;
+mod journal;
+mod output;
+mod query;
+mod search;
 
-use crate::arguments::Commands;
-use crate::tasks::NewTask;
+use crate::arguments::{Commands, DbCommands, ProjectCommands, TagCommands, TrashCommands};
+use crate::backup::Snapshot;
+use crate::error::{JournalConflictError, TaskNotFoundError};
+use crate::journal::Batch;
+use crate::output::{ConfigRecord, DateRecord, DoctorRecord, HistoryRecord, OutputFormat, ProjectRecord, SchemaRecord, SearchRecord, Table, TagRecord, TaskDetailRecord, TaskRecord, TrashRecord};
+use crate::projects::NewProject;
+use crate::search::SearchTerms;
+use crate::tasks::{blocked_tasks, descendants, flatten_tree, open_blockers, subtask_progress, NewTask, Status, TaskDetails, TaskEdit, TaskFilter, TaskSort};
 use anyhow::Result;
+use chrono::{Local, Utc};
 use clap::Parser;
 use log::LevelFilter;
+use std::io::IsTerminal;
+use std::path::PathBuf;
 use simplelog::{ColorChoice, Config, TermLogger, TerminalMode};
 use url::Url;
 
@@ -21,6 +38,36 @@ fn setup_logging(level_filter: LevelFilter) -> Result<()> {
     )?)
 }
 
+/// Moves a task to `status` if the configured transitions allow it.
+async fn change_status(config: &config::Config, number: i32, status: Status) {
+    log::debug!("Creating persistence layer");
+    let persistence = persistence::get_repository(config).await;
+
+    let mut task = persistence.get_by_id(number)
+                              .await
+                              .unwrap_or_else(|e| {
+                                  log::error!("Failed to get tasks: {}", e);
+                                  std::process::exit(1);
+                              });
+
+    config.transitions()
+          .check(number, task.status, status)
+          .unwrap_or_else(|e| {
+              log::error!("Failed to change status: {}", e);
+              std::process::exit(1);
+          });
+
+    log::info!("Moving task {} from {} to {}", number, task.status, status);
+    task.set_status(status);
+
+    persistence.update(task)
+               .await
+               .unwrap_or_else(|e| {
+                   log::error!("Failed to update tasks: {}", e);
+                   std::process::exit(1);
+               })
+}
+
 #[tokio::main]
 async fn main() {
     // Parse the command line arguments.
@@ -42,7 +89,7 @@ async fn main() {
     log::debug!("Config path set to: {}", config_path.to_string_lossy());
 
     // Default to a file:// url if config isn't read.
-    let config = config::Config::from_path(config_path)
+    let mut config = config::Config::from_path(config_path)
         .or_else(|_| {
             log::trace!("No config file found, using default config");
             config::Config::default()
@@ -53,34 +100,156 @@ async fn main() {
         });
 
     // Config here is either read from a file or is default-constructed.
+    config.set_dry_run(args.dry_run);
+
+    // Read commands print records instead of text when asked for another format.
+    let output = args.output;
 
     // Execute the command given in the arguments.
     match args.command {
-        // List all tasks.
-        Commands::List => {
+        // List all tasks, or those matching the given filters.
+        Commands::List { query, overdue, due_before, due_after, sort, tags, project, archived, ready, columns } => {
             log::trace!("Found instruction Commands::List");
             log::debug!("Creating persistence layer");
-            let persistence = persistence::get_repository(&config);
+            let persistence = persistence::get_repository(&config).await;
+
+            let projects = persistence.get_projects().await.unwrap_or_else(|e| {
+                log::error!("Failed to get projects: {}", e);
+                std::process::exit(1);
+            });
+
+            let project_id = project.map(|name| {
+                projects.iter()
+                        .find(|project| project.name == name)
+                        .map(|project| project.id)
+                        .unwrap_or_else(|| {
+                            log::error!("No project named \"{}\" found", name);
+                            std::process::exit(1);
+                        })
+            });
+
+            // The query can be given as one quoted argument or as several words.
+            let query = if query.is_empty() {
+                None
+            } else {
+                Some(query::parse_query(&query.join(" "), Local::now()).unwrap_or_else(|e| {
+                    log::error!("{}", e);
+                    std::process::exit(1);
+                }))
+            };
+
+            let filter = TaskFilter {
+                overdue,
+                due_before,
+                due_after,
+                tags,
+                project_id,
+                archived,
+                ready,
+                query,
+            };
 
             log::info!("Getting tasks");
-            let tasks = persistence.get_all().await.unwrap_or_else(|e| {
+            log::debug!("Using filter {:?}", filter);
+            let mut tasks = persistence.find(&filter).await.unwrap_or_else(|e| {
                 log::error!("Failed to get tasks: {}", e);
                 std::process::exit(1);
             });
 
-            log::debug!("Listing {} tasks(s):", tasks.len());
-            for task in tasks.iter() {
-                println!("{}. {}", task.id, task);
+            log::debug!("Sorting tasks by {:?}", sort);
+            sort.sort(&mut tasks);
+
+            if output != OutputFormat::Text {
+                let records: Vec<_> = tasks.iter().map(|task| TaskRecord::new(task, &projects)).collect();
+                output::print_records(output, &records).unwrap_or_else(|e| {
+                    log::error!("Failed to write tasks: {}", e);
+                    std::process::exit(1);
+                });
+                std::process::exit(0);
+            }
+
+            // Progress and blockers take every task into account, including those the filter left out.
+            let all_tasks = persistence.get_all().await.unwrap_or_else(|e| {
+                log::error!("Failed to get tasks: {}", e);
+                std::process::exit(1);
+            });
+
+            let columns = if columns.is_empty() { config.columns() } else { columns };
+            log::debug!("Listing {} tasks(s) with columns {:?}", tasks.len(), columns);
+            let now = Utc::now();
+            let mut table = Table::new(columns);
+            for (project, tasks) in projects::group_by_project(&tasks, &projects) {
+                table.section(project.map(|project| project.to_string()));
+                let project_name = project.map(|project| project.name.as_str());
+
+                for (depth, task) in flatten_tree(&tasks) {
+                    let progress = subtask_progress(&all_tasks, task.id)
+                        .map(|(done, total)| format!(" ({}/{} done)", done, total))
+                        .unwrap_or_default();
+                    let blockers = open_blockers(&all_tasks, task);
+                    let blocked = if blockers.is_empty() || !task.is_open() {
+                        String::new()
+                    } else {
+                        let blockers: Vec<_> = blockers.iter().map(|id| id.to_string()).collect();
+                        format!(" (waiting for {})", blockers.join(", "))
+                    };
+                    table.add_task(task, depth, &format!("{}{}", progress, blocked), project_name, now);
+                }
+            }
+
+            if !table.is_empty() {
+                print!("{}", table.render(output::terminal_width(), output::use_colour()));
             }
 
             log::debug!("Done listing tasks, exiting early because no changes were made");
             std::process::exit(0);
         }
+        // Find tasks by the words in their name or description, best match first.
+        Commands::Search { terms, limit } => {
+            log::trace!("Found instruction Commands::Search");
+            let terms = SearchTerms::parse(&terms.join(" ")).unwrap_or_else(|e| {
+                log::error!("{}", e);
+                std::process::exit(1);
+            });
+
+            log::debug!("Creating persistence layer");
+            let persistence = persistence::get_repository(&config).await;
+
+            log::info!("Searching for {:?}", terms.words());
+            let hits = persistence.search(&terms, limit).await.unwrap_or_else(|e| {
+                log::error!("Failed to search tasks: {}", e);
+                std::process::exit(1);
+            });
+
+            if output != OutputFormat::Text {
+                let records: Vec<_> = hits.iter().map(SearchRecord::new).collect();
+                output::print_records(output, &records).unwrap_or_else(|e| {
+                    log::error!("Failed to write search results: {}", e);
+                    std::process::exit(1);
+                });
+                std::process::exit(0);
+            }
+
+            print!("{}", output::render_search_results(&hits, output::use_colour()));
+
+            std::process::exit(0);
+        }
         // Add a new task with the given name and optionally a description.
-        Commands::Add { name, description } => {
+        Commands::Add { name, description, due, scheduled, priority, tags, project, parent, repeat } => {
             log::trace!("Found instruction Commands::Add");
             log::debug!("Creating persistence layer");
-            let persistence = persistence::get_repository(&config);
+            let persistence = persistence::get_repository(&config).await;
+
+            let project_id = match project {
+                Some(name) => Some(persistence.get_project_by_name(&name)
+                                              .await
+                                              .unwrap_or_else(|e| {
+                                                  log::error!("Failed to get project: {}", e);
+                                                  std::process::exit(1);
+                                              })
+                                              .id),
+                None => None,
+            };
 
             if description.is_none() {
                 log::info!("Adding new tasks with no description");
@@ -91,6 +260,13 @@ async fn main() {
             let task = NewTask {
                 name,
                 description,
+                due,
+                scheduled,
+                priority,
+                tags,
+                project_id,
+                parent_id: parent,
+                recurrence: repeat,
             };
 
             persistence.add(task)
@@ -104,7 +280,7 @@ async fn main() {
         Commands::Remove { number } => {
             log::trace!("Found instruction Commands::Remove");
             log::debug!("Creating persistence layer");
-            let persistence = persistence::get_repository(&config);
+            let persistence = persistence::get_repository(&config).await;
 
             log::info!("Removing task {}", number);
             persistence.remove(number)
@@ -114,13 +290,292 @@ async fn main() {
                            std::process::exit(1);
                        });
         }
+        // Print a single task in full.
+        Commands::Show { number } => {
+            log::trace!("Found instruction Commands::Show");
+            log::debug!("Creating persistence layer");
+            let persistence = persistence::get_repository(&config).await;
+
+            let task = persistence.get_by_id(number)
+                                  .await
+                                  .unwrap_or_else(|e| {
+                                      log::error!("Failed to get tasks: {}", e);
+                                      std::process::exit(1);
+                                  });
+
+            let tasks = persistence.get_all().await.unwrap_or_else(|e| {
+                log::error!("Failed to get tasks: {}", e);
+                std::process::exit(1);
+            });
+
+            let projects = persistence.get_projects().await.unwrap_or_else(|e| {
+                log::error!("Failed to get projects: {}", e);
+                std::process::exit(1);
+            });
+
+            let project = task.project_id
+                              .and_then(|id| projects.iter().find(|project| project.id == id))
+                              .map(|project| project.name.clone());
+            let details = TaskDetails::new(task, &tasks, project);
+
+            if output != OutputFormat::Text {
+                output::print_records(output, &[TaskDetailRecord::new(&details, &projects)]).unwrap_or_else(|e| {
+                    log::error!("Failed to write tasks: {}", e);
+                    std::process::exit(1);
+                });
+                std::process::exit(0);
+            }
+
+            let width = output::terminal_width().unwrap_or(80);
+            print!("{}", details.render(width, Utc::now()));
+
+            std::process::exit(0);
+        }
+        // Change the fields of a task, either from the command line or in an editor.
+        Commands::Edit { number, name, description, clear_description, due, clear_due, scheduled, clear_scheduled } => {
+            log::trace!("Found instruction Commands::Edit");
+            log::debug!("Creating persistence layer");
+            let persistence = persistence::get_repository(&config).await;
+
+            let original = persistence.get_by_id(number)
+                                      .await
+                                      .unwrap_or_else(|e| {
+                                          log::error!("Failed to get tasks: {}", e);
+                                          std::process::exit(1);
+                                      });
+            let mut task = original.clone();
+
+            let dates_given = due.is_some() || clear_due || scheduled.is_some() || clear_scheduled;
+            if name.is_none() && description.is_none() && !clear_description && !dates_given {
+                let contents = TaskEdit::from_task(&task).to_toml(number).unwrap_or_else(|e| {
+                    log::error!("Failed to write out task {}: {}", number, e);
+                    std::process::exit(1);
+                });
+
+                let edited = editor::edit_until_valid(&editor::editor_command(), &contents, |contents| {
+                    let mut task = original.clone();
+                    TaskEdit::from_toml(contents)?.apply(&mut task)?;
+                    Ok(task)
+                }).unwrap_or_else(|e| {
+                    log::error!("Failed to edit task {}: {}", number, e);
+                    std::process::exit(1);
+                });
+
+                match edited {
+                    Some(edited) => task = edited,
+                    None => {
+                        println!("Edit cancelled, task {} is unchanged", number);
+                        std::process::exit(0);
+                    }
+                }
+            } else {
+                // The flags go through the same checks as an edit made in the editor.
+                let mut edit = TaskEdit::from_task(&task);
+                if let Some(name) = name {
+                    edit.name = name;
+                }
+                if let Some(description) = description {
+                    edit.description = Some(description);
+                }
+                if clear_description {
+                    edit.description = None;
+                }
+                edit.apply(&mut task).unwrap_or_else(|e| {
+                    log::error!("Failed to edit task {}: {}", number, e);
+                    std::process::exit(1);
+                });
+
+                // Dates are already parsed, so they skip the written out form to keep their seconds.
+                if due.is_some() || clear_due {
+                    task.due = due;
+                }
+                if scheduled.is_some() || clear_scheduled {
+                    task.scheduled = scheduled;
+                }
+            }
+
+            if task == original {
+                println!("No changes to task {}", number);
+                std::process::exit(0);
+            }
+
+            log::info!("Updating task {}", number);
+            persistence.update(task)
+                       .await
+                       .unwrap_or_else(|e| {
+                           log::error!("Failed to update tasks: {}", e);
+                           std::process::exit(1);
+                       })
+        }
         // Mark the task with the given ID as completed.
-        Commands::Complete { number } => {
+        Commands::Complete { number, cascade, force } => {
             log::trace!("Found instruction Commands::Complete");
             log::debug!("Creating persistence layer");
-            let persistence = persistence::get_repository(&config);
+            let persistence = persistence::get_repository(&config).await;
+
+            let tasks = persistence.get_all().await.unwrap_or_else(|e| {
+                log::error!("Failed to get tasks: {}", e);
+                std::process::exit(1);
+            });
+
+            let task = tasks.iter()
+                            .find(|task| task.id == number)
+                            .cloned()
+                            .unwrap_or_else(|| {
+                                log::error!("Failed to get tasks: {}", TaskNotFoundError { id: number });
+                                std::process::exit(1);
+                            });
+
+            let open_subtasks: Vec<_> = descendants(&tasks, number).into_iter()
+                                                                   .filter(|subtask| subtask.is_open())
+                                                                   .cloned()
+                                                                   .collect();
+
+            if !open_subtasks.is_empty() && !cascade {
+                log::error!("Task {} has {} open subtask(s), complete them first or use --cascade", number, open_subtasks.len());
+                std::process::exit(1);
+            }
+
+            let mut completing = open_subtasks;
+            completing.push(task);
+
+            // Subtasks completed by --cascade have to be free to go just as much as the task itself.
+            let blocked = blocked_tasks(&tasks, &completing);
+            if !blocked.is_empty() {
+                let blocked: Vec<_> = blocked.iter()
+                                             .map(|(id, blockers)| {
+                                                 let blockers: Vec<_> = blockers.iter().map(|id| id.to_string()).collect();
+                                                 format!("task {} is waiting for task(s) {}", id, blockers.join(", "))
+                                             })
+                                             .collect();
+                if !force {
+                    log::error!("Cannot complete task {}: {}. Complete those first or use --force", number, blocked.join("; "));
+                    std::process::exit(1);
+                }
+                log::warn!("Completing task {} although {}", number, blocked.join("; "));
+            }
+
+            // Check every task first so a refused subtask doesn't leave the rest half done.
+            for task in completing.iter() {
+                config.transitions()
+                      .check(task.id, task.status, Status::Done)
+                      .unwrap_or_else(|e| {
+                          log::error!("Failed to complete task: {}", e);
+                          std::process::exit(1);
+                      });
+            }
+
+            for mut task in completing {
+                log::info!("Marking task {} as complete", task.id);
+                task.complete();
+
+                // Recurring tasks are followed by their next occurrence.
+                let next = task.next_occurrence(Utc::now()).unwrap_or_else(|e| {
+                    log::error!("Failed to work out when task {} repeats: {}", task.id, e);
+                    std::process::exit(1);
+                });
+                // The rule moves on to the next occurrence, so completing this one again adds nothing.
+                task.recurrence = None;
+
+                persistence.update(task)
+                           .await
+                           .unwrap_or_else(|e| {
+                               log::error!("Failed to update tasks: {}", e);
+                               std::process::exit(1);
+                           });
+
+                if let Some(next) = next {
+                    log::info!("Adding the next occurrence of \"{}\"", next.name);
+                    if let Some(due) = &next.due {
+                        println!("\"{}\" repeats, next due {}", next.name, dates::format_date_time(due));
+                    }
+
+                    persistence.add(next)
+                               .await
+                               .unwrap_or_else(|e| {
+                                   log::error!("Failed to add tasks: {}", e);
+                                   std::process::exit(1);
+                               });
+                }
+            }
+        }
+        // Move a task through its workflow.
+        Commands::Start { number } => {
+            log::trace!("Found instruction Commands::Start");
+            change_status(&config, number, Status::InProgress).await;
+        }
+        Commands::Reopen { number } => {
+            log::trace!("Found instruction Commands::Reopen");
+            change_status(&config, number, Status::Todo).await;
+        }
+        Commands::Cancel { number } => {
+            log::trace!("Found instruction Commands::Cancel");
+            change_status(&config, number, Status::Cancelled).await;
+        }
+        Commands::Wait { number, blocked } => {
+            log::trace!("Found instruction Commands::Wait");
+            let status = if blocked { Status::Blocked } else { Status::Waiting };
+            change_status(&config, number, status).await;
+        }
+        // Set how a task repeats, or stop it repeating.
+        Commands::Repeat { number, rule } => {
+            log::trace!("Found instruction Commands::Repeat");
+            log::debug!("Creating persistence layer");
+            let persistence = persistence::get_repository(&config).await;
+
+            let mut task = persistence.get_by_id(number)
+                                      .await
+                                      .unwrap_or_else(|e| {
+                                          log::error!("Failed to get tasks: {}", e);
+                                          std::process::exit(1);
+                                      });
+
+            log::info!("Setting task {} to repeat {:?}", number, rule);
+            task.recurrence = rule;
+
+            persistence.update(task)
+                       .await
+                       .unwrap_or_else(|e| {
+                           log::error!("Failed to update tasks: {}", e);
+                           std::process::exit(1);
+                       })
+        }
+        // Make a task wait for other tasks, or stop waiting for them.
+        Commands::Depend { number, on, remove } => {
+            log::trace!("Found instruction Commands::Depend");
+            log::debug!("Creating persistence layer");
+            let persistence = persistence::get_repository(&config).await;
+
+            let mut task = persistence.get_by_id(number)
+                                      .await
+                                      .unwrap_or_else(|e| {
+                                          log::error!("Failed to get tasks: {}", e);
+                                          std::process::exit(1);
+                                      });
+
+            if remove {
+                log::info!("Task {} no longer waits for {:?}", number, on);
+                task.depends_on.retain(|depends_on| !on.contains(depends_on));
+            } else {
+                log::info!("Task {} now waits for {:?}", number, on);
+                task.depends_on.extend(on);
+                task.depends_on.sort();
+                task.depends_on.dedup();
+            }
+
+            persistence.update(task)
+                       .await
+                       .unwrap_or_else(|e| {
+                           log::error!("Failed to update tasks: {}", e);
+                           std::process::exit(1);
+                       })
+        }
+        // Move a task below another task, or back to the top level.
+        Commands::Parent { number, parent } => {
+            log::trace!("Found instruction Commands::Parent");
+            log::debug!("Creating persistence layer");
+            let persistence = persistence::get_repository(&config).await;
 
-            log::info!("Marking task {} as complete", number);
             let mut task = persistence.get_by_id(number)
                                       .await
                                       .unwrap_or_else(|e| {
@@ -128,7 +583,8 @@ async fn main() {
                                           std::process::exit(1);
                                       });
 
-            task.complete();
+            log::info!("Moving task {} below {:?}", number, parent);
+            task.parent_id = parent;
 
             persistence.update(task)
                        .await
@@ -137,6 +593,99 @@ async fn main() {
                            std::process::exit(1);
                        })
         }
+        // Change how important the task with the given ID is.
+        Commands::Prioritize { number, priority } => {
+            log::trace!("Found instruction Commands::Prioritize");
+            log::debug!("Creating persistence layer");
+            let persistence = persistence::get_repository(&config).await;
+
+            log::info!("Setting priority of task {} to {}", number, priority);
+            let mut task = persistence.get_by_id(number)
+                                      .await
+                                      .unwrap_or_else(|e| {
+                                          log::error!("Failed to get tasks: {}", e);
+                                          std::process::exit(1);
+                                      });
+
+            task.priority = priority;
+
+            persistence.update(task)
+                       .await
+                       .unwrap_or_else(|e| {
+                           log::error!("Failed to update tasks: {}", e);
+                           std::process::exit(1);
+                       })
+        }
+        // Add tags to, or remove tags from, the task with the given ID.
+        Commands::Tag { command } => {
+            log::trace!("Found instruction Commands::Tag");
+            log::debug!("Creating persistence layer");
+            let persistence = persistence::get_repository(&config).await;
+
+            let number = match command {
+                TagCommands::Add { number, .. } | TagCommands::Remove { number, .. } => number,
+            };
+
+            let mut task = persistence.get_by_id(number)
+                                      .await
+                                      .unwrap_or_else(|e| {
+                                          log::error!("Failed to get tasks: {}", e);
+                                          std::process::exit(1);
+                                      });
+
+            match command {
+                TagCommands::Add { tags, .. } => {
+                    for tag in tags {
+                        log::info!("Tagging task {} with {}", number, tag);
+                        if !task.add_tag(tag.clone()) {
+                            log::warn!("Task {} is already tagged with {}", number, tag);
+                        }
+                    }
+                }
+                TagCommands::Remove { tags, .. } => {
+                    for tag in tags {
+                        log::info!("Removing tag {} from task {}", tag, number);
+                        if !task.remove_tag(&tag) {
+                            log::warn!("Task {} is not tagged with {}", number, tag);
+                        }
+                    }
+                }
+            }
+
+            persistence.update(task)
+                       .await
+                       .unwrap_or_else(|e| {
+                           log::error!("Failed to update tasks: {}", e);
+                           std::process::exit(1);
+                       })
+        }
+        // List every tag along with the number of tasks that have it.
+        Commands::Tags => {
+            log::trace!("Found instruction Commands::Tags");
+            log::debug!("Creating persistence layer");
+            let persistence = persistence::get_repository(&config).await;
+
+            log::info!("Getting tags");
+            let tags = persistence.get_tags().await.unwrap_or_else(|e| {
+                log::error!("Failed to get tags: {}", e);
+                std::process::exit(1);
+            });
+
+            if output != OutputFormat::Text {
+                let records: Vec<_> = tags.into_iter().map(|(tag, tasks)| TagRecord { tag, tasks }).collect();
+                output::print_records(output, &records).unwrap_or_else(|e| {
+                    log::error!("Failed to write tags: {}", e);
+                    std::process::exit(1);
+                });
+                std::process::exit(0);
+            }
+
+            for (tag, count) in tags.iter() {
+                println!("{} ({})", tag, count);
+            }
+
+            std::process::exit(0);
+        }
         // Generate a new configuration file that points to the given storage location.
         Commands::Config { storage: storage_url } => {
             log::trace!("Found instruction Commands::Config");
@@ -148,8 +697,6 @@ async fn main() {
                     std::process::exit(1);
                 });
 
-                let new_config = config::Config::new_with_url(new_storage_url);
-
                 let config_file_location = config::Config::default_path()
                     .unwrap_or_else(|| {
                         log::error!("Failed to get default path");
@@ -157,6 +704,16 @@ async fn main() {
                     })
                     .join("config.toml");
 
+                let new_config = config::Config::from_path_with_storage(&config_file_location, new_storage_url).unwrap_or_else(|e| {
+                    log::error!("Failed to read the existing config, fix or remove it first: {}", e);
+                    std::process::exit(1);
+                });
+
+                if config.dry_run() {
+                    println!("Would write config to {}: storage = {}", config_file_location.to_string_lossy(), new_config.storage());
+                    std::process::exit(0);
+                }
+
                 log::debug!("Writing config to {}", config_file_location.to_string_lossy());
 
                 new_config.write_to_file(&config_file_location).unwrap_or_else(|e| {
@@ -179,9 +736,489 @@ async fn main() {
                     log::error!("Failed to load config: {}", e);
                     std::process::exit(1);
                 });
+
+                if output != OutputFormat::Text {
+                    output::print_records(output, &[ConfigRecord::new(&config)]).unwrap_or_else(|e| {
+                        log::error!("Failed to write config: {}", e);
+                        std::process::exit(1);
+                    });
+                    std::process::exit(0);
+                }
+
                 println!("{}", config);
             }
         }
+        // Create a new project.
+        Commands::Project { command: ProjectCommands::Add { name, description } } => {
+            log::trace!("Found instruction ProjectCommands::Add");
+            log::debug!("Creating persistence layer");
+            let persistence = persistence::get_repository(&config).await;
+
+            let name = name.trim().to_string();
+            if name.is_empty() {
+                log::error!("Project names cannot be empty");
+                std::process::exit(1);
+            }
+
+            log::info!("Adding project {}", name);
+            persistence.add_project(NewProject { name, description })
+                       .await
+                       .unwrap_or_else(|e| {
+                           log::error!("Failed to add project: {}", e);
+                           std::process::exit(1);
+                       });
+        }
+        // List every project, archived or not.
+        Commands::Project { command: ProjectCommands::List } => {
+            log::trace!("Found instruction ProjectCommands::List");
+            log::debug!("Creating persistence layer");
+            let persistence = persistence::get_repository(&config).await;
+
+            log::info!("Getting projects");
+            let projects = persistence.get_projects().await.unwrap_or_else(|e| {
+                log::error!("Failed to get projects: {}", e);
+                std::process::exit(1);
+            });
+
+            if output != OutputFormat::Text {
+                let records: Vec<_> = projects.iter().map(ProjectRecord::new).collect();
+                output::print_records(output, &records).unwrap_or_else(|e| {
+                    log::error!("Failed to write projects: {}", e);
+                    std::process::exit(1);
+                });
+                std::process::exit(0);
+            }
+
+            for project in projects.iter() {
+                println!("{}", project);
+            }
+
+            std::process::exit(0);
+        }
+        // Archive a project, or bring it back.
+        Commands::Project { command: ProjectCommands::Archive { ref name } } |
+        Commands::Project { command: ProjectCommands::Unarchive { ref name } } => {
+            let archived = matches!(args.command, Commands::Project { command: ProjectCommands::Archive { .. } });
+            log::trace!("Found instruction ProjectCommands::{}", if archived { "Archive" } else { "Unarchive" });
+            log::debug!("Creating persistence layer");
+            let persistence = persistence::get_repository(&config).await;
+
+            let project = persistence.get_project_by_name(name)
+                                     .await
+                                     .unwrap_or_else(|e| {
+                                         log::error!("Failed to get project: {}", e);
+                                         std::process::exit(1);
+                                     });
+
+            log::info!("Setting archived to {} on project {}", archived, project.name);
+            persistence.archive_project(project.id, archived)
+                       .await
+                       .unwrap_or_else(|e| {
+                           log::error!("Failed to update project: {}", e);
+                           std::process::exit(1);
+                       });
+        }
+        // Show a project along with all of its tasks.
+        Commands::Project { command: ProjectCommands::Show { name } } => {
+            log::trace!("Found instruction ProjectCommands::Show");
+            log::debug!("Creating persistence layer");
+            let persistence = persistence::get_repository(&config).await;
+
+            let project = persistence.get_project_by_name(&name)
+                                     .await
+                                     .unwrap_or_else(|e| {
+                                         log::error!("Failed to get project: {}", e);
+                                         std::process::exit(1);
+                                     });
+
+            let filter = TaskFilter {
+                project_id: Some(project.id),
+                ..TaskFilter::default()
+            };
+            let mut tasks = persistence.find(&filter).await.unwrap_or_else(|e| {
+                log::error!("Failed to get tasks: {}", e);
+                std::process::exit(1);
+            });
+            TaskSort::default().sort(&mut tasks);
+
+            if output != OutputFormat::Text {
+                let projects = [project];
+                let records: Vec<_> = tasks.iter().map(|task| TaskRecord::new(task, &projects)).collect();
+                output::print_records(output, &records).unwrap_or_else(|e| {
+                    log::error!("Failed to write tasks: {}", e);
+                    std::process::exit(1);
+                });
+                std::process::exit(0);
+            }
+
+            let completed = tasks.iter().filter(|task| task.completed()).count();
+            println!("{}", project);
+            println!("{} task(s), {} completed", tasks.len(), completed);
+            for task in tasks.iter() {
+                println!("  {}. {}", task.id, task);
+            }
+
+            std::process::exit(0);
+        }
+        // List what is in the trash.
+        Commands::Trash { command: TrashCommands::List } => {
+            log::trace!("Found instruction TrashCommands::List");
+            let persistence = persistence::get_repository(&config).await;
+
+            let trash = persistence.get_trash().await.unwrap_or_else(|e| {
+                log::error!("Failed to get the trash: {}", e);
+                std::process::exit(1);
+            });
+
+            if output != OutputFormat::Text {
+                let records: Vec<_> = trash.iter().map(TrashRecord::new).collect();
+                output::print_records(output, &records).unwrap_or_else(|e| {
+                    log::error!("Failed to write the trash: {}", e);
+                    std::process::exit(1);
+                });
+                std::process::exit(0);
+            }
+
+            if trash.is_empty() {
+                println!("The trash is empty");
+            }
+            for task in trash.iter() {
+                let removed = task.deleted_at.map(|deleted_at| deleted_at.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string());
+                println!("{}  {}  (removed {})", task.id, task.name, removed.unwrap_or_default());
+            }
+        }
+        // Take a task back out of the trash.
+        Commands::Trash { command: TrashCommands::Restore { number } } => {
+            log::trace!("Found instruction TrashCommands::Restore");
+            let persistence = persistence::get_repository(&config).await;
+
+            log::info!("Restoring task {} from the trash", number);
+            persistence.restore_from_trash(number).await.unwrap_or_else(|e| {
+                log::error!("Failed to restore task: {}", e);
+                std::process::exit(1);
+            });
+        }
+        // Delete everything in the trash for good.
+        Commands::Trash { command: TrashCommands::Empty } => {
+            log::trace!("Found instruction TrashCommands::Empty");
+            let persistence = persistence::get_unjournaled_repository(&config).await;
+
+            let purged = persistence.empty_trash(Utc::now()).await.unwrap_or_else(|e| {
+                log::error!("Failed to empty the trash: {}", e);
+                std::process::exit(1);
+            });
+            if config.dry_run() {
+                println!("Would delete {} task(s) for good", purged);
+            } else {
+                println!("Deleted {} task(s) for good", purged);
+            }
+        }
+        // Look for problems with the configured storage and repair them unless told not to.
+        Commands::Doctor { check } => {
+            log::trace!("Found instruction Commands::Doctor");
+
+            log::info!("Checking storage for problems");
+            let report = persistence::doctor(&config, !check && !config.dry_run())
+                .await
+                .unwrap_or_else(|e| {
+                    log::error!("Failed to check storage: {}", e);
+                    std::process::exit(1);
+                });
+
+            if output != OutputFormat::Text {
+                output::print_records(output, &[DoctorRecord::new(&report)]).unwrap_or_else(|e| {
+                    log::error!("Failed to write report: {}", e);
+                    std::process::exit(1);
+                });
+            } else {
+                print!("{}", report);
+            }
+
+            if check && !report.is_healthy() {
+                std::process::exit(1);
+            }
+        }
+        // Copy everything from one storage URL to another, keeping ids, so a team can move backends.
+        Commands::Migrate { from, to } => {
+            log::trace!("Found instruction Commands::Migrate");
+            if from == to {
+                log::error!("The storage to copy from and to are the same");
+                std::process::exit(1);
+            }
+
+            // Nothing is ever written to the source, dry run or not.
+            let source = persistence::open_source_repository(&config::Config::new_with_url(from)).await.unwrap_or_else(|e| {
+                log::error!("Failed to open the storage to copy from: {}", e);
+                std::process::exit(1);
+            });
+            let mut target_config = config::Config::new_with_url(to);
+            target_config.set_dry_run(config.dry_run());
+            let target = persistence::get_unjournaled_repository(&target_config).await;
+
+            log::info!("Copying tasks and projects");
+            let show_progress = std::io::stderr().is_terminal();
+            let mut progress_shown = false;
+            let report = persistence::transfer(source.as_ref(), target.as_ref(), &mut |done, total| {
+                if show_progress {
+                    eprint!("\rCopying tasks: {}/{}", done, total);
+                    progress_shown = true;
+                }
+            }).await;
+            if progress_shown {
+                eprintln!();
+            }
+
+            let report = report.unwrap_or_else(|e| {
+                log::error!("Failed to migrate: {}", e);
+                std::process::exit(1);
+            });
+            print!("{}", report);
+        }
+        // Snapshot the whole store into the backup directory and prune old snapshots.
+        Commands::Backup { directory } => {
+            log::trace!("Found instruction Commands::Backup");
+            let directory = directory.map(Ok)
+                                     .unwrap_or_else(|| config.backups().directory())
+                                     .unwrap_or_else(|e| {
+                                         log::error!("Failed to get backup directory: {}", e);
+                                         std::process::exit(1);
+                                     });
+
+            let persistence = persistence::get_repository(&config).await;
+            let snapshot = Snapshot::take(persistence.as_ref()).await.unwrap_or_else(|e| {
+                log::error!("Failed to read tasks: {}", e);
+                std::process::exit(1);
+            });
+
+            if config.dry_run() {
+                println!("Would back up {} task(s) and {} project(s) to {}",
+                         snapshot.tasks.len(), snapshot.projects.len(), directory.join(snapshot.file_name()).display());
+                std::process::exit(0);
+            }
+
+            let path = snapshot.write(&directory).unwrap_or_else(|e| {
+                log::error!("Failed to write backup: {}", e);
+                std::process::exit(1);
+            });
+            println!("Backed up {} task(s) and {} project(s) to {}", snapshot.tasks.len(), snapshot.projects.len(), path.display());
+
+            let removed = backup::prune(&directory, config.backups()).unwrap_or_else(|e| {
+                log::error!("Failed to remove old backups: {}", e);
+                std::process::exit(1);
+            });
+            for path in removed {
+                println!("Removed old backup {}", path.display());
+            }
+        }
+        // Put the configured store back the way it was in a snapshot.
+        Commands::Restore { snapshot } => {
+            log::trace!("Found instruction Commands::Restore");
+            let directory = config.backups().directory().unwrap_or_else(|e| {
+                log::error!("Failed to get backup directory: {}", e);
+                std::process::exit(1);
+            });
+
+            let path = if snapshot == "latest" {
+                let snapshots = backup::list(&directory).unwrap_or_else(|e| {
+                    log::error!("Failed to list backups: {}", e);
+                    std::process::exit(1);
+                });
+                snapshots.into_iter().next_back().map(|(_, path)| path).unwrap_or_else(|| {
+                    log::error!("No backups found in {}", directory.display());
+                    std::process::exit(1);
+                })
+            } else {
+                PathBuf::from(snapshot)
+            };
+
+            log::info!("Reading backup {}", path.display());
+            let snapshot = Snapshot::read(&path).unwrap_or_else(|e| {
+                log::error!("Failed to read backup: {}", e);
+                std::process::exit(1);
+            });
+
+            let persistence = persistence::get_unjournaled_repository(&config).await;
+            // Nothing else may change the tasks between backing them up and restoring over them.
+            let _lock = persistence.hold_lock().await.unwrap_or_else(|e| {
+                log::error!("Failed to lock the storage: {}", e);
+                std::process::exit(1);
+            });
+            if !config.dry_run() {
+                // Keep what is there now, so the restore can be undone the same way.
+                let current = Snapshot::take(persistence.as_ref()).await.unwrap_or_else(|e| {
+                    log::error!("Failed to read tasks: {}", e);
+                    std::process::exit(1);
+                });
+                let saved = current.write(&directory).unwrap_or_else(|e| {
+                    log::error!("Failed to back up the current tasks before restoring: {}", e);
+                    std::process::exit(1);
+                });
+                println!("Backed up the current tasks to {}", saved.display());
+            }
+
+            let report = persistence::restore(persistence.as_ref(), &snapshot.tasks, &snapshot.projects)
+                .await
+                .unwrap_or_else(|e| {
+                    log::error!("Failed to restore backup: {}", e);
+                    std::process::exit(1);
+                });
+            print!("{}", report);
+
+            // The backup of the current tasks counts towards retention like any other.
+            if !config.dry_run() {
+                let removed = backup::prune(&directory, config.backups()).unwrap_or_else(|e| {
+                    log::error!("Failed to remove old backups: {}", e);
+                    std::process::exit(1);
+                });
+                for path in removed {
+                    println!("Removed old backup {}", path.display());
+                }
+            }
+        }
+        // Step back or forward through the journal one command at a time.
+        Commands::Undo { force } | Commands::Redo { force } => {
+            let redoing = matches!(args.command, Commands::Redo { .. });
+            log::trace!("Found instruction Commands::{}", if redoing { "Redo" } else { "Undo" });
+            let persistence = persistence::get_unjournaled_repository(&config).await;
+
+            let result = if redoing {
+                persistence::redo(persistence.as_ref(), force).await
+            } else {
+                persistence::undo(persistence.as_ref(), force).await
+            };
+            let batch = result.unwrap_or_else(|e| {
+                log::error!("Failed to {}: {}", if redoing { "redo" } else { "undo" }, e);
+                if e.is::<JournalConflictError>() {
+                    log::error!("Run `todo {} --force` to overwrite them anyway.", if redoing { "redo" } else { "undo" });
+                }
+                std::process::exit(1);
+            });
+
+            match batch {
+                Some(batch) if redoing => println!("Redid `{}` ({} task(s))", batch.command(), batch.after().len()),
+                Some(batch) => println!("Undid `{}` ({} task(s))", batch.command(), batch.before().len()),
+                None => println!("Nothing to {}", if redoing { "redo" } else { "undo" }),
+            }
+        }
+        // List the commands in the journal, newest first.
+        Commands::History { limit } => {
+            log::trace!("Found instruction Commands::History");
+            let persistence = persistence::get_unjournaled_repository(&config).await;
+
+            let journal = persistence.get_journal().await.unwrap_or_else(|e| {
+                log::error!("Failed to read the journal: {}", e);
+                std::process::exit(1);
+            });
+            let batches: Vec<Batch> = Batch::group(journal).into_iter().rev().take(limit).collect();
+
+            if output != OutputFormat::Text {
+                let records: Vec<_> = batches.iter().map(HistoryRecord::new).collect();
+                output::print_records(output, &records).unwrap_or_else(|e| {
+                    log::error!("Failed to write history: {}", e);
+                    std::process::exit(1);
+                });
+                std::process::exit(0);
+            }
+
+            if batches.is_empty() {
+                println!("No changes to undo");
+            }
+            for batch in batches.iter() {
+                println!("{}  {}  ({} task(s)){}",
+                         batch.created_at().with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+                         batch.command(),
+                         batch.before().len(),
+                         if batch.undone() { "  [undone]" } else { "" });
+            }
+        }
+        // Show how a date expression resolves, for checking what `--due` will be set to.
+        Commands::ParseDate { expression, now, due } => {
+            log::trace!("Found instruction Commands::ParseDate");
+            let now = now.unwrap_or_else(|| Local::now().fixed_offset());
+            log::debug!("Parsing \"{}\" relative to {}", expression, now);
+
+            let parsed = if due {
+                dates::parse_due_date_expression(&expression, now)
+            } else {
+                dates::parse_date_expression(&expression, now)
+            };
+            let parsed = parsed.unwrap_or_else(|e| {
+                log::error!("Failed to parse date: {}", e);
+                std::process::exit(1);
+            });
+
+            if output != OutputFormat::Text {
+                output::print_records(output, &[DateRecord::new(expression, parsed)]).unwrap_or_else(|e| {
+                    log::error!("Failed to write date: {}", e);
+                    std::process::exit(1);
+                });
+                std::process::exit(0);
+            }
+
+            println!("Local: {}", parsed.format("%a %Y-%m-%d %H:%M:%S %:z"));
+            println!("UTC:   {}", parsed.with_timezone(&Utc).to_rfc3339());
+
+            std::process::exit(0);
+        }
+        // Apply pending schema migrations to the configured database.
+        Commands::Db { command: DbCommands::Migrate } => {
+            log::trace!("Found instruction DbCommands::Migrate");
+            log::debug!("Creating persistence layer");
+            let persistence = persistence::open_repository(&config);
+
+            if config.dry_run() {
+                match persistence.schema_status().await {
+                    Ok(Some(status)) => print!("{}", status),
+                    Ok(None) => println!("The configured storage does not use a database schema"),
+                    Err(e) => {
+                        log::error!("Failed to read schema status: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+                std::process::exit(0);
+            }
+
+            log::info!("Migrating database schema");
+            persistence.migrate()
+                       .await
+                       .unwrap_or_else(|e| {
+                           log::error!("Failed to migrate schema: {}", e);
+                           std::process::exit(1);
+                       });
+
+            println!("Schema is up to date");
+        }
+        // Show the schema version of the configured database.
+        Commands::Db { command: DbCommands::Status } => {
+            log::trace!("Found instruction DbCommands::Status");
+            log::debug!("Creating persistence layer");
+            let persistence = persistence::open_repository(&config);
+
+            log::info!("Reading schema status");
+            let status = persistence.schema_status()
+                                    .await
+                                    .unwrap_or_else(|e| {
+                                        log::error!("Failed to read schema status: {}", e);
+                                        std::process::exit(1);
+                                    });
+
+            if output != OutputFormat::Text {
+                // Storage without a database schema has no record to print.
+                let records: Vec<SchemaRecord> = status.iter().map(SchemaRecord::new).collect();
+                output::print_records(output, &records).unwrap_or_else(|e| {
+                    log::error!("Failed to write schema status: {}", e);
+                    std::process::exit(1);
+                });
+                std::process::exit(0);
+            }
+
+            match status {
+                Some(status) => print!("{}", status),
+                None => println!("The configured storage does not use a database schema"),
+            }
+
+            std::process::exit(0);
+        }
     }
 
     // Exit gracefully if we get here.
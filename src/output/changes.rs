@@ -0,0 +1,136 @@
+use crate::output::records::{ProjectRecord, Record, TaskRecord};
+use crate::output::terminal::{GREEN, RED, RESET, YELLOW};
+use crate::projects::Project;
+use crate::tasks::Task;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// Fields that change on every write and would only get in the way of the diff.
+const IGNORED_FIELDS: &[&str] = &["id", "updated_at"];
+
+/// Describes what changed between two copies of the stored tasks and projects, for
+/// `--dry-run`. Added things are marked `+`, removed ones `-` and changed ones `~`, with
+/// the fields that changed listed underneath.
+pub fn render_changes(
+    before: (&[Task], &[Project]),
+    after: (&[Task], &[Project]),
+    colour: bool,
+) -> String {
+    let tasks = |(tasks, projects): (&[Task], &[Project])| -> BTreeMap<i32, Map<String, Value>> {
+        tasks.iter().map(|task| (task.id, fields(&TaskRecord::new(task, projects)))).collect()
+    };
+    let projects = |(_, projects): (&[Task], &[Project])| -> BTreeMap<i32, Map<String, Value>> {
+        projects.iter().map(|project| (project.id, fields(&ProjectRecord::new(project)))).collect()
+    };
+
+    let mut out = String::new();
+    render_kind(&mut out, "task", TaskRecord::COLUMNS, &tasks(before), &tasks(after), colour);
+    render_kind(&mut out, "project", ProjectRecord::COLUMNS, &projects(before), &projects(after), colour);
+    out
+}
+
+fn render_kind(
+    out: &mut String,
+    kind: &str,
+    columns: &[&str],
+    before: &BTreeMap<i32, Map<String, Value>>,
+    after: &BTreeMap<i32, Map<String, Value>>,
+    colour: bool,
+) {
+    let mut ids: Vec<i32> = before.keys().chain(after.keys()).copied().collect();
+    ids.sort();
+    ids.dedup();
+
+    for id in ids {
+        let (marker, colour_code, record) = match (before.get(&id), after.get(&id)) {
+            (None, Some(added)) => ('+', GREEN, added),
+            (Some(removed), None) => ('-', RED, removed),
+            (Some(old), Some(new)) if changed_fields(columns, old, new).next().is_some() => ('~', YELLOW, new),
+            _ => continue,
+        };
+
+        let heading = format!("{} {} {} {}", marker, kind, id, text(&record["name"]));
+        if colour {
+            out.push_str(&format!("{}{}{}\n", colour_code, heading, RESET));
+        } else {
+            out.push_str(&format!("{}\n", heading));
+        }
+
+        if let (Some(old), Some(new)) = (before.get(&id), after.get(&id)) {
+            for field in changed_fields(columns, old, new) {
+                out.push_str(&format!("    {}: {} → {}\n", field, text(&old[field]), text(&new[field])));
+            }
+        }
+    }
+}
+
+fn changed_fields<'a>(
+    columns: &'a [&'a str],
+    old: &'a Map<String, Value>,
+    new: &'a Map<String, Value>,
+) -> impl Iterator<Item = &'a str> {
+    columns.iter()
+           .copied()
+           .filter(|field| !IGNORED_FIELDS.contains(field))
+           .filter(move |field| old.get(*field) != new.get(*field))
+}
+
+fn fields(record: &impl Record) -> Map<String, Value> {
+    match serde_json::to_value(record) {
+        Ok(Value::Object(fields)) => fields,
+        _ => Map::new(),
+    }
+}
+
+/// A field value as it appears in the diff: strings quoted, and nothing shown as `none`.
+fn text(value: &Value) -> String {
+    match value {
+        Value::Null => "none".to_string(),
+        Value::Array(values) => format!("[{}]", values.iter().map(text).collect::<Vec<_>>().join(", ")),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render_changes;
+    use crate::projects::{NewProject, Project};
+    use crate::tasks::{NewTask, Status, Task};
+    use chrono::{Duration, TimeZone, Utc};
+
+    fn task(id: i32, name: &str) -> Task {
+        let new_task = NewTask {
+            name: name.into(),
+            ..NewTask::default()
+        };
+        Task::from_new_task(id, new_task, Utc.with_ymd_and_hms(2026, 10, 1, 9, 0, 0).unwrap())
+    }
+
+    #[test]
+    fn additions_removals_and_changed_fields_are_listed() {
+        let project = Project::from_new_project(1, NewProject { name: "house".into(), ..NewProject::default() });
+        let before = vec![task(1, "Buy milk"), task(2, "Fix the door"), task(3, "Write report")];
+
+        let mut after = before.clone();
+        after.remove(0);
+        after[0].status = Status::Done;
+        after[0].tags = vec!["home".into()];
+        after[0].updated_at += Duration::hours(1);
+        after.push(task(4, "Call the plumber"));
+
+        let mut archived = project.clone();
+        archived.archived = true;
+
+        assert_eq!(
+            render_changes((&before, &[project]), (&after, &[archived]), false),
+            "- task 1 \"Buy milk\"\n\
+             ~ task 2 \"Fix the door\"\n\
+             \x20   status: \"todo\" → \"done\"\n\
+             \x20   tags: [] → [\"home\"]\n\
+             + task 4 \"Call the plumber\"\n\
+             ~ project 1 \"house\"\n\
+             \x20   archived: false → true\n"
+        );
+        assert_eq!(render_changes((&before, &[]), (&before, &[]), false), "");
+    }
+}
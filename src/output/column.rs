@@ -0,0 +1,104 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// A column of the `todo list` table.
+#[derive(Debug, Hash, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Column {
+    Id,
+    Status,
+    Priority,
+    Due,
+    Scheduled,
+    Name,
+    Description,
+    Project,
+    Tags,
+    Repeat,
+    Created,
+    Updated,
+    Completed,
+}
+
+impl Column {
+    /// The columns shown when neither `--columns` nor the config file picks any.
+    pub const DEFAULT: [Column; 6] = [
+        Column::Id,
+        Column::Status,
+        Column::Priority,
+        Column::Due,
+        Column::Name,
+        Column::Tags,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Column::Id => "id",
+            Column::Status => "status",
+            Column::Priority => "priority",
+            Column::Due => "due",
+            Column::Scheduled => "scheduled",
+            Column::Name => "name",
+            Column::Description => "description",
+            Column::Project => "project",
+            Column::Tags => "tags",
+            Column::Repeat => "repeat",
+            Column::Created => "created",
+            Column::Updated => "updated",
+            Column::Completed => "completed",
+        }
+    }
+
+    /// Free text columns, which are cut short first when the table is too wide.
+    pub fn is_flexible(&self) -> bool {
+        matches!(self, Column::Name | Column::Description | Column::Tags)
+    }
+}
+
+impl FromStr for Column {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "id" | "number" => Ok(Column::Id),
+            "status" => Ok(Column::Status),
+            "priority" => Ok(Column::Priority),
+            "due" => Ok(Column::Due),
+            "scheduled" => Ok(Column::Scheduled),
+            "name" => Ok(Column::Name),
+            "description" => Ok(Column::Description),
+            "project" => Ok(Column::Project),
+            "tags" => Ok(Column::Tags),
+            "repeat" | "recurrence" => Ok(Column::Repeat),
+            "created" => Ok(Column::Created),
+            "updated" => Ok(Column::Updated),
+            "completed" => Ok(Column::Completed),
+            _ => Err(anyhow!(
+                "Unknown column \"{}\", expected id, status, priority, due, scheduled, name, description, project, tags, repeat, created, updated or completed",
+                s
+            )),
+        }
+    }
+}
+
+impl Display for Column {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl TryFrom<String> for Column {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        value.parse()
+    }
+}
+
+impl From<Column> for String {
+    fn from(column: Column) -> Self {
+        column.as_str().to_string()
+    }
+}
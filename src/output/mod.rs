@@ -0,0 +1,23 @@
+mod changes;
+pub use changes::render_changes;
+
+mod column;
+pub use column::Column;
+
+mod output_format;
+pub use output_format::OutputFormat;
+
+mod records;
+pub use records::{ConfigRecord, DateRecord, DoctorRecord, HistoryRecord, ProjectRecord, Record, SchemaRecord, SearchRecord, TagRecord, TaskDetailRecord, TaskRecord, TrashRecord, SCHEMA_VERSION};
+
+mod search_results;
+pub use search_results::render_search_results;
+
+mod table;
+pub use table::Table;
+
+mod terminal;
+pub use terminal::{terminal_width, use_colour};
+
+mod writer;
+pub use writer::print_records;
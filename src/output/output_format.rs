@@ -0,0 +1,17 @@
+use clap::ValueEnum;
+
+/// How read commands print their results. Everything but `Text` follows the versioned
+/// schema in [`crate::output::Record`], so scripts don't depend on the `Display` impls.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// For people, laid out differently by each command.
+    #[default]
+    Text,
+    /// One document holding every record.
+    Json,
+    /// One record per line.
+    Jsonl,
+    Csv,
+    Tsv,
+    Yaml,
+}
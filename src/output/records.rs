@@ -0,0 +1,367 @@
+use crate::config::Config;
+use crate::journal::Batch;
+use crate::persistence::{DoctorReport, SchemaStatus};
+use crate::projects::Project;
+use crate::search::SearchHit;
+use crate::tasks::{Status, Task, TaskDetails, TaskSummary};
+use chrono::{DateTime, FixedOffset, SecondsFormat, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// The version of the records below. It goes up whenever a field is renamed, removed or
+/// changes meaning; new fields are added without changing it.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// A row of machine-readable output.
+///
+/// Records are built from the stored types field by field rather than reusing their serde
+/// impls, so changes to the storage formats never change the output.
+pub trait Record: Serialize {
+    /// What a list of these records is called in the JSON and YAML documents.
+    const KIND: &'static str;
+
+    /// The field names in the order they are serialised, used as the CSV and TSV header.
+    const COLUMNS: &'static [&'static str];
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TaskRecord {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub status: String,
+    pub priority: String,
+    pub due: Option<String>,
+    pub scheduled: Option<String>,
+    pub recurrence: Option<String>,
+    pub project: Option<String>,
+    pub parent_id: Option<i32>,
+    pub depends_on: Vec<i32>,
+    pub tags: Vec<String>,
+    pub created_at: String,
+    pub updated_at: String,
+    pub completed_at: Option<String>,
+}
+
+impl TaskRecord {
+    /// `projects` is used to look up the name of the task's project.
+    pub fn new(task: &Task, projects: &[Project]) -> Self {
+        let project = task.project_id
+                          .and_then(|id| projects.iter().find(|project| project.id == id))
+                          .map(|project| project.name.clone());
+
+        Self {
+            id: task.id,
+            name: task.name.clone(),
+            description: task.description.clone(),
+            status: task.status.as_str().to_string(),
+            priority: task.priority.to_string(),
+            due: task.due.as_ref().map(timestamp),
+            scheduled: task.scheduled.as_ref().map(timestamp),
+            recurrence: task.recurrence.as_ref().map(|recurrence| recurrence.to_string()),
+            project,
+            parent_id: task.parent_id,
+            depends_on: task.depends_on.clone(),
+            tags: task.tags.clone(),
+            created_at: timestamp(&task.created_at),
+            updated_at: timestamp(&task.updated_at),
+            completed_at: task.completed_at.as_ref().map(timestamp),
+        }
+    }
+}
+
+impl Record for TaskRecord {
+    const KIND: &'static str = "tasks";
+    const COLUMNS: &'static [&'static str] = &[
+        "id", "name", "description", "status", "priority", "due", "scheduled", "recurrence", "project",
+        "parent_id", "depends_on", "tags", "created_at", "updated_at", "completed_at",
+    ];
+}
+
+/// A task together with the tasks it is related to, as printed by `todo show`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TaskDetailRecord {
+    #[serde(flatten)]
+    pub task: TaskRecord,
+    pub parent: Option<RelatedTaskRecord>,
+    pub subtasks: Vec<RelatedTaskRecord>,
+    pub waiting_for: Vec<RelatedTaskRecord>,
+    pub blocking: Vec<RelatedTaskRecord>,
+}
+
+impl TaskDetailRecord {
+    pub fn new(details: &TaskDetails, projects: &[Project]) -> Self {
+        let related = |summaries: &[TaskSummary]| summaries.iter().map(RelatedTaskRecord::new).collect();
+
+        Self {
+            task: TaskRecord::new(&details.task, projects),
+            parent: details.parent.as_ref().map(RelatedTaskRecord::new),
+            subtasks: related(&details.subtasks),
+            waiting_for: related(&details.waiting_for),
+            blocking: related(&details.blocking),
+        }
+    }
+}
+
+impl Record for TaskDetailRecord {
+    const KIND: &'static str = "task_details";
+    const COLUMNS: &'static [&'static str] = &[
+        "id", "name", "description", "status", "priority", "due", "scheduled", "recurrence", "project",
+        "parent_id", "depends_on", "tags", "created_at", "updated_at", "completed_at",
+        "parent", "subtasks", "waiting_for", "blocking",
+    ];
+}
+
+/// Another task mentioned in a [`TaskDetailRecord`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RelatedTaskRecord {
+    pub id: i32,
+    pub name: String,
+    pub status: String,
+}
+
+impl RelatedTaskRecord {
+    pub fn new(summary: &TaskSummary) -> Self {
+        Self {
+            id: summary.id,
+            name: summary.name.clone(),
+            status: summary.status.as_str().to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProjectRecord {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub archived: bool,
+}
+
+impl ProjectRecord {
+    pub fn new(project: &Project) -> Self {
+        Self {
+            id: project.id,
+            name: project.name.clone(),
+            description: project.description.clone(),
+            archived: project.archived,
+        }
+    }
+}
+
+impl Record for ProjectRecord {
+    const KIND: &'static str = "projects";
+    const COLUMNS: &'static [&'static str] = &["id", "name", "description", "archived"];
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TagRecord {
+    pub tag: String,
+    pub tasks: i64,
+}
+
+impl Record for TagRecord {
+    const KIND: &'static str = "tags";
+    const COLUMNS: &'static [&'static str] = &["tag", "tasks"];
+}
+
+/// A task found by `todo search`. The snippet is plain text; `matches` lists the words in
+/// it that matched, in order.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchRecord {
+    pub id: i32,
+    pub name: String,
+    pub status: String,
+    pub score: f64,
+    pub snippet: String,
+    pub matches: Vec<String>,
+}
+
+impl SearchRecord {
+    pub fn new(hit: &SearchHit) -> Self {
+        Self {
+            id: hit.task.id,
+            name: hit.task.name.clone(),
+            status: hit.task.status.as_str().to_string(),
+            score: hit.score,
+            snippet: hit.plain_snippet(),
+            matches: hit.matches(),
+        }
+    }
+}
+
+impl Record for SearchRecord {
+    const KIND: &'static str = "results";
+    const COLUMNS: &'static [&'static str] = &["id", "name", "status", "score", "snippet", "matches"];
+}
+
+/// A parsed date expression, as printed by `todo parse-date`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DateRecord {
+    pub expression: String,
+    pub local: String,
+    pub utc: String,
+}
+
+impl DateRecord {
+    pub fn new(expression: String, parsed: DateTime<FixedOffset>) -> Self {
+        Self {
+            expression,
+            local: parsed.to_rfc3339_opts(SecondsFormat::Secs, true),
+            utc: timestamp(&parsed.with_timezone(&Utc)),
+        }
+    }
+}
+
+impl Record for DateRecord {
+    const KIND: &'static str = "dates";
+    const COLUMNS: &'static [&'static str] = &["expression", "local", "utc"];
+}
+
+/// A task in the trash, as listed by `todo trash list`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TrashRecord {
+    pub id: i32,
+    pub name: String,
+    pub status: String,
+    pub deleted_at: Option<String>,
+}
+
+impl TrashRecord {
+    pub fn new(task: &Task) -> Self {
+        Self {
+            id: task.id,
+            name: task.name.clone(),
+            status: task.status.as_str().to_string(),
+            deleted_at: task.deleted_at.as_ref().map(timestamp),
+        }
+    }
+}
+
+impl Record for TrashRecord {
+    const KIND: &'static str = "trash";
+    const COLUMNS: &'static [&'static str] = &["id", "name", "status", "deleted_at"];
+}
+
+/// A run of `todo` in the journal, as listed by `todo history`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HistoryRecord {
+    pub command: String,
+    pub created_at: String,
+    pub tasks: usize,
+    pub undone: bool,
+}
+
+impl HistoryRecord {
+    pub fn new(batch: &Batch) -> Self {
+        Self {
+            command: batch.command().to_string(),
+            created_at: timestamp(&batch.created_at()),
+            tasks: batch.before().len(),
+            undone: batch.undone(),
+        }
+    }
+}
+
+impl Record for HistoryRecord {
+    const KIND: &'static str = "history";
+    const COLUMNS: &'static [&'static str] = &["command", "created_at", "tasks", "undone"];
+}
+
+/// The schema of the configured database, as printed by `todo db status`. `pending` lists
+/// the versions of the migrations that have not been applied.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SchemaRecord {
+    pub current_version: Option<i64>,
+    pub latest_version: i64,
+    pub up_to_date: bool,
+    pub pending: Vec<i64>,
+}
+
+impl SchemaRecord {
+    pub fn new(status: &SchemaStatus) -> Self {
+        Self {
+            current_version: status.current_version,
+            latest_version: status.latest_version,
+            up_to_date: status.is_up_to_date(),
+            pending: status.pending.iter().map(|(version, _)| *version).collect(),
+        }
+    }
+}
+
+impl Record for SchemaRecord {
+    const KIND: &'static str = "schema";
+    const COLUMNS: &'static [&'static str] = &["current_version", "latest_version", "up_to_date", "pending"];
+}
+
+/// What `todo doctor` found and repaired.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DoctorRecord {
+    pub healthy: bool,
+    pub problems: Vec<String>,
+    pub repairs: Vec<String>,
+}
+
+impl DoctorRecord {
+    pub fn new(report: &DoctorReport) -> Self {
+        Self {
+            healthy: report.is_healthy(),
+            problems: report.problems.clone(),
+            repairs: report.repairs.clone(),
+        }
+    }
+}
+
+impl Record for DoctorRecord {
+    const KIND: &'static str = "doctor";
+    const COLUMNS: &'static [&'static str] = &["healthy", "problems", "repairs"];
+}
+
+/// The settings in use, as shown by `todo config`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConfigRecord {
+    pub storage: String,
+    pub columns: Vec<String>,
+    /// Where each status can move to, including the defaults for statuses the config leaves out.
+    pub transitions: BTreeMap<String, Vec<String>>,
+    pub backup_directory: Option<String>,
+    pub backups_kept: usize,
+    pub daily_backups_kept: usize,
+    /// 0 when removed tasks are kept until the trash is emptied.
+    pub trash_days: u32,
+}
+
+impl ConfigRecord {
+    pub fn new(config: &Config) -> Self {
+        let transitions = Status::ALL.into_iter()
+                                     .map(|from| {
+                                         let to = Status::ALL.into_iter()
+                                                             .filter(|to| config.transitions().allows(from, *to))
+                                                             .map(|to| to.as_str().to_string())
+                                                             .collect();
+                                         (from.as_str().to_string(), to)
+                                     })
+                                     .collect();
+
+        Self {
+            storage: config.storage().to_string(),
+            columns: config.columns().iter().map(|column| column.as_str().to_string()).collect(),
+            transitions,
+            backup_directory: config.backups().directory().ok().map(|directory| directory.to_string_lossy().into_owned()),
+            backups_kept: config.backups().keep_last,
+            daily_backups_kept: config.backups().keep_daily,
+            trash_days: config.trash().keep_days,
+        }
+    }
+}
+
+impl Record for ConfigRecord {
+    const KIND: &'static str = "config";
+    const COLUMNS: &'static [&'static str] =
+        &["storage", "columns", "transitions", "backup_directory", "backups_kept", "daily_backups_kept", "trash_days"];
+}
+
+/// Timestamps are written in UTC to the second, such as `2026-11-06T17:00:00Z`.
+fn timestamp(date_time: &DateTime<Utc>) -> String {
+    date_time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
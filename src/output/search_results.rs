@@ -0,0 +1,65 @@
+use crate::output::terminal::{BOLD, RESET, YELLOW};
+use crate::search::SearchHit;
+
+/// Lays out the results of `todo search`, one task per line with its matching words
+/// highlighted. When the match is in the description, the snippet goes on the next line.
+pub fn render_search_results(hits: &[SearchHit], colour: bool) -> String {
+    let id_width = hits.iter().map(|hit| hit.task.id.to_string().len()).max().unwrap_or(0);
+    let status_width = hits.iter().map(|hit| status(hit).chars().count()).max().unwrap_or(0);
+    let indent = " ".repeat(id_width + status_width + 4);
+    let highlight_start = if colour { format!("{}{}", BOLD, YELLOW) } else { "[".to_string() };
+    let highlight_end = if colour { RESET } else { "]" };
+
+    let mut out = String::new();
+    for hit in hits {
+        let snippet = hit.highlight(&highlight_start, highlight_end);
+        let prefix = format!("{:>id_width$}  {:<status_width$}  ", hit.task.id, status(hit));
+
+        if hit.plain_snippet() == hit.task.name {
+            out.push_str(&format!("{}{}\n", prefix, snippet));
+        } else {
+            out.push_str(&format!("{}{}\n{}{}\n", prefix, hit.task.name, indent, snippet));
+        }
+    }
+
+    out
+}
+
+fn status(hit: &SearchHit) -> String {
+    format!("{} {}", hit.task.status.glyph(), hit.task.status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render_search_results;
+    use crate::search::SearchHit;
+    use crate::tasks::{NewTask, Status, Task};
+    use chrono::Utc;
+
+    fn hit(id: i32, name: &str, snippet: &str) -> SearchHit {
+        let new_task = NewTask {
+            name: name.into(),
+            ..NewTask::default()
+        };
+        SearchHit {
+            task: Task::from_new_task(id, new_task, Utc::now()),
+            score: 1.0,
+            snippet: snippet.into(),
+        }
+    }
+
+    #[test]
+    fn description_matches_go_under_the_name() {
+        let mut done = hit(12, "Check the load balancer", "The \u{2}TLS\u{3} certificate runs out…");
+        done.task.status = Status::Done;
+        let hits = [hit(3, "Renew the TLS cert", "Renew the \u{2}TLS\u{3} cert"), done];
+
+        assert_eq!(
+            render_search_results(&hits, false),
+            " 3  ☐ todo  Renew the [TLS] cert\n\
+             12  ☑ done  Check the load balancer\n\
+             \x20           The [TLS] certificate runs out…\n"
+        );
+        assert!(render_search_results(&hits, true).contains("Renew the \x1b[1m\x1b[33mTLS\x1b[0m cert"));
+    }
+}
@@ -0,0 +1,290 @@
+use crate::dates::format_date_time;
+use crate::output::terminal::{BOLD, DIM, RED, RESET, YELLOW};
+use crate::output::Column;
+use crate::tasks::{Priority, Task};
+use chrono::{DateTime, Utc};
+
+const SEPARATOR: &str = "  ";
+const MIN_FLEXIBLE_WIDTH: usize = 8;
+
+/// How a row is highlighted when colour is on.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum RowStyle {
+    #[default]
+    Plain,
+    Important,
+    Overdue,
+    Closed,
+}
+
+impl RowStyle {
+    fn for_task(task: &Task, now: DateTime<Utc>) -> Self {
+        if !task.is_open() {
+            RowStyle::Closed
+        } else if task.is_overdue(now) {
+            RowStyle::Overdue
+        } else if task.priority >= Priority::High {
+            RowStyle::Important
+        } else {
+            RowStyle::Plain
+        }
+    }
+
+    fn escape(&self) -> Option<String> {
+        match self {
+            RowStyle::Plain => None,
+            RowStyle::Important => Some(format!("{}{}", BOLD, YELLOW)),
+            RowStyle::Overdue => Some(RED.to_string()),
+            RowStyle::Closed => Some(DIM.to_string()),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Row {
+    cells: Vec<String>,
+    style: RowStyle,
+}
+
+#[derive(Debug)]
+struct Section {
+    heading: Option<String>,
+    rows: Vec<Row>,
+}
+
+/// The task table printed by `todo list`. Rows can be split into sections under their own
+/// headings, which share one set of column widths.
+#[derive(Debug)]
+pub struct Table {
+    columns: Vec<Column>,
+    sections: Vec<Section>,
+}
+
+impl Table {
+    pub fn new(columns: Vec<Column>) -> Self {
+        Self {
+            columns,
+            sections: vec![],
+        }
+    }
+
+    /// Starts a new section; rows added after this go below `heading`.
+    pub fn section(&mut self, heading: Option<String>) {
+        self.sections.push(Section { heading, rows: vec![] });
+    }
+
+    /// Adds a row for `task`, with the name indented `depth` levels and followed by `note`.
+    pub fn add_task(&mut self, task: &Task, depth: usize, note: &str, project: Option<&str>, now: DateTime<Utc>) {
+        let cells = self.columns.iter()
+                                .map(|column| task_cell(*column, task, depth, note, project, now))
+                                .collect();
+        let row = Row {
+            cells,
+            style: RowStyle::for_task(task, now),
+        };
+
+        match self.sections.last_mut() {
+            Some(section) => section.rows.push(row),
+            None => self.sections.push(Section { heading: None, rows: vec![row] }),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sections.iter().all(|section| section.rows.is_empty())
+    }
+
+    /// Lays the table out, cutting free text columns short to fit in `width` if given.
+    pub fn render(&self, width: Option<usize>, colour: bool) -> String {
+        let headers: Vec<String> = self.columns.iter().map(|column| column.as_str().to_uppercase()).collect();
+        let widths = self.column_widths(&headers, width);
+
+        let mut out = String::new();
+        push_line(&mut out, &format_cells(&headers, &widths), colour.then_some(BOLD));
+
+        for (index, section) in self.sections.iter().enumerate() {
+            if let Some(heading) = &section.heading {
+                if index > 0 {
+                    out.push('\n');
+                }
+                push_line(&mut out, heading, colour.then_some(BOLD));
+            }
+
+            for row in section.rows.iter() {
+                let escape = if colour { row.style.escape() } else { None };
+                push_line(&mut out, &format_cells(&row.cells, &widths), escape.as_deref());
+            }
+        }
+
+        out
+    }
+
+    fn column_widths(&self, headers: &[String], limit: Option<usize>) -> Vec<usize> {
+        let mut widths: Vec<usize> = headers.iter().map(|header| header.chars().count()).collect();
+        for row in self.sections.iter().flat_map(|section| section.rows.iter()) {
+            for (width, cell) in widths.iter_mut().zip(row.cells.iter()) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        let Some(limit) = limit else {
+            return widths;
+        };
+
+        // Take one character at a time from the widest free text column until the table fits.
+        let separators = SEPARATOR.len() * widths.len().saturating_sub(1);
+        while widths.iter().sum::<usize>() + separators > limit {
+            let widest = self.columns.iter()
+                                     .enumerate()
+                                     .filter(|(index, column)| column.is_flexible() && widths[*index] > MIN_FLEXIBLE_WIDTH)
+                                     .max_by_key(|(index, _)| widths[*index])
+                                     .map(|(index, _)| index);
+            match widest {
+                Some(index) => widths[index] -= 1,
+                None => break,
+            }
+        }
+
+        widths
+    }
+}
+
+fn task_cell(column: Column, task: &Task, depth: usize, note: &str, project: Option<&str>, now: DateTime<Utc>) -> String {
+    match column {
+        Column::Id => task.id.to_string(),
+        Column::Status => format!("{} {}", task.status.glyph(), task.status),
+        Column::Priority => match task.priority {
+            Priority::None => String::new(),
+            priority => priority.to_string(),
+        },
+        Column::Due => match &task.due {
+            Some(due) if task.is_overdue(now) => format!("{} !", format_date_time(due)),
+            Some(due) => format_date_time(due),
+            None => String::new(),
+        },
+        Column::Scheduled => task.scheduled.as_ref().map(format_date_time).unwrap_or_default(),
+        Column::Name => format!("{}{}{}", "  ".repeat(depth), task.name, note),
+        Column::Description => task.description().unwrap_or_default().lines().next().unwrap_or_default().to_string(),
+        Column::Project => project.unwrap_or_default().to_string(),
+        Column::Tags => task.tags.iter().map(|tag| format!("+{}", tag)).collect::<Vec<_>>().join(" "),
+        Column::Repeat => task.recurrence.as_ref().map(|recurrence| recurrence.to_string()).unwrap_or_default(),
+        Column::Created => format_date_time(&task.created_at),
+        Column::Updated => format_date_time(&task.updated_at),
+        Column::Completed => task.completed_at.as_ref().map(format_date_time).unwrap_or_default(),
+    }
+}
+
+/// Pads every cell but the last to its column's width, cutting off anything longer.
+fn format_cells(cells: &[String], widths: &[usize]) -> String {
+    let last = cells.len().saturating_sub(1);
+    let cells: Vec<String> = cells.iter()
+                                  .zip(widths.iter())
+                                  .enumerate()
+                                  .map(|(index, (cell, width))| {
+                                      let cell = truncate(cell, *width);
+                                      if index == last { cell } else { format!("{:<width$}", cell) }
+                                  })
+                                  .collect();
+
+    cells.join(SEPARATOR).trim_end().to_string()
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+
+    let mut truncated: String = text.chars().take(width.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
+
+fn push_line(out: &mut String, line: &str, escape: Option<&str>) {
+    match escape {
+        Some(escape) => out.push_str(&format!("{}{}{}\n", escape, line, RESET)),
+        None => {
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Table;
+    use crate::dates::{parse_due_date_time, start_of_local_day};
+    use crate::output::Column;
+    use crate::tasks::{NewTask, Priority, Status, Task};
+    use chrono::{Duration, Utc};
+
+    fn task(id: i32, name: &str) -> Task {
+        let new_task = NewTask {
+            name: name.into(),
+            ..NewTask::default()
+        };
+        Task::from_new_task(id, new_task, Utc::now())
+    }
+
+    fn table() -> Table {
+        let now = Utc::now();
+        let mut urgent = task(2, "Renew the passport before the summer holidays");
+        urgent.priority = Priority::Urgent;
+        let mut done = task(10, "Buy milk");
+        done.status = Status::Done;
+
+        let mut table = Table::new(vec![Column::Id, Column::Status, Column::Priority, Column::Name]);
+        table.add_task(&urgent, 0, "", None, now);
+        table.add_task(&done, 1, " (1/2 done)", None, now);
+        table
+    }
+
+    #[test]
+    fn columns_are_aligned() {
+        let rendered = table().render(None, false);
+        assert_eq!(
+            rendered,
+            "ID  STATUS  PRIORITY  NAME\n\
+             2   ☐ todo  urgent    Renew the passport before the summer holidays\n\
+             10  ☑ done              Buy milk (1/2 done)\n"
+        );
+    }
+
+    #[test]
+    fn free_text_is_cut_short_to_fit() {
+        let rendered = table().render(Some(40), false);
+        for line in rendered.lines() {
+            assert!(line.chars().count() <= 40, "{:?} is too long", line);
+        }
+        assert!(rendered.contains("2   ☐ todo  urgent    Renew the passpor…\n"));
+    }
+
+    #[test]
+    fn rows_are_coloured_by_state() {
+        let mut overdue = task(3, "Pay rent");
+        overdue.due = Some(Utc::now() - Duration::days(1));
+        let mut table = table();
+        table.add_task(&overdue, 0, "", None, Utc::now());
+
+        let lines: Vec<_> = table.render(None, true).lines().map(String::from).collect();
+        assert!(lines[0].starts_with("\x1b[1m"));
+        assert!(lines[1].starts_with("\x1b[1m\x1b[33m"));
+        assert!(lines[2].starts_with("\x1b[2m"));
+        assert!(lines[3].starts_with("\x1b[31m"));
+        assert!(lines.iter().all(|line| line.ends_with("\x1b[0m")));
+    }
+
+    #[test]
+    fn tasks_due_today_are_not_overdue() {
+        let now = Utc::now();
+        let mut due_tonight = task(3, "Pay rent");
+        due_tonight.due = Some(parse_due_date_time("today").unwrap());
+        let mut due_at_midnight = task(4, "Water the plants");
+        due_at_midnight.due = start_of_local_day(now);
+        let mut table = Table::new(vec![Column::Id, Column::Due]);
+        table.add_task(&due_tonight, 0, "", None, now);
+        table.add_task(&due_at_midnight, 0, "", None, now);
+
+        let rendered = table.render(None, true);
+        assert!(!rendered.contains('!'), "{:?}", rendered);
+        assert!(!rendered.contains("\x1b[31m"), "{:?}", rendered);
+    }
+}
@@ -0,0 +1,41 @@
+use std::io::IsTerminal;
+
+pub const BOLD: &str = "\x1b[1m";
+pub const DIM: &str = "\x1b[2m";
+pub const RED: &str = "\x1b[31m";
+pub const GREEN: &str = "\x1b[32m";
+pub const YELLOW: &str = "\x1b[33m";
+pub const RESET: &str = "\x1b[0m";
+
+/// How wide the terminal is, or `None` when output is not going to a terminal.
+///
+/// `COLUMNS` wins when it is set, so the width can be forced for a single command.
+pub fn terminal_width() -> Option<usize> {
+    if !std::io::stdout().is_terminal() {
+        return None;
+    }
+
+    std::env::var("COLUMNS").ok()
+                            .and_then(|columns| columns.parse().ok())
+                            .or_else(query_width)
+                            .or(Some(80))
+}
+
+/// Whether to colour output: only on a terminal, and never when `NO_COLOR` is set.
+pub fn use_colour() -> bool {
+    std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()) && std::io::stdout().is_terminal()
+}
+
+#[cfg(unix)]
+fn query_width() -> Option<usize> {
+    let mut size = libc::winsize { ws_row: 0, ws_col: 0, ws_xpixel: 0, ws_ypixel: 0 };
+    // SAFETY: TIOCGWINSZ only writes a `winsize` through the pointer, which outlives the call.
+    let result = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) };
+
+    (result == 0 && size.ws_col > 0).then_some(size.ws_col as usize)
+}
+
+#[cfg(not(unix))]
+fn query_width() -> Option<usize> {
+    None
+}
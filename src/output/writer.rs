@@ -0,0 +1,226 @@
+use crate::output::{OutputFormat, Record, SCHEMA_VERSION};
+use anyhow::{bail, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::io::Write;
+
+/// The JSON and YAML documents wrap the records so readers can check the version first.
+#[derive(Serialize)]
+struct Document<'a, R: Record> {
+    schema_version: u32,
+    kind: &'static str,
+    items: &'a [R],
+}
+
+pub fn print_records<R: Record>(format: OutputFormat, records: &[R]) -> Result<()> {
+    let stdout = std::io::stdout();
+    write_records(format, records, &mut stdout.lock())
+}
+
+/// Writes `records` in a machine-readable format. Text output is up to each command.
+pub fn write_records<R: Record>(format: OutputFormat, records: &[R], out: &mut impl Write) -> Result<()> {
+    let document = Document {
+        schema_version: SCHEMA_VERSION,
+        kind: R::KIND,
+        items: records,
+    };
+
+    match format {
+        OutputFormat::Text => bail!("Text output has no schema"),
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut *out, &document)?;
+            writeln!(out)?;
+        }
+        OutputFormat::Jsonl => {
+            for record in records {
+                serde_json::to_writer(&mut *out, record)?;
+                writeln!(out)?;
+            }
+        }
+        OutputFormat::Yaml => serde_yaml::to_writer(&mut *out, &document)?,
+        OutputFormat::Csv => write_delimited(b',', records, out)?,
+        OutputFormat::Tsv => write_delimited(b'\t', records, out)?,
+    }
+
+    out.flush()?;
+    Ok(())
+}
+
+/// Writes one row per record under a header of [`Record::COLUMNS`]. Lists are joined
+/// with `;`, related tasks are written as their id and missing values are left empty.
+fn write_delimited<R: Record>(delimiter: u8, records: &[R], out: &mut impl Write) -> Result<()> {
+    let mut writer = csv::WriterBuilder::new().delimiter(delimiter).from_writer(out);
+    writer.write_record(R::COLUMNS)?;
+
+    for record in records {
+        let value = serde_json::to_value(record)?;
+        let row: Vec<String> = R::COLUMNS.iter()
+                                         .map(|column| cell(value.get(column).unwrap_or(&Value::Null)))
+                                         .collect();
+        writer.write_record(&row)?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        Value::Array(values) => values.iter().map(cell).collect::<Vec<_>>().join(";"),
+        Value::Object(fields) if fields.contains_key("id") => cell(&fields["id"]),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::write_records;
+    use crate::config::Config;
+    use crate::output::{ConfigRecord, DateRecord, DoctorRecord, OutputFormat, ProjectRecord, Record, SchemaRecord, TagRecord, TaskDetailRecord, TaskRecord};
+    use crate::persistence::DoctorReport;
+    use crate::projects::Project;
+    use crate::tasks::{NewTask, Priority, Status, Task, TaskDetails};
+    use chrono::{Local, TimeZone, Utc};
+    use serde_json::Value;
+
+    /// Compares output with the file in `src/output/snapshots`. Run the tests with
+    /// `UPDATE_SNAPSHOTS=1` to rewrite the files after a deliberate schema change, and bump
+    /// `SCHEMA_VERSION` if an existing field changed.
+    fn assert_snapshot(name: &str, actual: &str) {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src/output/snapshots").join(name);
+        if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            std::fs::write(&path, actual).unwrap();
+        }
+
+        let expected = std::fs::read_to_string(&path).unwrap_or_default();
+        assert_eq!(actual, expected, "output differs from {}", path.to_string_lossy());
+    }
+
+    fn render<R: Record>(format: OutputFormat, records: &[R]) -> String {
+        let mut out = vec![];
+        write_records(format, records, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn tasks() -> Vec<TaskRecord> {
+        let projects = vec![Project { id: 1, name: "house".into(), description: None, archived: false }];
+        stored_tasks().iter().map(|task| TaskRecord::new(task, &projects)).collect()
+    }
+
+    fn stored_tasks() -> Vec<Task> {
+        let created = Utc.with_ymd_and_hms(2026, 10, 1, 9, 30, 0).unwrap();
+
+        let new_task = NewTask {
+            name: "Fix the door".into(),
+            description: Some("The hinge, \"again\"".into()),
+            due: Some(Utc.with_ymd_and_hms(2026, 11, 6, 17, 0, 0).unwrap()),
+            priority: Priority::High,
+            project_id: Some(1),
+            recurrence: Some("every 2 weeks".parse().unwrap()),
+            tags: vec!["diy".into(), "home".into()],
+            ..NewTask::default()
+        };
+        let first = Task::from_new_task(1, new_task, created);
+
+        let new_task = NewTask {
+            name: "Buy screws".into(),
+            parent_id: Some(1),
+            ..NewTask::default()
+        };
+        let mut second = Task::from_new_task(2, new_task, created);
+        second.status = Status::Done;
+        second.depends_on = vec![1];
+        second.completed_at = Some(Utc.with_ymd_and_hms(2026, 10, 2, 12, 0, 0).unwrap());
+
+        vec![first, second]
+    }
+
+    fn details(id: i32) -> TaskDetailRecord {
+        let tasks = stored_tasks();
+        let task = tasks.iter().find(|task| task.id == id).unwrap().clone();
+        let projects = vec![Project { id: 1, name: "house".into(), description: None, archived: false }];
+        TaskDetailRecord::new(&TaskDetails::new(task, &tasks, None), &projects)
+    }
+
+    #[test]
+    fn task_output_matches_the_snapshots() {
+        let tasks = tasks();
+        assert_snapshot("tasks.json", &render(OutputFormat::Json, &tasks));
+        assert_snapshot("tasks.jsonl", &render(OutputFormat::Jsonl, &tasks));
+        assert_snapshot("tasks.csv", &render(OutputFormat::Csv, &tasks));
+        assert_snapshot("tasks.tsv", &render(OutputFormat::Tsv, &tasks));
+        assert_snapshot("tasks.yaml", &render(OutputFormat::Yaml, &tasks));
+    }
+
+    #[test]
+    fn task_details_include_related_tasks() {
+        let first = details(1);
+        assert_eq!(first.subtasks.iter().map(|task| task.id).collect::<Vec<_>>(), [2]);
+        assert_eq!(first.blocking.iter().map(|task| task.id).collect::<Vec<_>>(), [2]);
+        assert_snapshot("task_details.json", &render(OutputFormat::Json, &[details(2)]));
+    }
+
+    #[test]
+    fn other_output_matches_the_snapshots() {
+        let projects = vec![ProjectRecord { id: 1, name: "house".into(), description: Some("Jobs, big and small".into()), archived: true }];
+        let tags = vec![TagRecord { tag: "home".into(), tasks: 3 }];
+        assert_snapshot("projects.csv", &render(OutputFormat::Csv, &projects));
+        assert_snapshot("tags.json", &render(OutputFormat::Json, &tags));
+    }
+
+    fn config() -> Config {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("config.toml");
+        std::fs::write(&path, "storage = \"sqlite:///home/user/todo.db\"\n\
+                               columns = [\"id\", \"status\", \"due\", \"name\"]\n\
+                               [transitions]\n\
+                               done = []\n\
+                               [backups]\n\
+                               directory = \"/home/user/backups\"\n\
+                               keep_last = 3\n\
+                               keep_daily = 2\n\
+                               [trash]\n\
+                               keep_days = 0\n").unwrap();
+        Config::from_path(&path).unwrap()
+    }
+
+    #[test]
+    fn config_output_matches_the_snapshots() {
+        let config = [ConfigRecord::new(&config())];
+        assert_eq!(config[0].transitions["done"], Vec::<String>::new());
+        assert_eq!(config[0].transitions["cancelled"], ["todo"]);
+        assert_snapshot("config.json", &render(OutputFormat::Json, &config));
+    }
+
+    #[test]
+    fn empty_lists_still_have_a_header() {
+        assert_eq!(render::<TagRecord>(OutputFormat::Csv, &[]), "tag,tasks\n");
+    }
+
+    fn assert_columns<R: Record>(record: &R) {
+        let Value::Object(fields) = serde_json::to_value(record).unwrap() else {
+            panic!("{} serialise as objects", R::KIND);
+        };
+
+        let mut columns = R::COLUMNS.to_vec();
+        columns.sort();
+        let mut keys: Vec<_> = fields.keys().map(String::as_str).collect();
+        keys.sort();
+        assert_eq!(columns, keys, "columns of {}", R::KIND);
+    }
+
+    #[test]
+    fn columns_match_the_serialised_fields() {
+        let now = Local::now().fixed_offset();
+        assert_columns(&tasks()[0]);
+        assert_columns(&ProjectRecord::new(&Project { id: 1, name: "house".into(), description: None, archived: false }));
+        assert_columns(&TagRecord { tag: "home".into(), tasks: 1 });
+        assert_columns(&DateRecord::new("now".into(), now));
+        assert_columns(&details(2));
+        assert_columns(&SchemaRecord { current_version: Some(12), latest_version: 12, up_to_date: true, pending: vec![] });
+        assert_columns(&DoctorRecord::new(&DoctorReport::default()));
+        assert_columns(&ConfigRecord::new(&config()));
+    }
+}
@@ -0,0 +1,593 @@
+use super::file_repository::{FileOptions, FileRepository};
+use super::memory_repository::MemoryRepository;
+use super::transfer::{all_tasks, comparable, restore, transfer};
+use super::Repository;
+use crate::error::{DependencyCycleError, TaskNotFoundError};
+use crate::journal::{JournalEntry, NewJournalEntry, TaskChange, KEPT_BATCHES};
+use crate::projects::{NewProject, Project};
+use crate::query::parse_query;
+use crate::search::SearchTerms;
+use crate::tasks::{NewTask, Priority, Status, Task, TaskFilter};
+use anyhow::Error;
+use chrono::{DateTime, Duration, SubsecRound, TimeZone, Utc};
+
+/// Queries and the names of the tasks every backend must return for them.
+const QUERIES: &[(&str, &[&str])] = &[
+    ("status:open", &["Call the_plumber", "Deploy release", "Write report"]),
+    ("status:closed", &["Buy milk", "Fix the door"]),
+    ("status=waiting", &["Call the_plumber"]),
+    ("tag:ops or priority>=high", &["Call the_plumber", "Deploy release"]),
+    ("not tag:home", &["Buy milk", "Deploy release", "Write report"]),
+    ("tag:none", &["Buy milk"]),
+    ("project:house", &["Deploy release", "Fix the door"]),
+    ("project!=house", &["Buy milk", "Call the_plumber", "Write report"]),
+    ("project:none", &["Buy milk", "Call the_plumber", "Write report"]),
+    ("due<2026-11-01", &["Call the_plumber", "Deploy release"]),
+    ("not due<2026-11-01", &["Buy milk", "Fix the door", "Write report"]),
+    ("due:2026-10-20", &["Call the_plumber", "Deploy release"]),
+    ("due>2026-10-20 and due<=2026-11-05", &["Write report"]),
+    ("due:none", &["Buy milk", "Fix the door"]),
+    ("completed!=none", &["Fix the door"]),
+    ("name:the", &["Call the_plumber", "Fix the door"]),
+    ("name:the_", &["Call the_plumber"]),
+    ("REPORT", &["Write report"]),
+    ("description:100%", &["Write report"]),
+    ("name=\"buy milk\"", &["Buy milk"]),
+    ("description!=numbers", &["Buy milk", "Call the_plumber", "Deploy release", "Fix the door", "Write report"]),
+    ("description:ÖLHEIZUNG", &["Call the_plumber"]),
+    ("description=\"ask about the ölheizung\" or name:milk", &["Buy milk", "Call the_plumber"]),
+    ("parent!=none", &["Buy milk"]),
+    ("status:open and (tag:ops or priority>=high) and due<2026-11-01", &["Call the_plumber", "Deploy release"]),
+];
+
+/// Searches and the names of the tasks every backend must return for them, best first.
+const SEARCHES: &[(&str, &[&str])] = &[
+    ("report", &["Write report", "Fix the door"]),
+    ("tls", &["Deploy release"]),
+    ("PLUMB", &["Call the_plumber"]),
+    ("numbers done", &["Write report"]),
+    ("door milk", &[]),
+];
+
+/// Runs every check that a `Repository` has to pass against a backend, calling `open` for
+/// empty storage before each one.
+///
+/// Ids start at 1 and every new task gets a higher id than any task that exists; whether
+/// the id of the newest task comes back after it is removed is up to the backend.
+pub async fn check_backend<R: Repository + Sync>(backend: &str, open: impl AsyncFn() -> R) {
+    ids_count_up_from_one(backend, &open().await).await;
+    new_tasks_start_out_todo(backend, &open().await).await;
+    missing_tasks_are_not_found(backend, &open().await).await;
+    updates_replace_everything_but_the_creation_time(backend, &open().await).await;
+    failed_updates_change_nothing(backend, &open().await).await;
+    removing_a_task_lets_go_of_its_subtasks_and_dependents(backend, &open().await).await;
+    removed_tasks_wait_in_the_trash_until_purged(backend, &open().await).await;
+    deleted_tasks_are_gone_for_good(backend, &open().await).await;
+    the_trash_survives_a_migration_and_a_restore(backend, &open().await).await;
+    lists_come_back_in_order(backend, &open().await).await;
+    projects_have_unique_names(backend, &open().await).await;
+    imports_keep_ids_and_timestamps(backend, &open().await).await;
+    the_journal_keeps_recent_batches_until_they_are_undone(backend, &open().await).await;
+
+    let repository = open().await;
+    add_fixture(&repository).await;
+    queries_match_the_same_tasks(backend, &repository).await;
+    searches_rank_the_same_tasks(backend, &repository).await;
+}
+
+fn new_task(name: &str) -> NewTask {
+    NewTask {
+        name: name.into(),
+        ..NewTask::default()
+    }
+}
+
+fn date(day: u32, hour: u32) -> Option<DateTime<Utc>> {
+    Some(Utc.with_ymd_and_hms(2026, 10, 1, hour, 0, 0).unwrap() + chrono::Days::new(day as u64 - 1))
+}
+
+fn assert_not_found(backend: &str, operation: &str, result: Result<impl std::fmt::Debug, Error>, id: i32) {
+    let error = result.expect_err(&format!("{} backend, {} should fail", backend, operation));
+    let not_found = error.downcast_ref::<TaskNotFoundError>()
+                         .unwrap_or_else(|| panic!("{} backend, {} failed with {:?}", backend, operation, error));
+    assert_eq!(not_found.id, id, "{} backend, {}", backend, operation);
+    assert_eq!(error.to_string(), format!("No task with id {} found", id), "{} backend, {}", backend, operation);
+}
+
+async fn ids(repository: &(dyn Repository + Sync)) -> Vec<i32> {
+    repository.get_all().await.unwrap().into_iter().map(|task| task.id).collect()
+}
+
+async fn ids_count_up_from_one(backend: &str, repository: &(dyn Repository + Sync)) {
+    for name in ["first", "second", "third"] {
+        repository.add(new_task(name)).await.unwrap();
+    }
+    assert_eq!(ids(repository).await, [1, 2, 3], "{} backend", backend);
+
+    repository.remove(2).await.unwrap();
+    repository.add(new_task("fourth")).await.unwrap();
+    assert_eq!(ids(repository).await, [1, 3, 4], "{} backend, an id in the middle is never reused", backend);
+    assert_eq!(repository.get_by_id(4).await.unwrap().name, "fourth", "{} backend", backend);
+}
+
+async fn new_tasks_start_out_todo(backend: &str, repository: &(dyn Repository + Sync)) {
+    let before = Utc::now() - Duration::seconds(1);
+    repository.add(NewTask {
+        tags: vec!["b".into(), "a".into(), "b".into()],
+        ..new_task("first")
+    }).await.unwrap();
+    let after = Utc::now() + Duration::seconds(1);
+
+    let task = repository.get_by_id(1).await.unwrap();
+    assert_eq!(task.status, Status::Todo, "{} backend", backend);
+    assert_eq!(task.created_at, task.updated_at, "{} backend", backend);
+    assert!(before <= task.created_at && task.created_at <= after, "{} backend, created at {}", backend, task.created_at);
+    assert_eq!(task.completed_at, None, "{} backend", backend);
+    assert_eq!(task.tags, ["a", "b"], "{} backend, tags are sorted without duplicates", backend);
+    assert!(task.depends_on.is_empty(), "{} backend", backend);
+}
+
+async fn missing_tasks_are_not_found(backend: &str, repository: &(dyn Repository + Sync)) {
+    repository.add(new_task("first")).await.unwrap();
+    let mut missing = repository.get_by_id(1).await.unwrap();
+    missing.id = 99;
+
+    assert_not_found(backend, "getting a missing task", repository.get_by_id(99).await, 99);
+    assert_not_found(backend, "updating a missing task", repository.update(missing).await, 99);
+    assert_not_found(backend, "removing a missing task", repository.remove(99).await, 99);
+    assert_not_found(backend, "adding a subtask of a missing task", repository.add(NewTask {
+        parent_id: Some(99),
+        ..new_task("orphan")
+    }).await, 99);
+
+    let mut task = repository.get_by_id(1).await.unwrap();
+    task.depends_on = vec![99];
+    assert_not_found(backend, "depending on a missing task", repository.update(task).await, 99);
+    assert_eq!(ids(repository).await, [1], "{} backend", backend);
+}
+
+async fn updates_replace_everything_but_the_creation_time(backend: &str, repository: &(dyn Repository + Sync)) {
+    repository.add_project(NewProject { name: "house".into(), description: None }).await.unwrap();
+    let project = repository.get_project_by_name("house").await.unwrap();
+    repository.add(new_task("first")).await.unwrap();
+    repository.add(new_task("second")).await.unwrap();
+    let original = repository.get_by_id(1).await.unwrap();
+
+    let mut changed = original.clone();
+    changed.name = "renamed".into();
+    changed.description = Some("with a description".into());
+    changed.set_status(Status::Done);
+    changed.completed_at = date(2, 9);
+    changed.due = date(3, 17);
+    changed.scheduled = date(3, 9);
+    changed.priority = Priority::High;
+    changed.project_id = Some(project.id);
+    changed.recurrence = Some("weekly".parse().unwrap());
+    changed.tags = vec!["work".into(), "home".into(), "work".into()];
+    changed.depends_on = vec![2];
+    changed.created_at = Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap();
+    repository.update(changed.clone()).await.unwrap();
+
+    let stored = repository.get_by_id(1).await.unwrap();
+    assert!(stored.updated_at >= original.updated_at, "{} backend, updated at {}", backend, stored.updated_at);
+
+    let mut expected = changed;
+    expected.created_at = original.created_at;
+    expected.updated_at = stored.updated_at;
+    expected.tags = vec!["home".into(), "work".into()];
+    assert_eq!(stored, expected, "{} backend", backend);
+
+    let mut reopened = stored;
+    reopened.set_status(Status::Todo);
+    repository.update(reopened).await.unwrap();
+    assert_eq!(repository.get_by_id(1).await.unwrap().completed_at, None, "{} backend", backend);
+}
+
+async fn failed_updates_change_nothing(backend: &str, repository: &(dyn Repository + Sync)) {
+    repository.add(new_task("first")).await.unwrap();
+    repository.add(new_task("second")).await.unwrap();
+    let mut second = repository.get_by_id(2).await.unwrap();
+    second.depends_on = vec![1];
+    repository.update(second).await.unwrap();
+
+    let before = repository.get_all().await.unwrap();
+    let mut first = before[0].clone();
+    first.name = "renamed".into();
+    first.tags = vec!["new".into()];
+    first.depends_on = vec![2];
+
+    let error = repository.update(first).await.expect_err("a dependency cycle should be refused");
+    assert!(error.downcast_ref::<DependencyCycleError>().is_some(), "{} backend, failed with {:?}", backend, error);
+    assert_eq!(repository.get_all().await.unwrap(), before, "{} backend", backend);
+}
+
+async fn removing_a_task_lets_go_of_its_subtasks_and_dependents(backend: &str, repository: &(dyn Repository + Sync)) {
+    repository.add(new_task("parent")).await.unwrap();
+    repository.add(NewTask { parent_id: Some(1), ..new_task("subtask") }).await.unwrap();
+    repository.add(new_task("dependent")).await.unwrap();
+    let mut dependent = repository.get_by_id(3).await.unwrap();
+    dependent.depends_on = vec![1];
+    repository.update(dependent).await.unwrap();
+    repository.add(new_task("unrelated")).await.unwrap();
+    let related: Vec<i32> = repository.get_related(1).await.unwrap().iter().map(|task| task.id).collect();
+    assert_eq!(related, [1, 2, 3], "{} backend", backend);
+
+    repository.remove(1).await.unwrap();
+
+    assert_not_found(backend, "getting a removed task", repository.get_by_id(1).await, 1);
+    assert_eq!(repository.get_by_id(2).await.unwrap().parent_id, None, "{} backend", backend);
+    assert!(repository.get_by_id(3).await.unwrap().depends_on.is_empty(), "{} backend", backend);
+}
+
+async fn removed_tasks_wait_in_the_trash_until_purged(backend: &str, repository: &(dyn Repository + Sync)) {
+    repository.add(NewTask { tags: vec!["home".into()], ..new_task("parent") }).await.unwrap();
+    repository.add(NewTask { parent_id: Some(1), ..new_task("subtask") }).await.unwrap();
+    let before = Utc::now();
+
+    repository.remove(1).await.unwrap();
+
+    let trash = repository.get_trash().await.unwrap();
+    assert_eq!(trash.iter().map(|task| (task.id, task.tags.clone())).collect::<Vec<_>>(), [(1, vec!["home".to_string()])], "{} backend", backend);
+    assert!(trash[0].deleted_at.is_some_and(|deleted_at| deleted_at >= before.trunc_subsecs(6)), "{} backend", backend);
+    assert_eq!(ids(repository).await, [2], "{} backend", backend);
+    let found: Vec<_> = repository.find(&TaskFilter::default()).await.unwrap().into_iter().map(|task| task.id).collect();
+    assert_eq!(found, [2], "{} backend, trashed tasks are not found", backend);
+    assert!(repository.search(&SearchTerms::parse("parent").unwrap(), 10).await.unwrap().is_empty(), "{} backend", backend);
+    assert!(repository.get_tags().await.unwrap().is_empty(), "{} backend, trashed tasks do not count towards tags", backend);
+    assert_not_found(backend, "removing a task twice", repository.remove(1).await, 1);
+    assert_not_found(backend, "updating a trashed task", repository.update(trash[0].clone()).await, 1);
+    assert_not_found(backend, "adding a subtask of a trashed task", repository.add(NewTask { parent_id: Some(1), ..new_task("orphan") }).await, 1);
+
+    repository.restore_from_trash(1).await.unwrap();
+    assert_eq!(ids(repository).await, [1, 2], "{} backend", backend);
+    assert_eq!(repository.get_by_id(1).await.unwrap().deleted_at, None, "{} backend", backend);
+    assert!(repository.get_trash().await.unwrap().is_empty(), "{} backend", backend);
+    assert!(repository.restore_from_trash(1).await.is_err(), "{} backend, restoring a task that is not in the trash", backend);
+
+    repository.remove(2).await.unwrap();
+    assert_eq!(repository.empty_trash(before).await.unwrap(), 0, "{} backend, only older tasks are purged", backend);
+    assert_eq!(repository.empty_trash(Utc::now() + Duration::seconds(1)).await.unwrap(), 1, "{} backend", backend);
+    assert!(repository.get_trash().await.unwrap().is_empty(), "{} backend", backend);
+    assert_eq!(ids(repository).await, [1], "{} backend", backend);
+}
+
+async fn deleted_tasks_are_gone_for_good(backend: &str, repository: &(dyn Repository + Sync)) {
+    for name in ["parent", "subtask", "waiting"] {
+        repository.add(new_task(name)).await.unwrap();
+    }
+    let mut subtask = repository.get_by_id(2).await.unwrap();
+    subtask.parent_id = Some(1);
+    repository.update(subtask).await.unwrap();
+    let mut waiting = repository.get_by_id(3).await.unwrap();
+    waiting.depends_on = vec![1];
+    repository.update(waiting).await.unwrap();
+
+    repository.delete(1).await.unwrap();
+    assert_eq!(ids(repository).await, [2, 3], "{} backend", backend);
+    assert!(repository.get_trash().await.unwrap().is_empty(), "{} backend, deleted tasks skip the trash", backend);
+    assert_eq!(repository.get_by_id(2).await.unwrap().parent_id, None, "{} backend", backend);
+    assert!(repository.get_by_id(3).await.unwrap().depends_on.is_empty(), "{} backend", backend);
+
+    repository.remove(2).await.unwrap();
+    repository.delete(2).await.unwrap();
+    assert!(repository.get_trash().await.unwrap().is_empty(), "{} backend, tasks in the trash can be deleted", backend);
+    assert_not_found(backend, "deleting a task twice", repository.delete(2).await, 2);
+}
+
+async fn the_trash_survives_a_migration_and_a_restore(backend: &str, repository: &(dyn Repository + Sync)) {
+    repository.add(new_task("kept")).await.unwrap();
+    repository.add(NewTask { tags: vec!["home".into()], ..new_task("trashed") }).await.unwrap();
+    let mut trashed = repository.get_by_id(2).await.unwrap();
+    trashed.depends_on = vec![1];
+    repository.update(trashed).await.unwrap();
+    repository.remove(2).await.unwrap();
+    let backup: Vec<Task> = all_tasks(repository).await.unwrap().iter().map(comparable).collect();
+
+    let copy = MemoryRepository::new(vec![], vec![]);
+    transfer(repository, &copy, &mut |_, _| {}).await.unwrap();
+    assert_eq!(copy.get_trash().await.unwrap(), repository.get_trash().await.unwrap(), "{} backend, migrating", backend);
+
+    repository.restore_from_trash(2).await.unwrap();
+    repository.add(new_task("added since")).await.unwrap();
+    restore(repository, &backup, &[]).await.unwrap();
+    let restored: Vec<Task> = all_tasks(repository).await.unwrap().iter().map(comparable).collect();
+    assert_eq!(restored, backup, "{} backend, restoring", backend);
+    assert_eq!(ids(repository).await, [1], "{} backend, restoring", backend);
+}
+
+async fn lists_come_back_in_order(backend: &str, repository: &(dyn Repository + Sync)) {
+    for (name, tags) in [("first", vec!["work"]), ("second", vec!["home", "work"]), ("third", vec![])] {
+        repository.add(NewTask {
+            tags: tags.into_iter().map(String::from).collect(),
+            ..new_task(name)
+        }).await.unwrap();
+    }
+    // Updating a task must not move it to the end.
+    let mut first = repository.get_by_id(1).await.unwrap();
+    first.name = "first, renamed".into();
+    repository.update(first).await.unwrap();
+
+    assert_eq!(ids(repository).await, [1, 2, 3], "{} backend", backend);
+    let found: Vec<_> = repository.find(&TaskFilter::default()).await.unwrap().into_iter().map(|task| task.id).collect();
+    assert_eq!(found, [1, 2, 3], "{} backend", backend);
+    assert_eq!(repository.get_tags().await.unwrap(), [("home".into(), 1), ("work".into(), 2)], "{} backend", backend);
+
+    for name in ["work", "garden", "house"] {
+        repository.add_project(NewProject { name: name.into(), description: None }).await.unwrap();
+    }
+    let projects: Vec<_> = repository.get_projects().await.unwrap().into_iter().map(|project| project.name).collect();
+    assert_eq!(projects, ["garden", "house", "work"], "{} backend", backend);
+}
+
+async fn projects_have_unique_names(backend: &str, repository: &(dyn Repository + Sync)) {
+    repository.add_project(NewProject { name: "house".into(), description: Some("Jobs around the house".into()) }).await.unwrap();
+
+    let error = repository.add_project(NewProject { name: "house".into(), description: None }).await.unwrap_err();
+    assert_eq!(error.to_string(), "A project named \"house\" already exists", "{} backend", backend);
+
+    let project = repository.get_project_by_name("house").await.unwrap();
+    assert_eq!(project.id, 1, "{} backend", backend);
+    assert_eq!(project.description.as_deref(), Some("Jobs around the house"), "{} backend", backend);
+
+    repository.archive_project(project.id, true).await.unwrap();
+    assert!(repository.get_projects().await.unwrap()[0].archived, "{} backend", backend);
+
+    let error = repository.archive_project(99, true).await.unwrap_err();
+    assert_eq!(error.to_string(), "No project with id 99 found", "{} backend", backend);
+    let error = repository.get_project_by_name("garden").await.unwrap_err();
+    assert_eq!(error.to_string(), "No project named \"garden\" found", "{} backend", backend);
+}
+
+async fn the_journal_keeps_recent_batches_until_they_are_undone(backend: &str, repository: &(dyn Repository + Sync)) {
+    let created_at = Utc.with_ymd_and_hms(2026, 10, 18, 9, 30, 0).unwrap();
+    let task = Task::from_new_task(1, new_task("journaled"), created_at);
+    let entry = |batch: i64| NewJournalEntry {
+        batch,
+        command: format!("add journaled {}", batch),
+        created_at,
+        changes: vec![TaskChange { id: 1, before: None, after: Some(task.clone()) }],
+    };
+
+    for batch in 1..=KEPT_BATCHES as i64 + 1 {
+        repository.add_journal_entry(entry(batch)).await.unwrap();
+    }
+    let journal = repository.get_journal().await.unwrap();
+    let batches: Vec<i64> = journal.iter().map(|entry| entry.batch).collect();
+    assert_eq!(batches, (2..=KEPT_BATCHES as i64 + 1).collect::<Vec<_>>(), "{} backend, the oldest batch is dropped", backend);
+    assert!(journal.windows(2).all(|pair| pair[0].id < pair[1].id), "{} backend, entries come back oldest first", backend);
+    assert_eq!(journal[0], JournalEntry::from_new_entry(journal[0].id, entry(2)), "{} backend", backend);
+
+    let newest: Vec<i64> = journal.iter().rev().take(2).map(|entry| entry.id).collect();
+    repository.set_undone(&newest, true).await.unwrap();
+    let undone: Vec<bool> = repository.get_journal().await.unwrap().iter().rev().take(3).map(|entry| entry.undone).collect();
+    assert_eq!(undone, [true, true, false], "{} backend", backend);
+
+    repository.add_journal_entry(entry(100)).await.unwrap();
+    let journal = repository.get_journal().await.unwrap();
+    assert_eq!(journal.len(), KEPT_BATCHES - 1, "{} backend, undone entries are dropped by a new one", backend);
+    assert_eq!((journal.last().unwrap().batch, journal.last().unwrap().undone), (100, false), "{} backend", backend);
+}
+
+async fn imports_keep_ids_and_timestamps(backend: &str, repository: &(dyn Repository + Sync)) {
+    let project = Project { id: 7, name: "house".into(), description: None, archived: true };
+    repository.import_project(project.clone()).await.unwrap();
+
+    let mut parent = Task::from_new_task(5, NewTask {
+        project_id: Some(7),
+        tags: vec!["work".into(), "home".into()],
+        ..new_task("imported")
+    }, Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap());
+    parent.set_status(Status::Done);
+    parent.completed_at = date(2, 9);
+    parent.updated_at = Utc.with_ymd_and_hms(2001, 1, 1, 0, 0, 0).unwrap();
+    let mut subtask = Task::from_new_task(9, NewTask { parent_id: Some(5), ..new_task("subtask") }, parent.created_at);
+    subtask.depends_on = vec![5];
+    repository.import_task(parent.clone()).await.unwrap();
+    repository.import_task(subtask.clone()).await.unwrap();
+
+    parent.tags.sort();
+    assert_eq!(repository.get_all().await.unwrap(), [parent.clone(), subtask], "{} backend", backend);
+    assert_eq!(repository.get_projects().await.unwrap(), [project], "{} backend", backend);
+
+    parent.name = "imported again".into();
+    repository.import_task(parent.clone()).await.unwrap();
+    assert_eq!(repository.get_by_id(5).await.unwrap(), parent, "{} backend, importing twice replaces", backend);
+
+    assert_not_found(backend, "importing a subtask of a missing task", repository.import_task(Task {
+        parent_id: Some(99),
+        ..parent
+    }).await, 99);
+
+    repository.add(new_task("added")).await.unwrap();
+    repository.add_project(NewProject { name: "garden".into(), description: None }).await.unwrap();
+    assert_eq!(ids(repository).await, [5, 9, 10], "{} backend, imported ids are not handed out again", backend);
+    assert_eq!(repository.get_project_by_name("garden").await.unwrap().id, 8, "{} backend", backend);
+}
+
+async fn add_fixture(repository: &(dyn Repository + Sync)) {
+    repository.add_project(NewProject { name: "house".into(), description: None }).await.unwrap();
+    let house = repository.get_project_by_name("house").await.unwrap().id;
+
+    let tasks = [
+        NewTask { name: "Deploy release".into(), description: Some("Roll the release out to every server, then check the TLS certificate".into()), priority: Priority::High, due: date(20, 17), tags: vec!["ops".into()], project_id: Some(house), ..NewTask::default() },
+        NewTask { name: "Write report".into(), description: Some("Quarterly numbers, 100% done".into()), priority: Priority::Medium, due: date(36, 0), tags: vec!["work".into()], ..NewTask::default() },
+        NewTask { name: "Fix the door".into(), description: Some("The hinge squeaks, see the report".into()), priority: Priority::Low, tags: vec!["diy".into(), "home".into()], project_id: Some(house), ..NewTask::default() },
+        NewTask { name: "Call the_plumber".into(), description: Some("Ask about the Ölheizung".into()), priority: Priority::Urgent, due: date(20, 0), tags: vec!["home".into()], ..NewTask::default() },
+    ];
+    for task in tasks {
+        repository.add(task).await.unwrap();
+    }
+
+    let parent = repository.get_all().await.unwrap().into_iter().find(|task| task.name == "Deploy release").unwrap();
+    repository.add(NewTask { name: "Buy milk".into(), parent_id: Some(parent.id), ..NewTask::default() }).await.unwrap();
+
+    for mut task in repository.get_all().await.unwrap() {
+        let status = match task.name.as_str() {
+            "Deploy release" => Status::InProgress,
+            "Fix the door" => Status::Done,
+            "Call the_plumber" => Status::Waiting,
+            "Buy milk" => Status::Cancelled,
+            _ => continue,
+        };
+        task.set_status(status);
+        repository.update(task).await.unwrap();
+    }
+}
+
+async fn queries_match_the_same_tasks(backend: &str, repository: &(dyn Repository + Sync)) {
+    let now = Utc.with_ymd_and_hms(2026, 10, 18, 12, 0, 0).unwrap();
+
+    for (input, expected) in QUERIES {
+        let filter = TaskFilter {
+            query: Some(parse_query(input, now).unwrap()),
+            ..TaskFilter::default()
+        };
+        let mut names: Vec<_> = repository.find(&filter).await.unwrap().into_iter().map(|task| task.name).collect();
+        names.sort();
+        assert_eq!(names, *expected, "{} backend, query {:?}", backend, input);
+    }
+}
+
+async fn searches_rank_the_same_tasks(backend: &str, repository: &(dyn Repository + Sync)) {
+    for (input, expected) in SEARCHES {
+        let terms = SearchTerms::parse(input).unwrap();
+        let hits = repository.search(&terms, 10).await.unwrap();
+        let names: Vec<_> = hits.iter().map(|hit| hit.task.name.as_str()).collect();
+        assert_eq!(names, *expected, "{} backend, search {:?}", backend, input);
+
+        for hit in hits.iter() {
+            let matches = hit.matches();
+            assert!(!matches.is_empty(), "{} backend, search {:?}, snippet {:?}", backend, input, hit.snippet);
+            for word in matches {
+                assert!(terms.words().iter().any(|term| word.to_lowercase().starts_with(term.as_str())),
+                        "{} backend, search {:?}, snippet {:?}", backend, input, hit.snippet);
+            }
+        }
+    }
+
+    let hits = repository.search(&SearchTerms::parse("report").unwrap(), 1).await.unwrap();
+    assert_eq!(hits.len(), 1, "{} backend, limited search", backend);
+    assert_eq!(hits[0].task.tags, ["work"], "{} backend, search results have their tags", backend);
+}
+
+#[tokio::test]
+async fn file_backend_conforms() {
+    let directory = tempfile::tempdir().unwrap();
+
+    for extension in ["ron", "json", "toml", "yaml"] {
+        let opened = std::cell::Cell::new(0);
+        check_backend(&format!("file ({})", extension), async || {
+            opened.set(opened.get() + 1);
+            let path = directory.path().join(format!("tasks-{}.{}", opened.get(), extension));
+            let options = FileOptions::from_url(&url::Url::from_file_path(&path).unwrap()).unwrap();
+            FileRepository::new(path, options).unwrap()
+        }).await;
+    }
+}
+
+#[tokio::test]
+async fn memory_backend_conforms() {
+    check_backend("memory", async || MemoryRepository::new(vec![], vec![])).await;
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn sqlite_backend_conforms() {
+    let directory = tempfile::tempdir().unwrap();
+    let opened = std::cell::Cell::new(0);
+
+    check_backend("sqlite", async || {
+        opened.set(opened.get() + 1);
+        let path = directory.path().join(format!("tasks-{}.db", opened.get()));
+        let url = url::Url::parse(&format!("sqlite://{}", path.display())).unwrap();
+        let repository = super::sqlite_repository::SqliteRepository::new(&url).unwrap();
+        repository.migrate().await.unwrap();
+        repository
+    }).await;
+}
+
+/// Runs against the database in `TODO_TEST_POSTGRES_URL`, which is emptied before every
+/// check, or else against a throwaway server started with the Postgres tools on the `PATH`.
+/// Neither is a given, so it only runs when asked for with `--ignored`, and then fails
+/// rather than passing without a server.
+#[cfg(feature = "postgres")]
+#[tokio::test]
+#[ignore = "needs TODO_TEST_POSTGRES_URL or the Postgres tools on the PATH"]
+async fn postgres_backend_conforms() {
+    let server;
+    let url = match std::env::var("TODO_TEST_POSTGRES_URL") {
+        Ok(url) => url::Url::parse(&url).unwrap(),
+        Err(_) => match local_postgres::LocalPostgres::start() {
+            Ok(started) => {
+                server = started;
+                server.url()
+            }
+            Err(e) => panic!("TODO_TEST_POSTGRES_URL is not set and no local server could be started: {}", e),
+        },
+    };
+
+    let pool = sqlx::PgPool::connect(url.as_str()).await.unwrap();
+    super::postgres_repository::PostgresRepository::new(&url).unwrap().migrate().await.unwrap();
+
+    check_backend("postgres", async || {
+        sqlx::query("truncate task_dependencies, task_tags, tags, tasks, projects, journal restart identity")
+            .execute(&pool)
+            .await
+            .unwrap();
+        super::postgres_repository::PostgresRepository::new(&url).unwrap()
+    }).await;
+}
+
+#[cfg(feature = "postgres")]
+mod local_postgres {
+    use anyhow::{bail, Result};
+    use std::net::TcpListener;
+    use std::path::Path;
+    use std::process::{Command, Stdio};
+    use tempfile::TempDir;
+
+    /// A Postgres server in a temporary directory, stopped and deleted when dropped.
+    pub struct LocalPostgres {
+        directory: TempDir,
+        port: u16,
+    }
+
+    impl LocalPostgres {
+        /// Creates a cluster with `initdb` and starts it on a free port with `pg_ctl`.
+        pub fn start() -> Result<Self> {
+            let directory = tempfile::tempdir()?;
+            let data = directory.path().join("data");
+            run(Command::new("initdb").arg("--pgdata").arg(&data).args(["--username=postgres", "--auth=trust", "--no-sync"]))?;
+
+            let port = TcpListener::bind("127.0.0.1:0")?.local_addr()?.port();
+            let options = format!("-p {} -k {} -c listen_addresses=127.0.0.1 -c fsync=off", port, directory.path().display());
+            run(Command::new("pg_ctl").arg("--pgdata").arg(&data)
+                                      .arg("--log").arg(directory.path().join("server.log"))
+                                      .args(["--options", &options, "--wait", "start"]))?;
+
+            Ok(Self { directory, port })
+        }
+
+        pub fn url(&self) -> url::Url {
+            url::Url::parse(&format!("postgresql://postgres@127.0.0.1:{}/postgres", self.port)).unwrap()
+        }
+
+        fn data(&self) -> impl AsRef<Path> {
+            self.directory.path().join("data")
+        }
+    }
+
+    impl Drop for LocalPostgres {
+        fn drop(&mut self) {
+            let _ = run(Command::new("pg_ctl").arg("--pgdata").arg(self.data().as_ref()).args(["--mode=immediate", "stop"]));
+        }
+    }
+
+    fn run(command: &mut Command) -> Result<()> {
+        let output = command.stdout(Stdio::null()).stderr(Stdio::piped()).output()?;
+        if !output.status.success() {
+            bail!("{:?} failed: {}", command.get_program(), String::from_utf8_lossy(&output.stderr).trim());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,64 @@
+use crate::persistence::Repository;
+use anyhow::Result;
+use std::fmt::{Display, Formatter};
+
+/// The findings of a `todo doctor` run.
+#[derive(Debug, Default)]
+pub struct DoctorReport {
+    pub problems: Vec<String>,
+    pub repairs: Vec<String>,
+}
+
+impl DoctorReport {
+    pub fn is_healthy(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+impl Display for DoctorReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.is_healthy() {
+            return writeln!(f, "No problems found");
+        }
+
+        writeln!(f, "Found {} problem(s):", self.problems.len())?;
+        for problem in self.problems.iter() {
+            writeln!(f, "  - {}", problem)?;
+        }
+
+        if self.repairs.is_empty() {
+            writeln!(f, "Nothing was repaired")
+        } else {
+            writeln!(f, "Repairs:")?;
+            for repair in self.repairs.iter() {
+                writeln!(f, "  - {}", repair)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Checks a database backend for an outdated or unsupported schema.
+pub async fn check_schema(repository: &(dyn Repository + Sync), repair: bool) -> Result<DoctorReport> {
+    let mut report = DoctorReport::default();
+
+    let Some(status) = repository.schema_status().await? else {
+        return Ok(report);
+    };
+
+    if let Err(e) = status.ensure_supported() {
+        report.problems.push(e.to_string());
+        return Ok(report);
+    }
+
+    if !status.is_up_to_date() {
+        report.problems.push(format!("{} schema migration(s) have not been applied", status.pending.len()));
+
+        if repair {
+            repository.migrate().await?;
+            report.repairs.push("Applied pending schema migrations".into());
+        }
+    }
+
+    Ok(report)
+}
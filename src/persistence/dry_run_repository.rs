@@ -0,0 +1,110 @@
+use crate::journal::{JournalEntry, NewJournalEntry};
+use crate::output;
+use crate::persistence::memory_repository::MemoryRepository;
+use crate::persistence::Repository;
+use crate::projects::{NewProject, Project};
+use crate::tasks::{NewTask, Task};
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+/// Stands in for the configured repository under `--dry-run`. Changes go to an in-memory
+/// copy of the store, and what each one would have changed is printed instead of saved.
+pub struct DryRunRepository {
+    copy: MemoryRepository,
+}
+
+impl DryRunRepository {
+    pub fn new(copy: MemoryRepository) -> Self {
+        Self { copy }
+    }
+
+    async fn contents(&self) -> Result<(Vec<Task>, Vec<Project>)> {
+        Ok((self.copy.get_all().await?, self.copy.get_projects().await?))
+    }
+
+    /// Makes a change to the copy and prints the difference it made.
+    async fn change<T>(&self, change: impl AsyncFnOnce(&MemoryRepository) -> Result<T>) -> Result<T> {
+        let (tasks_before, projects_before) = self.contents().await?;
+        let result = change(&self.copy).await?;
+        let (tasks_after, projects_after) = self.contents().await?;
+
+        print!("{}", output::render_changes(
+            (&tasks_before, &projects_before),
+            (&tasks_after, &projects_after),
+            output::use_colour(),
+        ));
+        Ok(result)
+    }
+}
+
+#[async_trait]
+impl Repository for DryRunRepository {
+    async fn get_all(&self) -> Result<Vec<Task>> {
+        self.copy.get_all().await
+    }
+
+    async fn get_by_id(&self, id: i32) -> Result<Task> {
+        self.copy.get_by_id(id).await
+    }
+
+    async fn add(&self, task: NewTask) -> Result<i32> {
+        self.change(async |copy| copy.add(task).await).await
+    }
+
+    async fn remove(&self, id: i32) -> Result<()> {
+        self.change(async |copy| copy.remove(id).await).await
+    }
+
+    async fn update(&self, task: Task) -> Result<()> {
+        self.change(async |copy| copy.update(task).await).await
+    }
+
+    async fn get_trash(&self) -> Result<Vec<Task>> {
+        self.copy.get_trash().await
+    }
+
+    async fn empty_trash(&self, deleted_before: DateTime<Utc>) -> Result<usize> {
+        self.copy.empty_trash(deleted_before).await
+    }
+
+    async fn delete(&self, id: i32) -> Result<()> {
+        self.change(async |copy| copy.delete(id).await).await
+    }
+
+    async fn restore_from_trash(&self, id: i32) -> Result<()> {
+        self.change(async |copy| copy.restore_from_trash(id).await).await
+    }
+
+    async fn get_projects(&self) -> Result<Vec<Project>> {
+        self.copy.get_projects().await
+    }
+
+    async fn add_project(&self, project: NewProject) -> Result<()> {
+        self.change(async |copy| copy.add_project(project).await).await
+    }
+
+    async fn archive_project(&self, id: i32, archived: bool) -> Result<()> {
+        self.change(async |copy| copy.archive_project(id, archived).await).await
+    }
+
+    async fn import_task(&self, task: Task) -> Result<()> {
+        self.change(async |copy| copy.import_task(task).await).await
+    }
+
+    async fn import_project(&self, project: Project) -> Result<()> {
+        self.change(async |copy| copy.import_project(project).await).await
+    }
+
+    async fn add_journal_entry(&self, entry: NewJournalEntry) -> Result<()> {
+        self.copy.add_journal_entry(entry).await
+    }
+
+    async fn get_journal(&self) -> Result<Vec<JournalEntry>> {
+        self.copy.get_journal().await
+    }
+
+    async fn set_undone(&self, ids: &[i64], undone: bool) -> Result<()> {
+        self.copy.set_undone(ids, undone).await
+    }
+}
@@ -0,0 +1,381 @@
+use crate::error::TaskFileParseError;
+use crate::persistence::recovery;
+use crate::projects::Project;
+use crate::tasks::{Priority, Recurrence, Status, Task};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// The serialisation formats the file backend can read and write.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum FileFormat {
+    #[default]
+    Ron,
+    Json,
+    Toml,
+    Yaml,
+    Csv,
+}
+
+/// Where and why a task file failed to parse, before it is tied to a path.
+#[derive(Debug)]
+pub struct ParseFailure {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl ParseFailure {
+    fn at_offset(contents: &str, offset: usize, message: impl Into<String>) -> Self {
+        let preceding = &contents[..offset.min(contents.len())];
+        let line = preceding.matches('\n').count() + 1;
+        let column = preceding.len() - preceding.rfind('\n').map(|index| index + 1).unwrap_or(0) + 1;
+
+        Self {
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+
+    pub fn into_error(self, path: impl Into<PathBuf>) -> TaskFileParseError {
+        TaskFileParseError {
+            path: path.into(),
+            line: self.line,
+            column: self.column,
+            message: self.message,
+        }
+    }
+}
+
+/// CSV rows are flat, so list fields such as tags are joined into a single column.
+#[derive(Serialize, Deserialize)]
+struct CsvRecord {
+    id: i32,
+    name: String,
+    description: Option<String>,
+    #[serde(alias = "completed")]
+    status: Status,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    completed_at: Option<DateTime<Utc>>,
+    due: Option<DateTime<Utc>>,
+    scheduled: Option<DateTime<Utc>>,
+    #[serde(default)]
+    priority: Priority,
+    #[serde(default)]
+    project_id: Option<i32>,
+    #[serde(default)]
+    parent_id: Option<i32>,
+    #[serde(default)]
+    recurrence: Option<Recurrence>,
+    #[serde(default)]
+    deleted_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    tags: String,
+    #[serde(default)]
+    depends_on: String,
+}
+
+impl From<&Task> for CsvRecord {
+    fn from(task: &Task) -> Self {
+        Self {
+            id: task.id,
+            name: task.name.clone(),
+            description: task.description.clone(),
+            status: task.status,
+            created_at: task.created_at,
+            updated_at: task.updated_at,
+            completed_at: task.completed_at,
+            due: task.due,
+            scheduled: task.scheduled,
+            priority: task.priority,
+            project_id: task.project_id,
+            parent_id: task.parent_id,
+            recurrence: task.recurrence.clone(),
+            deleted_at: task.deleted_at,
+            tags: task.tags.join(";"),
+            depends_on: task.depends_on.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(";"),
+        }
+    }
+}
+
+impl From<CsvRecord> for Task {
+    fn from(record: CsvRecord) -> Self {
+        Self {
+            id: record.id,
+            name: record.name,
+            description: record.description,
+            status: record.status,
+            created_at: record.created_at,
+            updated_at: record.updated_at,
+            completed_at: record.completed_at,
+            due: record.due,
+            scheduled: record.scheduled,
+            priority: record.priority,
+            project_id: record.project_id,
+            parent_id: record.parent_id,
+            recurrence: record.recurrence,
+            deleted_at: record.deleted_at,
+            tags: record.tags.split(';').filter(|tag| !tag.is_empty()).map(String::from).collect(),
+            depends_on: record.depends_on.split(';').filter_map(|id| id.parse().ok()).collect(),
+        }
+    }
+}
+
+impl FileFormat {
+    /// Picks a format from the file extension, if it is one we recognise.
+    pub fn from_extension(path: impl AsRef<Path>) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?;
+        Self::from_str(&extension.to_ascii_lowercase()).ok()
+    }
+
+    pub fn serialise(&self, tasks: &[Task]) -> Result<String> {
+        match self {
+            FileFormat::Csv => self.serialise_records(&tasks.iter().map(CsvRecord::from).collect::<Vec<_>>(), "tasks"),
+            _ => self.serialise_records(tasks, "tasks"),
+        }
+    }
+
+    pub fn deserialise(&self, contents: &str) -> Result<Vec<Task>, ParseFailure> {
+        match self {
+            FileFormat::Csv => Ok(self.deserialise_records::<CsvRecord>(contents, "tasks")?
+                                   .into_iter()
+                                   .map(Task::from)
+                                   .collect()),
+            _ => self.deserialise_records(contents, "tasks"),
+        }
+    }
+
+    pub fn serialise_projects(&self, projects: &[Project]) -> Result<String> {
+        self.serialise_records(projects, "projects")
+    }
+
+    pub fn deserialise_projects(&self, contents: &str) -> Result<Vec<Project>, ParseFailure> {
+        self.deserialise_records(contents, "projects")
+    }
+
+    /// Writes a list of flat records. TOML documents must be tables, so the list is kept
+    /// under `toml_key` there.
+    fn serialise_records<T: Serialize>(&self, records: &[T], toml_key: &str) -> Result<String> {
+        Ok(match self {
+            FileFormat::Ron => ron::to_string(records)?,
+            FileFormat::Json => serde_json::to_string_pretty(records)?,
+            FileFormat::Toml => toml::to_string(&BTreeMap::from([(toml_key, records)]))?,
+            FileFormat::Yaml => serde_yaml::to_string(records)?,
+            FileFormat::Csv => {
+                let mut writer = csv::Writer::from_writer(vec![]);
+                for record in records {
+                    writer.serialize(record)?;
+                }
+                String::from_utf8(writer.into_inner()?)?
+            }
+        })
+    }
+
+    fn deserialise_records<T: DeserializeOwned>(&self, contents: &str, toml_key: &str) -> Result<Vec<T>, ParseFailure> {
+        match self {
+            FileFormat::Ron => ron::de::from_str(contents).map_err(|e| ParseFailure {
+                line: e.span.start.line,
+                column: e.span.start.col,
+                message: e.code.to_string(),
+            }),
+            FileFormat::Json => serde_json::from_str(contents).map_err(|e| ParseFailure {
+                line: e.line(),
+                column: e.column(),
+                message: e.to_string(),
+            }),
+            FileFormat::Toml => toml::from_str::<BTreeMap<String, Vec<T>>>(contents)
+                .map(|mut document| document.remove(toml_key).unwrap_or_default())
+                .map_err(|e| {
+                    let offset = e.span().map(|span| span.start).unwrap_or(0);
+                    ParseFailure::at_offset(contents, offset, e.message())
+                }),
+            FileFormat::Yaml => serde_yaml::from_str(contents).map_err(|e| {
+                let location = e.location();
+                ParseFailure {
+                    line: location.as_ref().map(|l| l.line()).unwrap_or(1),
+                    column: location.as_ref().map(|l| l.column()).unwrap_or(1),
+                    message: e.to_string(),
+                }
+            }),
+            FileFormat::Csv => csv::Reader::from_reader(contents.as_bytes())
+                .deserialize()
+                .collect::<Result<Vec<T>, _>>()
+                .map_err(|e| ParseFailure {
+                    line: e.position().map(|position| position.line() as usize).unwrap_or(1),
+                    column: 1,
+                    message: e.to_string(),
+                }),
+        }
+    }
+
+    /// Recovers whatever tasks still parse from contents that failed to deserialise.
+    pub fn salvage(&self, contents: &str) -> Vec<Task> {
+        match self {
+            FileFormat::Csv => self.salvage_records::<CsvRecord>(contents, "tasks")
+                                   .into_iter()
+                                   .map(Task::from)
+                                   .collect(),
+            _ => self.salvage_records(contents, "tasks"),
+        }
+    }
+
+    /// Recovers whatever projects still parse from contents that failed to deserialise.
+    pub fn salvage_projects(&self, contents: &str) -> Vec<Project> {
+        self.salvage_records(contents, "projects")
+    }
+
+    fn salvage_records<T: DeserializeOwned>(&self, contents: &str, toml_key: &str) -> Vec<T> {
+        match self {
+            FileFormat::Ron => recovery::salvage_entries(contents, |entry| ron::de::from_str(entry).ok()),
+            FileFormat::Json => recovery::salvage_entries(contents, |entry| serde_json::from_str(entry).ok()),
+            FileFormat::Toml => toml::from_str::<toml::Table>(contents)
+                .ok()
+                .and_then(|mut table| table.remove(toml_key))
+                .and_then(|records| records.as_array().cloned())
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|record| record.try_into().ok())
+                .collect(),
+            FileFormat::Yaml => serde_yaml::from_str::<Vec<serde_yaml::Value>>(contents)
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|record| serde_yaml::from_value(record).ok())
+                .collect(),
+            FileFormat::Csv => csv::Reader::from_reader(contents.as_bytes())
+                .deserialize::<T>()
+                .filter_map(|record| record.ok())
+                .collect(),
+        }
+    }
+}
+
+impl FromStr for FileFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "ron" => Ok(FileFormat::Ron),
+            "json" => Ok(FileFormat::Json),
+            "toml" => Ok(FileFormat::Toml),
+            "yaml" | "yml" => Ok(FileFormat::Yaml),
+            "csv" => Ok(FileFormat::Csv),
+            _ => Err(anyhow!("Unsupported file format \"{}\"", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FileFormat;
+    use crate::projects::{NewProject, Project};
+    use crate::tasks::{NewTask, Priority, Status, Task};
+    use chrono::{TimeZone, Utc};
+
+    const ALL_FORMATS: [FileFormat; 5] = [
+        FileFormat::Ron,
+        FileFormat::Json,
+        FileFormat::Toml,
+        FileFormat::Yaml,
+        FileFormat::Csv,
+    ];
+
+    fn sample_tasks() -> Vec<Task> {
+        let now = Utc.with_ymd_and_hms(2026, 10, 18, 9, 30, 0).unwrap();
+
+        let mut first = Task::from_new_task(1, NewTask {
+            name: "Renew the TLS cert".into(),
+            description: Some("Expires on the 3rd, \"prod\" and staging".into()),
+            due: Some(Utc.with_ymd_and_hms(2026, 11, 3, 0, 0, 0).unwrap()),
+            priority: Priority::High,
+            tags: vec!["ops".into(), "work".into()],
+            project_id: Some(4),
+            ..NewTask::default()
+        }, now);
+        first.depends_on = vec![2, 7];
+        first.recurrence = Some("every 2 weeks on mon,thu".parse().unwrap());
+        first.scheduled = Some(Utc.with_ymd_and_hms(2026, 11, 1, 8, 0, 0).unwrap());
+
+        let mut second = Task::from_new_task(2, NewTask {
+            name: "Water plants, again".into(),
+            ..NewTask::default()
+        }, now);
+        second.parent_id = Some(1);
+        second.status = Status::Done;
+        second.completed_at = Some(now);
+
+        vec![first, second]
+    }
+
+    #[test]
+    fn every_format_round_trips() {
+        for format in ALL_FORMATS {
+            let serialised = format.serialise(&sample_tasks()).unwrap();
+            let deserialised = format.deserialise(&serialised)
+                                     .unwrap_or_else(|e| panic!("{:?} failed to parse: {:?}\n{}", format, e, serialised));
+            assert_eq!(deserialised, sample_tasks(), "{:?} did not round trip", format);
+        }
+    }
+
+    #[test]
+    fn every_format_round_trips_projects() {
+        let mut archived = Project::from_new_project(2, NewProject { name: "old, unused".into(), ..NewProject::default() });
+        archived.archived = true;
+        let projects = vec![
+            Project::from_new_project(1, NewProject { name: "house".into(), description: Some("Jobs".into()) }),
+            archived,
+        ];
+
+        for format in ALL_FORMATS {
+            let serialised = format.serialise_projects(&projects).unwrap();
+            assert_eq!(format.deserialise_projects(&serialised).unwrap(), projects, "{:?} did not round trip", format);
+        }
+    }
+
+    #[test]
+    fn every_format_round_trips_an_empty_list() {
+        for format in ALL_FORMATS {
+            let serialised = format.serialise(&[]).unwrap();
+            assert_eq!(format.deserialise(&serialised).unwrap(), vec![], "{:?} did not round trip", format);
+        }
+    }
+
+    #[test]
+    fn every_format_salvages_intact_entries() {
+        for format in ALL_FORMATS {
+            let serialised = format.serialise(&sample_tasks()).unwrap();
+            assert_eq!(format.salvage(&serialised), sample_tasks(), "{:?} lost entries", format);
+        }
+    }
+
+    #[test]
+    fn every_format_salvages_intact_projects() {
+        let projects = vec![Project::from_new_project(1, NewProject { name: "house".into(), description: Some("Jobs".into()) })];
+        for format in ALL_FORMATS {
+            let serialised = format.serialise_projects(&projects).unwrap();
+            assert_eq!(format.salvage_projects(&serialised), projects, "{:?} lost entries", format);
+        }
+    }
+
+    #[test]
+    fn parse_failures_point_at_the_problem() {
+        let failure = FileFormat::Json.deserialise("[\n  {\"id\": 1,\n  oops\n]").unwrap_err();
+        assert_eq!(failure.line, 3);
+
+        let failure = FileFormat::Toml.deserialise("[[tasks]]\nid = 1\nname = \n").unwrap_err();
+        assert_eq!(failure.line, 3);
+    }
+
+    #[test]
+    fn format_is_picked_from_the_extension() {
+        assert_eq!(FileFormat::from_extension("tasks.json"), Some(FileFormat::Json));
+        assert_eq!(FileFormat::from_extension("tasks.YML"), Some(FileFormat::Yaml));
+        assert_eq!(FileFormat::from_extension("default.todo.ron"), Some(FileFormat::Ron));
+        assert_eq!(FileFormat::from_extension("tasks"), None);
+    }
+}
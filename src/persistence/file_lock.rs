@@ -0,0 +1,124 @@
+use crate::error::LockTimeoutError;
+use anyhow::Result;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const RETRY_INTERVAL: Duration = Duration::from_millis(25);
+
+/// An advisory lock on a file, released when dropped.
+pub struct FileLock {
+    file: File,
+    path: PathBuf,
+}
+
+impl FileLock {
+    /// Takes an exclusive lock, waiting at most `timeout` for other holders to let go.
+    pub fn exclusive(path: impl AsRef<Path>, timeout: Duration) -> Result<Self> {
+        Self::acquire(path.as_ref(), timeout, File::try_lock)
+    }
+
+    /// Takes a shared lock, waiting at most `timeout` for an exclusive holder to let go.
+    pub fn shared(path: impl AsRef<Path>, timeout: Duration) -> Result<Self> {
+        Self::acquire(path.as_ref(), timeout, File::try_lock_shared)
+    }
+
+    /// Takes an exclusive lock from async code. The wait happens on a blocking thread, so the
+    /// runtime carries on with other work in the meantime.
+    pub async fn wait_exclusive(path: impl AsRef<Path>, timeout: Duration) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        tokio::task::spawn_blocking(move || Self::exclusive(path, timeout)).await?
+    }
+
+    /// Takes a shared lock from async code, waiting on a blocking thread like
+    /// [`Self::wait_exclusive`].
+    pub async fn wait_shared(path: impl AsRef<Path>, timeout: Duration) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        tokio::task::spawn_blocking(move || Self::shared(path, timeout)).await?
+    }
+
+    fn acquire(
+        path: &Path,
+        timeout: Duration,
+        try_lock: fn(&File) -> Result<(), TryLockError>,
+    ) -> Result<Self> {
+        log::trace!("Locking {}", path.display());
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+
+        let started = Instant::now();
+        loop {
+            match try_lock(&file) {
+                Ok(()) => {
+                    return Ok(Self {
+                        file,
+                        path: path.into(),
+                    });
+                }
+                Err(TryLockError::WouldBlock) if started.elapsed() < timeout => {
+                    std::thread::sleep(RETRY_INTERVAL);
+                }
+                Err(TryLockError::WouldBlock) => {
+                    return Err(LockTimeoutError {
+                        path: path.into(),
+                        waited: started.elapsed(),
+                    }.into());
+                }
+                Err(TryLockError::Error(e)) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        log::trace!("Unlocking {}", self.path.display());
+        if let Err(e) = self.file.unlock() {
+            log::warn!("Failed to unlock {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FileLock;
+    use crate::error::LockTimeoutError;
+    use std::time::Duration;
+
+    #[test]
+    fn exclusive_lock_times_out_while_held() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("tasks.lock");
+
+        let _held = FileLock::exclusive(&path, Duration::from_secs(1)).unwrap();
+        let error = FileLock::exclusive(&path, Duration::from_millis(100)).err().unwrap();
+        assert!(error.downcast_ref::<LockTimeoutError>().is_some());
+    }
+
+    #[test]
+    fn lock_is_released_on_drop() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("tasks.lock");
+
+        drop(FileLock::exclusive(&path, Duration::from_secs(1)).unwrap());
+        assert!(FileLock::exclusive(&path, Duration::from_millis(100)).is_ok());
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn waiting_for_a_lock_leaves_the_runtime_free() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("tasks.lock");
+
+        let held = FileLock::exclusive(&path, Duration::from_secs(1)).unwrap();
+        let release = async {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            drop(held);
+        };
+        let (_, waited) = tokio::join!(release, FileLock::wait_shared(&path, Duration::from_secs(5)));
+        assert!(waited.is_ok());
+    }
+}
@@ -1,54 +1,488 @@
+use crate::error::{TaskFileParseError, TaskNotFoundError};
+use crate::journal::{JournalEntry, NewJournalEntry};
+use crate::persistence::doctor::DoctorReport;
+use crate::persistence::file_format::FileFormat;
+use crate::persistence::file_lock::FileLock;
+use crate::persistence::task_list;
 use crate::persistence::Repository;
-use crate::tasks::{NewTask, Task};
+use crate::projects::{NewProject, Project};
+use crate::tasks::{would_create_cycle, NewTask, Task};
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
-use std::fs::OpenOptions;
+use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::fs::{File, OpenOptions};
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::sync::RwLock;
+use url::Url;
+
+/// How long to wait for another process to finish with the task file before giving up.
+const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Options for the file backend, read from the query string of a `file://` storage URL.
+#[derive(Debug, Default, Clone)]
+pub struct FileOptions {
+    /// Keep going with whatever entries can be read when the file is damaged.
+    pub lenient: bool,
+    /// The serialisation format, from `format=` or else the file extension.
+    pub format: FileFormat,
+}
+
+impl FileOptions {
+    pub fn from_url(url: &Url) -> Result<Self> {
+        let mut options = Self {
+            format: FileFormat::from_extension(url.path()).unwrap_or_default(),
+            ..Self::default()
+        };
+
+        for (key, value) in url.query_pairs() {
+            match key.as_ref() {
+                "lenient" => options.lenient = matches!(value.as_ref(), "true" | "yes" | "1"),
+                "format" => options.format = FileFormat::from_str(&value)?,
+                _ => log::warn!("Ignoring unknown storage option \"{}\"", key),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+/// The contents of the task file, along with the parse error if it had to be salvaged.
+struct LoadedTasks {
+    tasks: Vec<Task>,
+    damage: Option<TaskFileParseError>,
+}
+
+/// The contents of the projects file, salvaged the same way.
+struct LoadedProjects {
+    projects: Vec<Project>,
+    damage: Option<TaskFileParseError>,
+}
 
 pub struct FileRepository {
     storage_path: PathBuf,
+    lock_path: PathBuf,
+    /// Projects live next to the task file, in the same format, so older task files stay readable.
+    projects_path: PathBuf,
+    /// The journal lives next to the task file too, always as one JSON entry per line.
+    journal_path: PathBuf,
+    options: FileOptions,
     tasks: RwLock<Vec<Task>>,
+    projects: RwLock<Vec<Project>>,
+    /// The lock handed out by [`Repository::hold_lock`], while it is held. Locking the file
+    /// again from this process would wait on it, so writes made meanwhile share it instead.
+    held_lock: Mutex<Weak<FileLock>>,
 }
 
 impl FileRepository {
-    pub fn new(storage_path: PathBuf) -> Result<Self> {
+    pub fn new(storage_path: PathBuf, options: FileOptions) -> Result<Self> {
         log::trace!("Creating new FileRepository with storage path: {}", storage_path.display());
 
         Self::create_file(&storage_path)?;
 
+        let lock_path = Self::sibling_path(&storage_path, "lock");
+        let projects_path = Self::sibling_path(&storage_path, "projects");
+        let journal_path = Self::sibling_path(&storage_path, "journal");
+        let (loaded, projects) = {
+            let _lock = FileLock::shared(&lock_path, LOCK_TIMEOUT)?;
+            (
+                Self::read_from_file(&storage_path, options.format)?,
+                Self::read_projects(&projects_path, options.format)?,
+            )
+        };
+
+        if let Some(damage) = loaded.damage {
+            if !options.lenient {
+                return Err(damage.into());
+            }
+            log::warn!("{}, continuing with the {} task(s) that could be read", damage, loaded.tasks.len());
+        }
+
+        log::trace!("Found {} task(s) in file", loaded.tasks.len());
+        let projects = Self::readable_projects(projects, &options)?;
+
+        Ok(Self {
+            storage_path,
+            lock_path,
+            projects_path,
+            journal_path,
+            options,
+            tasks: RwLock::new(loaded.tasks),
+            projects: RwLock::new(projects),
+            held_lock: Mutex::new(Weak::new()),
+        })
+    }
+
+    /// Reads the tasks and projects of a task file without creating or locking anything, so
+    /// they can be copied into another backend. A missing file holds nothing.
+    pub fn read_contents(storage_path: &Path, options: &FileOptions) -> Result<(Vec<Task>, Vec<Project>)> {
+        if !std::fs::exists(storage_path)? {
+            return Ok((vec![], vec![]));
+        }
+
+        let loaded = Self::read_from_file(storage_path, options.format)?;
+        if let Some(damage) = loaded.damage {
+            if !options.lenient {
+                return Err(damage.into());
+            }
+            log::warn!("{}, continuing with the {} task(s) that could be read", damage, loaded.tasks.len());
+        }
+
+        let projects = Self::read_projects(&Self::sibling_path(storage_path, "projects"), options.format)?;
+        Ok((loaded.tasks, Self::readable_projects(projects, options)?))
+    }
+
+    /// The projects that could be read. A damaged projects file is refused unless the
+    /// options are lenient, just like a damaged task file.
+    fn readable_projects(loaded: LoadedProjects, options: &FileOptions) -> Result<Vec<Project>> {
+        if let Some(damage) = loaded.damage {
+            if !options.lenient {
+                return Err(damage.into());
+            }
+            log::warn!("{}, continuing with the {} project(s) that could be read", damage, loaded.projects.len());
+        }
+        Ok(loaded.projects)
+    }
+
+    /// Reads the journal kept next to a task file, without creating or locking anything.
+    pub fn read_journal_of(storage_path: &Path) -> Result<Vec<JournalEntry>> {
+        Self::read_journal(&Self::sibling_path(storage_path, "journal"))
+    }
+
+    fn read_from_file(path: &Path, format: FileFormat) -> Result<LoadedTasks> {
         let mut file = OpenOptions::new()
             .read(true)
-            .open(&storage_path)?;
+            .open(path)?;
 
         let mut file_contents = String::new();
         file.read_to_string(&mut file_contents)?;
 
-        let tasks = ron::de::from_str::<Vec<Task>>(&file_contents)
-            .unwrap_or_else(|_| vec![]);
+        // A file that has only just been created holds no tasks yet.
+        if file_contents.trim().is_empty() {
+            return Ok(LoadedTasks {
+                tasks: vec![],
+                damage: None,
+            });
+        }
 
-        log::trace!("Found {} task(s) in file", tasks.len());
+        match format.deserialise(&file_contents) {
+            Ok(tasks) => Ok(LoadedTasks {
+                tasks,
+                damage: None,
+            }),
+            Err(failure) => Ok(LoadedTasks {
+                tasks: format.salvage(&file_contents),
+                damage: Some(failure.into_error(path)),
+            }),
+        }
+    }
 
-        Ok(Self {
-            storage_path,
-            tasks: RwLock::new(tasks),
-        })
+    fn read_projects(path: &Path, format: FileFormat) -> Result<LoadedProjects> {
+        let file_contents = match std::fs::exists(path)? {
+            true => std::fs::read_to_string(path)?,
+            false => String::new(),
+        };
+        if file_contents.trim().is_empty() {
+            return Ok(LoadedProjects {
+                projects: vec![],
+                damage: None,
+            });
+        }
+
+        match format.deserialise_projects(&file_contents) {
+            Ok(projects) => Ok(LoadedProjects {
+                projects,
+                damage: None,
+            }),
+            Err(failure) => Ok(LoadedProjects {
+                projects: format.salvage_projects(&file_contents),
+                damage: Some(failure.into_error(path)),
+            }),
+        }
+    }
+
+    fn read_journal(path: &Path) -> Result<Vec<JournalEntry>> {
+        if !std::fs::exists(path)? {
+            return Ok(vec![]);
+        }
+
+        std::fs::read_to_string(path)?
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line).map_err(|e| anyhow!("Failed to read journal {}: {}", path.display(), e)))
+            .collect()
+    }
+
+    /// Reads what can be read of the journal, along with the numbers of the lines that could not.
+    fn read_journal_leniently(path: &Path) -> Result<(Vec<JournalEntry>, Vec<usize>)> {
+        if !std::fs::exists(path)? {
+            return Ok((vec![], vec![]));
+        }
+
+        let mut journal = vec![];
+        let mut unreadable = vec![];
+        for (index, line) in std::fs::read_to_string(path)?.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(line) {
+                Ok(entry) => journal.push(entry),
+                Err(_) => unreadable.push(index + 1),
+            }
+        }
+        Ok((journal, unreadable))
+    }
+
+    fn serialise_journal(journal: &[JournalEntry]) -> Result<String> {
+        let mut serialised = String::new();
+        for entry in journal.iter() {
+            serialised.push_str(&serde_json::to_string(entry)?);
+            serialised.push('\n');
+        }
+        Ok(serialised)
     }
 
-    async fn write_to_file(&self) -> Result<()> {
-        log::trace!("Writing tasks to file");
+    /// Copies a file that could not be parsed out of the way before it gets overwritten.
+    fn back_up_damaged_file(path: &Path) -> Result<PathBuf> {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let backup_path = Self::sibling_path(path, &format!("corrupt-{}", timestamp));
+
+        std::fs::copy(path, &backup_path)?;
+        log::warn!("Backed up unreadable file to {}", backup_path.display());
+
+        Ok(backup_path)
+    }
+
+    /// Replaces the task file in one step, so a crash mid-write leaves either the old or
+    /// the new contents on disk and never a truncated file.
+    fn write_to_file(path: &Path, serialised_data: &str) -> Result<()> {
+        log::trace!("Writing {}", path.display());
+        let temporary_path = Self::sibling_path(path, &format!("tmp-{}", std::process::id()));
         let mut file = OpenOptions::new()
             .write(true)
             .create(true)
             .truncate(true)
-            .open(&self.storage_path)?;
+            .open(&temporary_path)?;
 
-        let serialised_data = ron::to_string(&self.tasks.read().await.clone())?;
         file.write_all(serialised_data.as_bytes())?;
+        file.sync_all()?;
+        drop(file);
+
+        std::fs::rename(&temporary_path, path).inspect_err(|_| {
+            let _ = std::fs::remove_file(&temporary_path);
+        })?;
+
+        // Persist the rename itself; not every platform lets a directory be opened for this.
+        if let Some(directory) = path.parent()
+            && let Ok(directory) = File::open(directory) {
+            directory.sync_all()?;
+        }
+
         Ok(())
     }
 
+    /// Takes the exclusive lock, unless it is held already.
+    async fn lock_exclusive(&self) -> Result<Arc<FileLock>> {
+        let held = self.held_lock.lock().unwrap().upgrade();
+        match held {
+            Some(lock) => Ok(lock),
+            None => Ok(Arc::new(FileLock::wait_exclusive(&self.lock_path, LOCK_TIMEOUT).await?)),
+        }
+    }
+
+    /// Takes a shared lock, unless the exclusive one is held already.
+    async fn lock_shared(&self) -> Result<Arc<FileLock>> {
+        let held = self.held_lock.lock().unwrap().upgrade();
+        match held {
+            Some(lock) => Ok(lock),
+            None => Ok(Arc::new(FileLock::wait_shared(&self.lock_path, LOCK_TIMEOUT).await?)),
+        }
+    }
+
+    /// Runs a read-modify-write cycle while holding the exclusive lock, re-reading the
+    /// file first so changes made by other processes since we loaded it are kept.
+    async fn modify<T>(&self, change: impl FnOnce(&mut Vec<Task>) -> Result<T>) -> Result<T> {
+        let mut cached_tasks = self.tasks.write().await;
+        let _lock = self.lock_exclusive().await?;
+
+        let LoadedTasks { mut tasks, damage } = Self::read_from_file(&self.storage_path, self.options.format)?;
+        if let Some(damage) = damage {
+            if !self.options.lenient {
+                return Err(damage.into());
+            }
+            Self::back_up_damaged_file(&self.storage_path)?;
+        }
+
+        let result = change(&mut tasks)?;
+        Self::write_to_file(&self.storage_path, &self.options.format.serialise(&tasks)?)?;
+
+        *cached_tasks = tasks;
+        Ok(result)
+    }
+
+    /// The same read-modify-write cycle as [`Self::modify`], for the projects file.
+    async fn modify_projects<T>(&self, change: impl FnOnce(&mut Vec<Project>) -> Result<T>) -> Result<T> {
+        let mut cached_projects = self.projects.write().await;
+        let _lock = self.lock_exclusive().await?;
+
+        let LoadedProjects { mut projects, damage } = Self::read_projects(&self.projects_path, self.options.format)?;
+        if let Some(damage) = damage {
+            if !self.options.lenient {
+                return Err(damage.into());
+            }
+            Self::back_up_damaged_file(&self.projects_path)?;
+        }
+
+        let result = change(&mut projects)?;
+        Self::write_to_file(&self.projects_path, &self.options.format.serialise_projects(&projects)?)?;
+
+        *cached_projects = projects;
+        Ok(result)
+    }
+
+    /// The same read-modify-write cycle as [`Self::modify`], for the journal.
+    async fn modify_journal(&self, change: impl FnOnce(&mut Vec<JournalEntry>)) -> Result<()> {
+        let _lock = self.lock_exclusive().await?;
+
+        let mut journal = Self::read_journal(&self.journal_path)?;
+        change(&mut journal);
+        Self::write_to_file(&self.journal_path, &Self::serialise_journal(&journal)?)
+    }
+
+    /// Checks the task file for damage and duplicate ids, and fixes what it can when
+    /// `repair` is set. The original file is backed up before anything is rewritten.
+    pub fn doctor(storage_path: &Path, options: &FileOptions, repair: bool) -> Result<DoctorReport> {
+        log::trace!("Checking task file {}", storage_path.display());
+        let mut report = DoctorReport::default();
+
+        if !std::fs::exists(storage_path)? {
+            log::info!("Task file {} does not exist yet", storage_path.display());
+            return Ok(report);
+        }
+
+        let _lock = FileLock::exclusive(Self::sibling_path(storage_path, "lock"), LOCK_TIMEOUT)?;
+        let LoadedTasks { mut tasks, damage } = Self::read_from_file(storage_path, options.format)?;
+
+        if let Some(damage) = damage {
+            report.problems.push(format!("{} ({} task(s) can be recovered)", damage, tasks.len()));
+        }
+
+        let projects_path = Self::sibling_path(storage_path, "projects");
+        let LoadedProjects { projects, damage: projects_damage } = Self::read_projects(&projects_path, options.format)?;
+        let project_ids: HashSet<_> = projects.iter().map(|project| project.id).collect();
+        for task in tasks.iter_mut() {
+            if let Some(project_id) = task.project_id
+                && !project_ids.contains(&project_id) {
+                report.problems.push(format!("Task {} belongs to project {}, which does not exist", task.id, project_id));
+                if repair {
+                    report.repairs.push(format!("Removed task {} from project {}", task.id, project_id));
+                    task.project_id = None;
+                }
+            }
+        }
+
+        let task_ids: HashSet<_> = tasks.iter().map(|task| task.id).collect();
+        for index in 0..tasks.len() {
+            let Some(parent_id) = tasks[index].parent_id else {
+                continue;
+            };
+
+            let problem = if !task_ids.contains(&parent_id) {
+                format!("Task {} is a subtask of task {}, which does not exist", tasks[index].id, parent_id)
+            } else if would_create_cycle(&tasks, tasks[index].id, parent_id) {
+                format!("Task {} is a subtask of itself", tasks[index].id)
+            } else {
+                continue;
+            };
+
+            report.problems.push(problem);
+            if repair {
+                report.repairs.push(format!("Moved task {} to the top level", tasks[index].id));
+                tasks[index].parent_id = None;
+            }
+        }
+
+        for task in tasks.iter_mut() {
+            let missing: Vec<_> = task.depends_on
+                                      .iter()
+                                      .copied()
+                                      .filter(|depends_on| !task_ids.contains(depends_on))
+                                      .collect();
+
+            for depends_on in missing {
+                report.problems.push(format!("Task {} depends on task {}, which does not exist", task.id, depends_on));
+                if repair {
+                    report.repairs.push(format!("Removed the dependency of task {} on task {}", task.id, depends_on));
+                    task.depends_on.retain(|id| *id != depends_on);
+                }
+            }
+        }
+
+        let mut seen_ids = HashSet::new();
+        let mut next_id = tasks.iter().map(|task| task.id).max().unwrap_or(0) + 1;
+        for task in tasks.iter_mut() {
+            if seen_ids.insert(task.id) {
+                continue;
+            }
+
+            report.problems.push(format!("More than one task has id {}", task.id));
+            if repair {
+                report.repairs.push(format!("Renumbered \"{}\" from {} to {}", task.name, task.id, next_id));
+                task.id = next_id;
+                next_id += 1;
+            }
+        }
+
+        if repair && !report.is_healthy() {
+            let backup_path = Self::back_up_damaged_file(storage_path)?;
+            report.repairs.push(format!("Backed up the original file to {}", backup_path.display()));
+
+            Self::write_to_file(storage_path, &options.format.serialise(&tasks)?)?;
+            report.repairs.push(format!("Rewrote the task file with {} task(s)", tasks.len()));
+        }
+
+        if let Some(damage) = projects_damage {
+            report.problems.push(format!("{} ({} project(s) can be recovered)", damage, projects.len()));
+            if repair {
+                let backup_path = Self::back_up_damaged_file(&projects_path)?;
+                report.repairs.push(format!("Backed up the original projects file to {}", backup_path.display()));
+
+                Self::write_to_file(&projects_path, &options.format.serialise_projects(&projects)?)?;
+                report.repairs.push(format!("Rewrote the projects file with {} project(s)", projects.len()));
+            }
+        }
+
+        let journal_path = Self::sibling_path(storage_path, "journal");
+        let (journal, unreadable) = Self::read_journal_leniently(&journal_path)?;
+        if !unreadable.is_empty() {
+            report.problems.push(format!("Line(s) {:?} of the journal {} cannot be read ({} entry(ies) can be recovered)",
+                                         unreadable, journal_path.display(), journal.len()));
+            if repair {
+                let backup_path = Self::back_up_damaged_file(&journal_path)?;
+                report.repairs.push(format!("Backed up the original journal to {}", backup_path.display()));
+
+                Self::write_to_file(&journal_path, &Self::serialise_journal(&journal)?)?;
+                report.repairs.push(format!("Rewrote the journal with {} entry(ies)", journal.len()));
+            }
+        }
+
+        Ok(report)
+    }
+
+    fn sibling_path(path: &Path, extension: &str) -> PathBuf {
+        let mut file_name = path.file_name()
+                                .map(OsString::from)
+                                .unwrap_or_default();
+        file_name.push(".");
+        file_name.push(extension);
+        path.with_file_name(file_name)
+    }
+
     fn create_file(path: impl AsRef<Path>) -> Result<()> {
         let path = path.as_ref();
         log::trace!("Creating file {}", path.display());
@@ -71,62 +505,358 @@ impl FileRepository {
 impl Repository for FileRepository {
     async fn get_all(&self) -> Result<Vec<Task>> {
         log::trace!("Getting all tasks");
-        let tasks = self.tasks.read().await;
-        Ok(tasks.clone())
+        let mut tasks: Vec<Task> = self.tasks.read().await.iter().filter(|task| task.deleted_at.is_none()).cloned().collect();
+        // Hand-edited or repaired files can be out of order; the databases always sort by id.
+        tasks.sort_by_key(|task| task.id);
+        Ok(tasks)
     }
 
     async fn get_by_id(&self, id: i32) -> Result<Task> {
         log::trace!("Getting task with id {}", id);
         let tasks = self.tasks.read().await;
         Ok(tasks.iter()
-                .find(|task| task.id == id)
-                .ok_or_else(|| anyhow!("No task with id {} found", id))?
+                .find(|task| task.id == id && task.deleted_at.is_none())
+                .ok_or(TaskNotFoundError { id })?
             .clone()
         )
     }
 
-    async fn add(&self, task: NewTask) -> Result<()> {
+    async fn add(&self, task: NewTask) -> Result<i32> {
         log::trace!("Adding new task");
-        {
-            let mut tasks = self.tasks.write().await;
-            let new_id = tasks.iter()
-                              .map(|a| a.id)
-                              .max()
-                              .map(|max| max + 1)
-                              .unwrap_or(1);
-            let new_task = Task {
-                id: new_id,
-                name: task.name,
-                description: task.description,
-                completed: false,
-            };
-
-            tasks.push(new_task);
-        }
-        self.write_to_file().await?;
-        Ok(())
+        self.modify(|tasks| {
+            let id = task_list::next_id(tasks.iter().map(|task| task.id));
+            task_list::add_task(tasks, id, task, Utc::now())?;
+            Ok(id)
+        }).await
     }
 
     async fn remove(&self, id: i32) -> Result<()> {
         log::trace!("Removing task with id {}", id);
-        {
-            let mut tasks = self.tasks.write().await;
-            tasks.retain(|task| task.id != id);
-        }
-        self.write_to_file().await?;
-        Ok(())
+        self.modify(|tasks| task_list::remove_task(tasks, id, Utc::now())).await
     }
 
     async fn update(&self, task: Task) -> Result<()> {
         log::trace!("Updating task with id {}", task.id);
-        {
-            let mut tasks = self.tasks.write().await;
-            let index = tasks.iter()
-                             .position(|a| a.id == task.id)
-                             .ok_or_else(|| anyhow!("No task with id {} found", task.id))?;
-            tasks[index] = task;
-        }
-        self.write_to_file().await?;
-        Ok(())
+        self.modify(|tasks| task_list::update_task(tasks, task, Utc::now())).await
+    }
+
+    async fn get_trash(&self) -> Result<Vec<Task>> {
+        log::trace!("Getting trash");
+        let mut tasks: Vec<Task> = self.tasks.read().await.iter().filter(|task| task.deleted_at.is_some()).cloned().collect();
+        tasks.sort_by_key(|task| task.id);
+        Ok(tasks)
+    }
+
+    async fn empty_trash(&self, deleted_before: DateTime<Utc>) -> Result<usize> {
+        log::trace!("Emptying trash of tasks deleted before {}", deleted_before);
+        // This runs on every start, so the file is only written when something has expired.
+        let expired = self.tasks.read().await.iter().any(|task| task.deleted_at.is_some_and(|deleted_at| deleted_at < deleted_before));
+        if !expired {
+            return Ok(0);
+        }
+        self.modify(|tasks| Ok(task_list::empty_trash(tasks, deleted_before))).await
+    }
+
+    async fn delete(&self, id: i32) -> Result<()> {
+        log::trace!("Deleting task with id {}", id);
+        self.modify(|tasks| task_list::delete_task(tasks, id)).await
+    }
+
+    async fn get_projects(&self) -> Result<Vec<Project>> {
+        log::trace!("Getting all projects");
+        let mut projects = self.projects.read().await.clone();
+        projects.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(projects)
+    }
+
+    async fn add_project(&self, project: NewProject) -> Result<()> {
+        log::trace!("Adding new project");
+        self.modify_projects(|projects| {
+            let id = task_list::next_id(projects.iter().map(|project| project.id));
+            task_list::add_project(projects, id, project)
+        }).await
+    }
+
+    async fn archive_project(&self, id: i32, archived: bool) -> Result<()> {
+        log::trace!("Setting archived to {} on project with id {}", archived, id);
+        self.modify_projects(|projects| task_list::archive_project(projects, id, archived)).await
+    }
+
+    async fn import_task(&self, task: Task) -> Result<()> {
+        log::trace!("Importing task with id {}", task.id);
+        self.modify(|tasks| task_list::import_task(tasks, task)).await
+    }
+
+    async fn import_project(&self, project: Project) -> Result<()> {
+        log::trace!("Importing project with id {}", project.id);
+        self.modify_projects(|projects| task_list::import_project(projects, project)).await
+    }
+
+    async fn hold_lock(&self) -> Result<Option<Arc<FileLock>>> {
+        let lock = self.lock_exclusive().await?;
+        *self.held_lock.lock().unwrap() = Arc::downgrade(&lock);
+        Ok(Some(lock))
+    }
+
+    async fn add_journal_entry(&self, entry: NewJournalEntry) -> Result<()> {
+        log::trace!("Adding journal entry for \"{}\"", entry.command);
+        self.modify_journal(|journal| task_list::add_journal_entry(journal, entry)).await
+    }
+
+    async fn get_journal(&self) -> Result<Vec<JournalEntry>> {
+        log::trace!("Getting journal");
+        let _lock = self.lock_shared().await?;
+        Self::read_journal(&self.journal_path)
+    }
+
+    async fn set_undone(&self, ids: &[i64], undone: bool) -> Result<()> {
+        log::trace!("Setting undone to {} on journal entries {:?}", undone, ids);
+        self.modify_journal(|journal| task_list::set_undone(journal, ids, undone)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FileOptions, FileRepository};
+    use crate::error::{DependencyCycleError, LockTimeoutError, TaskCycleError, TaskFileParseError};
+    use crate::journal::NewJournalEntry;
+    use crate::persistence::file_lock::FileLock;
+    use crate::persistence::journaling_repository::JournalingRepository;
+    use crate::persistence::Repository;
+    use crate::projects::NewProject;
+    use crate::tasks::{NewTask, Task, TaskFilter};
+    use chrono::Utc;
+    use std::time::Duration;
+
+    fn new_task(name: &str) -> NewTask {
+        NewTask {
+            name: name.into(),
+            ..NewTask::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn concurrent_writers_do_not_lose_updates() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("tasks.ron");
+
+        let first = FileRepository::new(path.clone(), FileOptions::default()).unwrap();
+        let second = FileRepository::new(path.clone(), FileOptions::default()).unwrap();
+
+        first.add(new_task("first")).await.unwrap();
+        second.add(new_task("second")).await.unwrap();
+
+        let tasks = FileRepository::new(path, FileOptions::default()).unwrap().get_all().await.unwrap();
+        let names: Vec<_> = tasks.iter().map(|task| task.name()).collect();
+        assert_eq!(names, vec!["first", "second"]);
+        assert_eq!(tasks[1].id, 2);
+    }
+
+    #[tokio::test]
+    async fn changes_share_a_held_lock_and_keep_other_writers_out() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("tasks.ron");
+
+        let first = FileRepository::new(path.clone(), FileOptions::default()).unwrap();
+        let held = first.hold_lock().await.unwrap();
+        first.add(new_task("first")).await.unwrap();
+        assert!(first.get_journal().await.unwrap().is_empty());
+
+        let lock_path = directory.path().join("tasks.ron.lock");
+        let waited = FileLock::shared(&lock_path, Duration::from_millis(100));
+        assert!(waited.err().unwrap().downcast_ref::<LockTimeoutError>().is_some());
+        drop(held);
+        let second = FileRepository::new(path, FileOptions::default()).unwrap();
+        second.add(new_task("second")).await.unwrap();
+        assert_eq!(second.get_all().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn writes_leave_no_temporary_files_behind() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("tasks.ron");
+
+        let repository = FileRepository::new(path, FileOptions::default()).unwrap();
+        repository.add(new_task("only")).await.unwrap();
+
+        let mut entries: Vec<_> = std::fs::read_dir(directory.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        entries.sort();
+        assert_eq!(entries, vec!["tasks.ron", "tasks.ron.lock"]);
+    }
+
+    const DAMAGED: &str = r#"[(id:1,name:"kept",description:None,completed:false),(id:2,name:"lo"#;
+
+    #[test]
+    fn damaged_file_is_refused_by_default() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("tasks.ron");
+        std::fs::write(&path, DAMAGED).unwrap();
+
+        let error = FileRepository::new(path.clone(), FileOptions::default()).err().unwrap();
+        let error = error.downcast_ref::<TaskFileParseError>().unwrap();
+        assert_eq!(error.line, 1);
+        assert_eq!(std::fs::read_to_string(path).unwrap(), DAMAGED);
+    }
+
+    #[tokio::test]
+    async fn lenient_mode_backs_up_before_writing() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("tasks.ron");
+        std::fs::write(&path, DAMAGED).unwrap();
+
+        let repository = FileRepository::new(path.clone(), FileOptions { lenient: true, ..FileOptions::default() }).unwrap();
+        repository.add(new_task("added")).await.unwrap();
+
+        let names: Vec<_> = repository.get_all().await.unwrap().into_iter().map(|task| task.name).collect();
+        assert_eq!(names, vec!["kept", "added"]);
+
+        let backups: Vec<_> = std::fs::read_dir(directory.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.to_string_lossy().contains(".corrupt-"))
+            .collect();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(std::fs::read_to_string(&backups[0]).unwrap(), DAMAGED);
+    }
+
+    #[test]
+    fn doctor_repairs_a_damaged_file() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("tasks.ron");
+        std::fs::write(&path, DAMAGED).unwrap();
+
+        let check = FileRepository::doctor(&path, &FileOptions::default(), false).unwrap();
+        assert!(!check.is_healthy());
+        assert!(check.repairs.is_empty());
+
+        FileRepository::doctor(&path, &FileOptions::default(), true).unwrap();
+        assert!(FileRepository::doctor(&path, &FileOptions::default(), false).unwrap().is_healthy());
+        assert!(FileRepository::new(path, FileOptions::default()).is_ok());
+    }
+
+    #[tokio::test]
+    async fn doctor_repairs_a_damaged_projects_file() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("tasks.ron");
+        let repository = FileRepository::new(path.clone(), FileOptions::default()).unwrap();
+        for name in ["house", "garden"] {
+            repository.add_project(NewProject { name: name.into(), ..NewProject::default() }).await.unwrap();
+        }
+        let projects_path = directory.path().join("tasks.ron.projects");
+        let contents = std::fs::read_to_string(&projects_path).unwrap();
+        std::fs::write(&projects_path, &contents[..contents.find("garden").unwrap()]).unwrap();
+
+        let error = FileRepository::new(path.clone(), FileOptions::default()).err().unwrap();
+        assert!(error.downcast_ref::<TaskFileParseError>().is_some());
+        let lenient = FileRepository::new(path.clone(), FileOptions { lenient: true, ..FileOptions::default() }).unwrap();
+        assert_eq!(lenient.get_projects().await.unwrap().len(), 1);
+
+        let check = FileRepository::doctor(&path, &FileOptions::default(), false).unwrap();
+        assert!(check.problems[0].contains("1 project(s) can be recovered"), "{:?}", check.problems);
+        FileRepository::doctor(&path, &FileOptions::default(), true).unwrap();
+        assert!(FileRepository::doctor(&path, &FileOptions::default(), false).unwrap().is_healthy());
+
+        let repository = FileRepository::new(path, FileOptions::default()).unwrap();
+        let names: Vec<_> = repository.get_projects().await.unwrap().into_iter().map(|project| project.name).collect();
+        assert_eq!(names, ["house"]);
+    }
+
+    #[tokio::test]
+    async fn doctor_repairs_a_damaged_journal() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("tasks.ron");
+        let repository = FileRepository::new(path.clone(), FileOptions::default()).unwrap();
+        repository.add_journal_entry(NewJournalEntry {
+            batch: 1,
+            command: "add kept".into(),
+            created_at: Utc::now(),
+            changes: vec![],
+        }).await.unwrap();
+        let journal_path = directory.path().join("tasks.ron.journal");
+        let mut journal = std::fs::read_to_string(&journal_path).unwrap();
+        journal.push_str("{\"id\":2,\"bat\n");
+        std::fs::write(&journal_path, journal).unwrap();
+        assert!(repository.get_journal().await.is_err());
+
+        let journaled = JournalingRepository::for_batch(Box::new(FileRepository::new(path.clone(), FileOptions::default()).unwrap()), 2, "add saved");
+        journaled.add(new_task("saved")).await.unwrap();
+        assert_eq!(journaled.get_by_id(1).await.unwrap().name, "saved");
+
+        let check = FileRepository::doctor(&path, &FileOptions::default(), false).unwrap();
+        assert!(check.problems[0].contains("Line(s) [2] of the journal"), "{:?}", check.problems);
+
+        FileRepository::doctor(&path, &FileOptions::default(), true).unwrap();
+        assert!(FileRepository::doctor(&path, &FileOptions::default(), false).unwrap().is_healthy());
+        let commands: Vec<_> = repository.get_journal().await.unwrap().into_iter().map(|entry| entry.command).collect();
+        assert_eq!(commands, ["add kept"]);
+    }
+
+    #[tokio::test]
+    async fn archived_projects_hide_their_tasks() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("tasks.json");
+
+        let repository = FileRepository::new(path.clone(), FileOptions::default()).unwrap();
+        repository.add_project(NewProject { name: "garden".into(), ..NewProject::default() }).await.unwrap();
+        let garden = repository.get_project_by_name("garden").await.unwrap();
+        repository.add(NewTask { project_id: Some(garden.id), ..new_task("mow") }).await.unwrap();
+        repository.add(new_task("loose")).await.unwrap();
+
+        repository.archive_project(garden.id, true).await.unwrap();
+
+        let repository = FileRepository::new(path, FileOptions::default()).unwrap();
+        let names = |tasks: Vec<Task>| tasks.into_iter().map(|task| task.name).collect::<Vec<_>>();
+        assert_eq!(names(repository.find(&TaskFilter::default()).await.unwrap()), vec!["loose"]);
+        assert_eq!(names(repository.find(&TaskFilter { archived: true, ..TaskFilter::default() }).await.unwrap()), vec!["mow", "loose"]);
+        assert_eq!(names(repository.find(&TaskFilter { project_id: Some(garden.id), ..TaskFilter::default() }).await.unwrap()), vec!["mow"]);
+    }
+
+    #[tokio::test]
+    async fn subtasks_cannot_become_their_own_ancestor() {
+        let directory = tempfile::tempdir().unwrap();
+        let repository = FileRepository::new(directory.path().join("tasks.ron"), FileOptions::default()).unwrap();
+
+        repository.add(new_task("parent")).await.unwrap();
+        repository.add(NewTask { parent_id: Some(1), ..new_task("child") }).await.unwrap();
+
+        let mut parent = repository.get_by_id(1).await.unwrap();
+        parent.parent_id = Some(2);
+        let error = repository.update(parent).await.unwrap_err();
+        assert!(error.is::<TaskCycleError>());
+
+        repository.remove(1).await.unwrap();
+        assert_eq!(repository.get_by_id(2).await.unwrap().parent_id, None);
+    }
+
+    #[tokio::test]
+    async fn dependency_cycles_are_refused_and_blocked_tasks_are_not_ready() {
+        let directory = tempfile::tempdir().unwrap();
+        let repository = FileRepository::new(directory.path().join("tasks.ron"), FileOptions::default()).unwrap();
+        for name in ["first", "second", "third"] {
+            repository.add(new_task(name)).await.unwrap();
+        }
+
+        let mut third = repository.get_by_id(3).await.unwrap();
+        third.depends_on = vec![2];
+        repository.update(third).await.unwrap();
+        let mut second = repository.get_by_id(2).await.unwrap();
+        second.depends_on = vec![1];
+        repository.update(second).await.unwrap();
+
+        let mut first = repository.get_by_id(1).await.unwrap();
+        first.depends_on = vec![3];
+        let error = repository.update(first).await.unwrap_err();
+        assert_eq!(error.downcast_ref::<DependencyCycleError>().unwrap().cycle, vec![1, 3, 2, 1]);
+
+        let ready: Vec<_> = repository.find(&TaskFilter { ready: true, ..TaskFilter::default() })
+                                      .await
+                                      .unwrap()
+                                      .into_iter()
+                                      .map(|task| task.id)
+                                      .collect();
+        assert_eq!(ready, vec![1]);
     }
 }
@@ -0,0 +1,201 @@
+use crate::error::TaskNotFoundError;
+use crate::journal::{JournalEntry, NewJournalEntry, TaskChange};
+use crate::persistence::file_lock::FileLock;
+use crate::persistence::{Repository, SchemaStatus};
+use crate::projects::{NewProject, Project};
+use crate::search::{SearchHit, SearchTerms};
+use crate::tasks::{NewTask, Task, TaskFilter};
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::sync::{Arc, LazyLock};
+
+/// Every change made by one run of `todo` goes into the same batch, so it is undone in one go.
+static BATCH: LazyLock<i64> = LazyLock::new(|| Utc::now().timestamp_micros());
+
+/// Wraps a repository so that every `add`, `remove` and `update` is written to its journal,
+/// with every task it changed as it was before and after, for `todo undo` to work from.
+pub struct JournalingRepository {
+    inner: Box<dyn Repository + Sync>,
+    batch: i64,
+    command: String,
+}
+
+impl JournalingRepository {
+    /// Records changes under this run of `todo` and its command line.
+    pub fn new(inner: Box<dyn Repository + Sync>) -> Self {
+        Self::for_batch(inner, *BATCH, &std::env::args().skip(1).collect::<Vec<_>>().join(" "))
+    }
+
+    pub fn for_batch(inner: Box<dyn Repository + Sync>, batch: i64, command: &str) -> Self {
+        Self {
+            inner,
+            batch,
+            command: command.into(),
+        }
+    }
+
+    /// Records a change that has been saved, with a warning if that fails rather than an
+    /// error: failing the command would only invite running it again and saving it twice.
+    async fn record(&self, before: Vec<Task>, ids: &[i32]) {
+        if let Err(e) = self.try_record(before, ids).await {
+            log::warn!("The change was saved, but could not be recorded for `todo undo`: {}", e);
+        }
+    }
+
+    /// Records how the tasks with `ids` differ from `before`, the states they were in before
+    /// a change to the inner repository. Only those tasks are read back, so a change costs
+    /// the same however many other tasks there are. Tasks that are no longer there are looked
+    /// for in the trash.
+    async fn try_record(&self, before: Vec<Task>, ids: &[i32]) -> Result<()> {
+        let mut after = vec![];
+        let mut trash = None;
+        for id in ids {
+            match self.inner.get_by_id(*id).await {
+                Ok(task) => after.push(task),
+                Err(e) if e.is::<TaskNotFoundError>() => {
+                    let trash = match &trash {
+                        Some(trash) => trash,
+                        None => trash.insert(self.inner.get_trash().await?),
+                    };
+                    after.extend(trash.iter().find(|task| task.id == *id).cloned());
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        let changes = TaskChange::between(&before, &after);
+        if changes.is_empty() {
+            return Ok(());
+        }
+
+        self.inner.add_journal_entry(NewJournalEntry {
+            batch: self.batch,
+            command: self.command.clone(),
+            created_at: Utc::now(),
+            changes,
+        }).await
+    }
+}
+
+#[async_trait]
+impl Repository for JournalingRepository {
+    async fn get_all(&self) -> Result<Vec<Task>> {
+        self.inner.get_all().await
+    }
+
+    async fn get_by_id(&self, id: i32) -> Result<Task> {
+        self.inner.get_by_id(id).await
+    }
+
+    async fn add(&self, task: NewTask) -> Result<i32> {
+        let _lock = self.inner.hold_lock().await?;
+        let id = self.inner.add(task).await?;
+        self.record(vec![], &[id]).await;
+        Ok(id)
+    }
+
+    /// Subtasks and dependents let go of the removed task, so they are recorded too.
+    async fn remove(&self, id: i32) -> Result<()> {
+        let _lock = self.inner.hold_lock().await?;
+        let before = self.inner.get_related(id).await?;
+        let ids: Vec<i32> = before.iter().map(|task| task.id).collect();
+        self.inner.remove(id).await?;
+        self.record(before, &ids).await;
+        Ok(())
+    }
+
+    async fn update(&self, task: Task) -> Result<()> {
+        let _lock = self.inner.hold_lock().await?;
+        let before = vec![self.inner.get_by_id(task.id).await?];
+        let id = task.id;
+        self.inner.update(task).await?;
+        self.record(before, &[id]).await;
+        Ok(())
+    }
+
+    async fn get_trash(&self) -> Result<Vec<Task>> {
+        self.inner.get_trash().await
+    }
+
+    async fn empty_trash(&self, deleted_before: DateTime<Utc>) -> Result<usize> {
+        self.inner.empty_trash(deleted_before).await
+    }
+
+    async fn delete(&self, id: i32) -> Result<()> {
+        self.inner.delete(id).await
+    }
+
+    async fn restore_from_trash(&self, id: i32) -> Result<()> {
+        let _lock = self.inner.hold_lock().await?;
+        let before: Vec<Task> = self.inner.get_trash().await?.into_iter().filter(|task| task.id == id).collect();
+        self.inner.restore_from_trash(id).await?;
+        self.record(before, &[id]).await;
+        Ok(())
+    }
+
+    async fn get_projects(&self) -> Result<Vec<Project>> {
+        self.inner.get_projects().await
+    }
+
+    async fn add_project(&self, project: NewProject) -> Result<()> {
+        self.inner.add_project(project).await
+    }
+
+    async fn archive_project(&self, id: i32, archived: bool) -> Result<()> {
+        self.inner.archive_project(id, archived).await
+    }
+
+    async fn import_task(&self, task: Task) -> Result<()> {
+        self.inner.import_task(task).await
+    }
+
+    async fn import_project(&self, project: Project) -> Result<()> {
+        self.inner.import_project(project).await
+    }
+
+    async fn add_journal_entry(&self, entry: NewJournalEntry) -> Result<()> {
+        self.inner.add_journal_entry(entry).await
+    }
+
+    async fn get_journal(&self) -> Result<Vec<JournalEntry>> {
+        self.inner.get_journal().await
+    }
+
+    async fn set_undone(&self, ids: &[i64], undone: bool) -> Result<()> {
+        self.inner.set_undone(ids, undone).await
+    }
+
+    // The backends' own queries are kept rather than falling back to the defaults.
+
+    async fn find(&self, filter: &TaskFilter) -> Result<Vec<Task>> {
+        self.inner.find(filter).await
+    }
+
+    async fn get_related(&self, id: i32) -> Result<Vec<Task>> {
+        self.inner.get_related(id).await
+    }
+
+    async fn hold_lock(&self) -> Result<Option<Arc<FileLock>>> {
+        self.inner.hold_lock().await
+    }
+
+    async fn search(&self, terms: &SearchTerms, limit: usize) -> Result<Vec<SearchHit>> {
+        self.inner.search(terms, limit).await
+    }
+
+    async fn get_project_by_name(&self, name: &str) -> Result<Project> {
+        self.inner.get_project_by_name(name).await
+    }
+
+    async fn migrate(&self) -> Result<()> {
+        self.inner.migrate().await
+    }
+
+    async fn get_tags(&self) -> Result<Vec<(String, i64)>> {
+        self.inner.get_tags().await
+    }
+
+    async fn schema_status(&self) -> Result<Option<SchemaStatus>> {
+        self.inner.schema_status().await
+    }
+}
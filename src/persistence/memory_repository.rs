@@ -0,0 +1,207 @@
+use crate::error::TaskNotFoundError;
+use crate::journal::{JournalEntry, NewJournalEntry};
+use crate::persistence::file_repository::{FileOptions, FileRepository};
+use crate::persistence::task_list;
+use crate::persistence::transfer::all_tasks;
+use crate::persistence::Repository;
+use crate::projects::{NewProject, Project};
+use crate::tasks::{NewTask, Task};
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::path::Path;
+use tokio::sync::RwLock;
+use url::Url;
+
+struct Contents {
+    tasks: Vec<Task>,
+    projects: Vec<Project>,
+    journal: Vec<JournalEntry>,
+    // Ids are handed out from counters, as the databases do, so a removed id is never reused.
+    next_task_id: i32,
+    next_project_id: i32,
+}
+
+/// Keeps everything in memory and forgets it on exit. Used for `mem://` storage, which is
+/// handy for trying things out and in tests, and underneath `--dry-run`.
+pub struct MemoryRepository {
+    contents: RwLock<Contents>,
+}
+
+impl MemoryRepository {
+    pub fn new(tasks: Vec<Task>, projects: Vec<Project>) -> Self {
+        log::trace!("Creating new MemoryRepository with {} task(s) and {} project(s)", tasks.len(), projects.len());
+
+        let next_task_id = task_list::next_id(tasks.iter().map(|task| task.id));
+        let next_project_id = task_list::next_id(projects.iter().map(|project| project.id));
+        Self {
+            contents: RwLock::new(Contents {
+                tasks,
+                projects,
+                journal: vec![],
+                next_task_id,
+                next_project_id,
+            }),
+        }
+    }
+
+    /// Opens a `mem://` URL. A path, as in `mem:///home/user/fixture.ron`, names a task file
+    /// to start from; it is read the same way as a `file://` URL but never written back.
+    pub fn from_url(url: &Url) -> Result<Self> {
+        if url.path().is_empty() || url.path() == "/" {
+            return Ok(Self::new(vec![], vec![]));
+        }
+
+        let (tasks, projects) = FileRepository::read_contents(Path::new(url.path()), &FileOptions::from_url(url)?)?;
+        Ok(Self::new(tasks, projects))
+    }
+
+    /// Starts the journal off with `journal` instead of empty.
+    pub fn with_journal(mut self, journal: Vec<JournalEntry>) -> Self {
+        self.contents.get_mut().journal = journal;
+        self
+    }
+
+    /// Copies everything out of another repository, trash and journal included.
+    pub async fn copy_of(repository: &(dyn Repository + Sync)) -> Result<Self> {
+        let tasks = all_tasks(repository).await?;
+        Ok(Self::new(tasks, repository.get_projects().await?).with_journal(repository.get_journal().await?))
+    }
+}
+
+#[async_trait]
+impl Repository for MemoryRepository {
+    async fn get_all(&self) -> Result<Vec<Task>> {
+        log::trace!("Getting all tasks");
+        let mut tasks: Vec<Task> = self.contents.read().await.tasks.iter().filter(|task| task.deleted_at.is_none()).cloned().collect();
+        tasks.sort_by_key(|task| task.id);
+        Ok(tasks)
+    }
+
+    async fn get_by_id(&self, id: i32) -> Result<Task> {
+        log::trace!("Getting task with id {}", id);
+        let contents = self.contents.read().await;
+        Ok(contents.tasks
+                   .iter()
+                   .find(|task| task.id == id && task.deleted_at.is_none())
+                   .ok_or(TaskNotFoundError { id })?
+                   .clone())
+    }
+
+    async fn add(&self, task: NewTask) -> Result<i32> {
+        log::trace!("Adding new task");
+        let mut contents = self.contents.write().await;
+        let id = contents.next_task_id;
+        task_list::add_task(&mut contents.tasks, id, task, Utc::now())?;
+        contents.next_task_id += 1;
+        Ok(id)
+    }
+
+    async fn remove(&self, id: i32) -> Result<()> {
+        log::trace!("Removing task with id {}", id);
+        task_list::remove_task(&mut self.contents.write().await.tasks, id, Utc::now())
+    }
+
+    async fn update(&self, task: Task) -> Result<()> {
+        log::trace!("Updating task with id {}", task.id);
+        task_list::update_task(&mut self.contents.write().await.tasks, task, Utc::now())
+    }
+
+    async fn get_trash(&self) -> Result<Vec<Task>> {
+        log::trace!("Getting trash");
+        let mut tasks: Vec<Task> = self.contents.read().await.tasks.iter().filter(|task| task.deleted_at.is_some()).cloned().collect();
+        tasks.sort_by_key(|task| task.id);
+        Ok(tasks)
+    }
+
+    async fn empty_trash(&self, deleted_before: DateTime<Utc>) -> Result<usize> {
+        log::trace!("Emptying trash of tasks deleted before {}", deleted_before);
+        Ok(task_list::empty_trash(&mut self.contents.write().await.tasks, deleted_before))
+    }
+
+    async fn delete(&self, id: i32) -> Result<()> {
+        log::trace!("Deleting task with id {}", id);
+        task_list::delete_task(&mut self.contents.write().await.tasks, id)
+    }
+
+    async fn get_projects(&self) -> Result<Vec<Project>> {
+        log::trace!("Getting all projects");
+        let mut projects = self.contents.read().await.projects.clone();
+        projects.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(projects)
+    }
+
+    async fn add_project(&self, project: NewProject) -> Result<()> {
+        log::trace!("Adding new project");
+        let mut contents = self.contents.write().await;
+        let id = contents.next_project_id;
+        task_list::add_project(&mut contents.projects, id, project)?;
+        contents.next_project_id += 1;
+        Ok(())
+    }
+
+    async fn archive_project(&self, id: i32, archived: bool) -> Result<()> {
+        log::trace!("Setting archived to {} on project with id {}", archived, id);
+        task_list::archive_project(&mut self.contents.write().await.projects, id, archived)
+    }
+
+    async fn import_task(&self, task: Task) -> Result<()> {
+        log::trace!("Importing task with id {}", task.id);
+        let mut contents = self.contents.write().await;
+        let next_task_id = contents.next_task_id.max(task.id + 1);
+        task_list::import_task(&mut contents.tasks, task)?;
+        contents.next_task_id = next_task_id;
+        Ok(())
+    }
+
+    async fn import_project(&self, project: Project) -> Result<()> {
+        log::trace!("Importing project with id {}", project.id);
+        let mut contents = self.contents.write().await;
+        let next_project_id = contents.next_project_id.max(project.id + 1);
+        task_list::import_project(&mut contents.projects, project)?;
+        contents.next_project_id = next_project_id;
+        Ok(())
+    }
+
+    async fn add_journal_entry(&self, entry: NewJournalEntry) -> Result<()> {
+        log::trace!("Adding journal entry for \"{}\"", entry.command);
+        task_list::add_journal_entry(&mut self.contents.write().await.journal, entry);
+        Ok(())
+    }
+
+    async fn get_journal(&self) -> Result<Vec<JournalEntry>> {
+        log::trace!("Getting journal");
+        Ok(self.contents.read().await.journal.clone())
+    }
+
+    async fn set_undone(&self, ids: &[i64], undone: bool) -> Result<()> {
+        log::trace!("Setting undone to {} on journal entries {:?}", undone, ids);
+        task_list::set_undone(&mut self.contents.write().await.journal, ids, undone);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MemoryRepository;
+    use crate::persistence::Repository;
+    use crate::tasks::NewTask;
+    use url::Url;
+
+    #[tokio::test]
+    async fn fixtures_are_read_but_never_written() {
+        let directory = tempfile::tempdir().unwrap();
+        let fixture = directory.path().join("fixture.json");
+        std::fs::write(&fixture, r#"[{"id": 4, "name": "Water the plants", "status": "todo",
+                                      "created_at": "2026-01-01T00:00:00Z", "updated_at": "2026-01-01T00:00:00Z"}]"#).unwrap();
+        let url = Url::parse(&format!("mem://{}", fixture.display())).unwrap();
+
+        let repository = MemoryRepository::from_url(&url).unwrap();
+        repository.add(NewTask { name: "Buy milk".into(), ..NewTask::default() }).await.unwrap();
+        let ids: Vec<i32> = repository.get_all().await.unwrap().iter().map(|task| task.id).collect();
+
+        assert_eq!(ids, [4, 5]);
+        assert!(std::fs::read_to_string(&fixture).unwrap().contains("Water the plants"));
+        assert!(!std::fs::read_to_string(&fixture).unwrap().contains("Buy milk"));
+    }
+}
@@ -1,4 +1,24 @@
+mod doctor;
+mod dry_run_repository;
+mod file_format;
+mod file_lock;
 mod file_repository;
+mod journaling_repository;
+mod memory_repository;
+mod recovery;
+mod schema;
+mod task_list;
+mod transfer;
+mod undo;
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+mod sql_filter;
+#[cfg(test)]
+mod conformance;
+
+pub use doctor::DoctorReport;
+pub use schema::SchemaStatus;
+pub use transfer::{all_tasks, restore, transfer};
+pub use undo::{redo, undo};
 
 
 // PostgreSQL feature
@@ -6,7 +26,7 @@ mod file_repository;
 mod postgres_repository;
 
 #[cfg(feature = "postgres")]
-use postgres_repository::get_postgres_repository;
+use postgres_repository::{get_postgres_repository, get_read_only_postgres_repository};
 
 
 // SQLite feature
@@ -14,25 +34,222 @@ use postgres_repository::get_postgres_repository;
 mod sqlite_repository;
 
 #[cfg(feature = "sqlite")]
-use sqlite_repository::get_sqlite_repository;
+use sqlite_repository::{get_read_only_sqlite_repository, get_sqlite_repository};
 
 
 use crate::config::{Config, StorageFormat};
-use crate::persistence::file_repository::FileRepository;
-use crate::tasks::{NewTask, Task};
-use anyhow::Result;
+use crate::error::TaskFileParseError;
+use crate::persistence::dry_run_repository::DryRunRepository;
+use crate::persistence::file_lock::FileLock;
+use crate::persistence::file_repository::{FileOptions, FileRepository};
+use crate::persistence::journaling_repository::JournalingRepository;
+use crate::persistence::memory_repository::MemoryRepository;
+use crate::journal::{JournalEntry, NewJournalEntry};
+use crate::projects::{NewProject, Project};
+use crate::search::{SearchHit, SearchIndex, SearchTerms};
+use crate::tasks::{open_blockers, NewTask, Task, TaskFilter};
+use anyhow::{anyhow, bail, Result};
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
 
 #[async_trait]
 pub trait Repository {
+    /// Every task that is not in the trash, by id.
     async fn get_all(&self) -> Result<Vec<Task>>;
     async fn get_by_id(&self, id: i32) -> Result<Task>;
-    async fn add(&self, task: NewTask) -> Result<()>;
+    /// Adds a task and returns the id it was given.
+    async fn add(&self, task: NewTask) -> Result<i32>;
+    /// Moves a task to the trash. Its subtasks move up to the top level and tasks that
+    /// depend on it stop doing so, just as if it were gone.
     async fn remove(&self, id: i32) -> Result<()>;
     async fn update(&self, task: Task) -> Result<()>;
+
+    /// Every task in the trash, by id.
+    async fn get_trash(&self) -> Result<Vec<Task>>;
+    /// Deletes the tasks moved to the trash before `deleted_before` for good, and returns
+    /// how many there were.
+    async fn empty_trash(&self, deleted_before: DateTime<Utc>) -> Result<usize>;
+    /// Deletes a task for good, whether or not it is in the trash, so undoing its creation
+    /// or restoring a backup leaves nothing behind. Its subtasks and dependents let go of it
+    /// as they do on removal.
+    async fn delete(&self, id: i32) -> Result<()>;
+
+    async fn get_projects(&self) -> Result<Vec<Project>>;
+    async fn add_project(&self, project: NewProject) -> Result<()>;
+    async fn archive_project(&self, id: i32, archived: bool) -> Result<()>;
+
+    /// Stores a task copied from another backend exactly as given, keeping its id and
+    /// timestamps and replacing any task with the same id. Its parent and the tasks it
+    /// depends on have to be there already.
+    async fn import_task(&self, task: Task) -> Result<()>;
+    /// Stores a project copied from another backend, keeping its id and replacing any
+    /// project with the same id.
+    async fn import_project(&self, project: Project) -> Result<()>;
+
+    /// Appends to the journal `todo undo` works from. Undone entries are forgotten first,
+    /// since they can no longer be redone, and only the newest `KEPT_BATCHES` batches are kept.
+    async fn add_journal_entry(&self, entry: NewJournalEntry) -> Result<()>;
+    /// Every entry in the journal, oldest first.
+    async fn get_journal(&self) -> Result<Vec<JournalEntry>>;
+    async fn set_undone(&self, ids: &[i64], undone: bool) -> Result<()>;
+
+    /// Takes a task back out of the trash. A parent or dependencies that are no longer
+    /// there are dropped.
+    async fn restore_from_trash(&self, id: i32) -> Result<()> {
+        let mut task = self.get_trash()
+                           .await?
+                           .into_iter()
+                           .find(|task| task.id == id)
+                           .ok_or_else(|| anyhow!("No task with id {} in the trash", id))?;
+        let ids: HashSet<i32> = self.get_all().await?.into_iter().map(|task| task.id).collect();
+
+        task.deleted_at = None;
+        task.parent_id = task.parent_id.filter(|parent_id| ids.contains(parent_id));
+        task.depends_on.retain(|depends_on| ids.contains(depends_on));
+        self.import_task(task).await
+    }
+
+    /// Returns a task along with its subtasks and the tasks that depend on it, which is
+    /// everything removing it touches. Backends that can look these up directly should
+    /// override this.
+    async fn get_related(&self, id: i32) -> Result<Vec<Task>> {
+        Ok(self.get_all()
+               .await?
+               .into_iter()
+               .filter(|task| task.id == id || task.parent_id == Some(id) || task.depends_on.contains(&id))
+               .collect())
+    }
+
+    /// Keeps other processes out of the store until the returned lock is dropped, so a
+    /// change and its journal entry are written together. Only the file backend has a lock
+    /// to hold; the others write each in a transaction of its own.
+    async fn hold_lock(&self) -> Result<Option<Arc<FileLock>>> {
+        Ok(None)
+    }
+
+    /// Returns the tasks matching `filter`. Backends that can narrow the query down
+    /// themselves should override this instead of filtering everything in memory.
+    async fn find(&self, filter: &TaskFilter) -> Result<Vec<Task>> {
+        let now = Utc::now();
+        let projects = if filter.hides_archived() || filter.query.is_some() {
+            self.get_projects().await?
+        } else {
+            vec![]
+        };
+        let archived_projects: HashSet<i32> = if filter.hides_archived() {
+            projects.iter()
+                    .filter(|project| project.archived)
+                    .map(|project| project.id)
+                    .collect()
+        } else {
+            HashSet::new()
+        };
+
+        let tasks = self.get_all().await?;
+        let ready: HashSet<i32> = if filter.ready {
+            tasks.iter()
+                 .filter(|task| task.is_open() && open_blockers(&tasks, task).is_empty())
+                 .map(|task| task.id)
+                 .collect()
+        } else {
+            HashSet::new()
+        };
+
+        Ok(tasks.iter()
+                .filter(|task| filter.matches(task, now))
+                .filter(|task| task.project_id.is_none_or(|project_id| !archived_projects.contains(&project_id)))
+                .filter(|task| !filter.ready || ready.contains(&task.id))
+                .filter(|task| filter.query.as_ref().is_none_or(|query| query.matches(task, &projects)))
+                .cloned()
+                .collect())
+    }
+
+    /// Returns up to `limit` tasks whose name or description contains every term, best
+    /// match first. Backends with a full-text index of their own should override this.
+    async fn search(&self, terms: &SearchTerms, limit: usize) -> Result<Vec<SearchHit>> {
+        let tasks = self.get_all().await?;
+        Ok(SearchIndex::new(&tasks).search(terms, limit))
+    }
+
+    async fn get_project_by_name(&self, name: &str) -> Result<Project> {
+        self.get_projects()
+            .await?
+            .into_iter()
+            .find(|project| project.name == name)
+            .ok_or_else(|| anyhow!("No project named \"{}\" found", name))
+    }
+
+    /// Brings the storage schema up to date. Backends without a schema do nothing.
+    async fn migrate(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Returns every tag in use along with how many tasks carry it, sorted by tag.
+    async fn get_tags(&self) -> Result<Vec<(String, i64)>> {
+        let mut counts = BTreeMap::<String, i64>::new();
+        for task in self.get_all().await? {
+            for tag in task.tags {
+                *counts.entry(tag).or_default() += 1;
+            }
+        }
+        Ok(counts.into_iter().collect())
+    }
+
+    /// Reports the state of the storage schema, or `None` if the backend has no schema.
+    async fn schema_status(&self) -> Result<Option<SchemaStatus>> {
+        Ok(None)
+    }
 }
 
-pub fn get_repository(config: &Config) -> Box<dyn Repository + Sync> {
+/// Opens the configured repository, migrates its schema, purges tasks that have been in the
+/// trash too long and records every change to its tasks in the journal for `todo undo`.
+/// Under `--dry-run` it hands out an in-memory copy instead, and the store is left untouched.
+pub async fn get_repository(config: &Config) -> Box<dyn Repository + Sync> {
+    let repository = get_unjournaled_repository(config).await;
+    if config.dry_run() {
+        return repository;
+    }
+
+    if let Some(deleted_before) = config.trash().purge_before(Utc::now()) {
+        match repository.empty_trash(deleted_before).await {
+            Ok(0) => {}
+            Ok(purged) => log::debug!("Purged {} task(s) that had been in the trash for over {} days", purged, config.trash().keep_days),
+            Err(e) => log::warn!("Failed to purge old tasks from the trash: {}", e),
+        }
+    }
+
+    Box::new(JournalingRepository::new(repository))
+}
+
+/// As [`get_repository`], for commands whose changes are not undone with `todo undo`:
+/// copying to another backend, restoring a backup, emptying the trash, and undoing and
+/// redoing themselves. Nothing is purged from the trash, so a store opened only to be
+/// copied to or restored keeps everything it is given.
+pub async fn get_unjournaled_repository(config: &Config) -> Box<dyn Repository + Sync> {
+    if config.dry_run() {
+        let copy = copy_for_dry_run(config).await.unwrap_or_else(|e| {
+            log::error!("Failed to copy storage for a dry run: {}", e);
+            std::process::exit(1);
+        });
+        log::warn!("Dry run, changes are shown but not saved");
+        return Box::new(DryRunRepository::new(copy));
+    }
+
+    let repository = open_repository(config);
+
+    log::debug!("Migrating repository schema");
+    repository.migrate().await.unwrap_or_else(|e| {
+        log::error!("Failed to migrate storage schema: {}", e);
+        std::process::exit(1);
+    });
+
+    repository
+}
+
+/// Opens the configured repository without touching its schema.
+pub fn open_repository(config: &Config) -> Box<dyn Repository + Sync> {
     log::debug!("Creating repository");
     match config.storage_format().unwrap_or_else(|err| {
         log::error!("Failed to get storage from config: {}", err);
@@ -50,22 +267,243 @@ pub fn get_repository(config: &Config) -> Box<dyn Repository + Sync> {
                 std::process::exit(1);
             });
 
-            Box::new(FileRepository::new(storage_filepath).unwrap_or_else(|e| {
+            let options = FileOptions::from_url(config.storage()).unwrap_or_else(|e| {
+                log::error!("Invalid storage options: {}", e);
+                std::process::exit(1);
+            });
+
+            Box::new(FileRepository::new(storage_filepath, options).unwrap_or_else(|e| {
                 log::error!("Failed to open file repository. ({})", e);
+                if e.is::<TaskFileParseError>() {
+                    log::error!("Run `todo doctor` to repair the file, or add `?lenient=true` to the storage URL to use the readable tasks.");
+                }
+                std::process::exit(1);
+            }))
+        }
+        StorageFormat::Memory => {
+            Box::new(MemoryRepository::from_url(config.storage()).unwrap_or_else(|e| {
+                log::error!("Failed to open in-memory repository. ({})", e);
                 std::process::exit(1);
             }))
         }
     }
 }
 
+/// Opens the configured storage to copy from, for `todo migrate`, without creating,
+/// migrating or purging anything. A database whose schema is out of date is refused, since
+/// bringing it up to date would change the source.
+pub async fn open_source_repository(config: &Config) -> Result<Box<dyn Repository + Sync>> {
+    let repository = match config.storage_format()? {
+        StorageFormat::Postgres => get_read_only_postgres_repository(config)?,
+        StorageFormat::Sqlite => get_read_only_sqlite_repository(config)?,
+        StorageFormat::LocalStorage => {
+            let storage_filepath = config.storage()
+                                         .to_file_path()
+                                         .map_err(|_| anyhow!("Failed to get storage path from config"))?;
+            std::fs::exists(&storage_filepath)?.then(|| open_repository(config))
+        }
+        StorageFormat::Memory => Some(open_repository(config)),
+    };
+    let Some(repository) = repository else {
+        bail!("The storage to copy from does not exist");
+    };
+
+    if let Some(status) = repository.schema_status().await? {
+        status.ensure_supported()?;
+        if !status.is_up_to_date() {
+            bail!("The storage to copy from is missing {} schema migration(s), run `todo db migrate` on it first", status.pending.len());
+        }
+    }
+    Ok(repository)
+}
+
+/// Reads the configured store into memory without creating, migrating or writing anything.
+async fn copy_for_dry_run(config: &Config) -> Result<MemoryRepository> {
+    match config.storage_format()? {
+        StorageFormat::LocalStorage => {
+            let storage_filepath = config.storage()
+                                         .to_file_path()
+                                         .map_err(|_| anyhow!("Failed to get storage path from config"))?;
+            let (tasks, projects) = FileRepository::read_contents(&storage_filepath, &FileOptions::from_url(config.storage())?)?;
+            Ok(MemoryRepository::new(tasks, projects).with_journal(FileRepository::read_journal_of(&storage_filepath)?))
+        }
+        StorageFormat::Memory => MemoryRepository::from_url(config.storage()),
+        StorageFormat::Postgres => copy_database(get_read_only_postgres_repository(config)?).await,
+        StorageFormat::Sqlite => copy_database(get_read_only_sqlite_repository(config)?).await,
+    }
+}
+
+/// Copies a database opened read-only into memory. A database that does not exist yet, or
+/// has never been migrated, holds nothing.
+async fn copy_database(repository: Option<Box<dyn Repository + Sync>>) -> Result<MemoryRepository> {
+    let Some(repository) = repository else {
+        return Ok(MemoryRepository::new(vec![], vec![]));
+    };
+
+    match repository.schema_status().await? {
+        Some(SchemaStatus { current_version: None, .. }) => Ok(MemoryRepository::new(vec![], vec![])),
+        Some(status) if !status.is_up_to_date() => {
+            bail!("{} schema migration(s) have not been applied, run `todo db migrate` first", status.pending.len())
+        }
+        _ => MemoryRepository::copy_of(repository.as_ref()).await,
+    }
+}
+
+/// Checks the configured storage for problems, repairing them if asked to.
+pub async fn doctor(config: &Config, repair: bool) -> Result<DoctorReport> {
+    match config.storage_format()? {
+        StorageFormat::LocalStorage => {
+            let storage_filepath = config.storage()
+                                         .to_file_path()
+                                         .map_err(|_| anyhow!("Failed to get storage path from config"))?;
+            FileRepository::doctor(&storage_filepath, &FileOptions::from_url(config.storage())?, repair)
+        }
+        // Nothing in memory outlives the process, so there is nothing to check.
+        StorageFormat::Memory => Ok(DoctorReport::default()),
+        StorageFormat::Postgres | StorageFormat::Sqlite => {
+            let repository = open_repository(config);
+            doctor::check_schema(repository.as_ref(), repair).await
+        }
+    }
+}
+
 
 #[cfg(not(feature = "postgres"))]
 fn get_postgres_repository(_config: &Config) -> Result<Box<dyn Repository + Sync>> {
     Err(anyhow!("Feature \"postgres\" is not enabled, unable to connect to PostgreSQL."))
 }
 
+#[cfg(not(feature = "postgres"))]
+fn get_read_only_postgres_repository(_config: &Config) -> Result<Option<Box<dyn Repository + Sync>>> {
+    Err(anyhow!("Feature \"postgres\" is not enabled, unable to connect to PostgreSQL."))
+}
+
 
 #[cfg(not(feature = "sqlite"))]
 fn get_sqlite_repository(_config: &Config) -> Result<Box<dyn Repository + Sync>> {
     Err(anyhow!("Feature \"sqlite\" is not enabled, unable to connect to SQLite."))
 }
+
+#[cfg(not(feature = "sqlite"))]
+fn get_read_only_sqlite_repository(_config: &Config) -> Result<Option<Box<dyn Repository + Sync>>> {
+    Err(anyhow!("Feature \"sqlite\" is not enabled, unable to connect to SQLite."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::file_repository::{FileOptions, FileRepository};
+    use super::journaling_repository::JournalingRepository;
+    use super::{get_repository, get_unjournaled_repository, undo, Repository};
+    #[cfg(feature = "sqlite")]
+    use super::open_source_repository;
+    use crate::config::Config;
+    use crate::tasks::NewTask;
+    use chrono::{Duration, Utc};
+    use url::Url;
+
+    fn dry_run(url: &Url) -> Config {
+        let mut config = Config::new_with_url(url.clone());
+        config.set_dry_run(true);
+        config
+    }
+
+    fn new_task(name: &str) -> NewTask {
+        NewTask {
+            name: name.into(),
+            ..NewTask::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn only_the_normal_command_path_purges_the_trash() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("tasks.ron");
+        let url = Url::parse(&format!("file://{}", path.display())).unwrap();
+
+        let stored = FileRepository::new(path, FileOptions::default()).unwrap();
+        stored.add(new_task("Fix the door")).await.unwrap();
+        let mut task = stored.get_by_id(1).await.unwrap();
+        task.deleted_at = Some(Utc::now() - Duration::days(60));
+        stored.import_task(task).await.unwrap();
+
+        let unjournaled = get_unjournaled_repository(&Config::new_with_url(url.clone())).await;
+        assert_eq!(unjournaled.get_trash().await.unwrap().len(), 1);
+        let repository = get_repository(&Config::new_with_url(url)).await;
+        assert!(repository.get_trash().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn dry_runs_can_undo_on_a_task_file() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("tasks.ron");
+        let url = Url::parse(&format!("file://{}", path.display())).unwrap();
+
+        let stored = JournalingRepository::for_batch(Box::new(FileRepository::new(path.clone(), FileOptions::default()).unwrap()), 1, "add");
+        stored.add(new_task("Fix the door")).await.unwrap();
+        let contents = std::fs::read(&path).unwrap();
+
+        let repository = get_repository(&dry_run(&url)).await;
+        let undone = undo(repository.as_ref(), false).await.unwrap().unwrap();
+        assert_eq!(undone.command(), "add");
+        assert!(repository.get_all().await.unwrap().is_empty());
+        assert_eq!(std::fs::read(&path).unwrap(), contents);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn dry_runs_leave_a_sqlite_database_alone() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("tasks.db");
+        let url = Url::parse(&format!("sqlite://{}", path.display())).unwrap();
+
+        let repository = get_repository(&dry_run(&url)).await;
+        repository.add(new_task("Fix the door")).await.unwrap();
+        assert_eq!(repository.get_all().await.unwrap().len(), 1);
+        assert!(!path.exists());
+
+        let stored = super::sqlite_repository::SqliteRepository::new(&url).unwrap();
+        stored.migrate().await.unwrap();
+        stored.add(new_task("Buy hinges")).await.unwrap();
+        let contents = std::fs::read(&path).unwrap();
+
+        let repository = get_repository(&dry_run(&url)).await;
+        repository.remove(1).await.unwrap();
+        repository.add(new_task("Sweep up")).await.unwrap();
+        assert_eq!(repository.get_trash().await.unwrap()[0].name, "Buy hinges");
+        assert_eq!(stored.get_all().await.unwrap()[0].name, "Buy hinges");
+        assert_eq!(std::fs::read(&path).unwrap(), contents);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn sources_to_copy_from_are_not_created_or_migrated() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("tasks.db");
+        let url = Url::parse(&format!("sqlite://{}", path.display())).unwrap();
+        let config = Config::new_with_url(url.clone());
+
+        assert!(open_source_repository(&config).await.is_err());
+        assert!(!path.exists());
+
+        std::fs::File::create(&path).unwrap();
+        assert!(open_source_repository(&config).await.is_err());
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), 0);
+
+        super::sqlite_repository::SqliteRepository::new(&url).unwrap().migrate().await.unwrap();
+        assert!(open_source_repository(&config).await.unwrap().get_all().await.unwrap().is_empty());
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    #[ignore = "needs TODO_TEST_POSTGRES_URL"]
+    async fn dry_runs_cannot_write_to_postgres() {
+        let url = Url::parse(&std::env::var("TODO_TEST_POSTGRES_URL").expect("TODO_TEST_POSTGRES_URL is not set")).unwrap();
+        super::postgres_repository::PostgresRepository::new(&url).unwrap().migrate().await.unwrap();
+
+        let repository = get_repository(&dry_run(&url)).await;
+        repository.add(new_task("Fix the door")).await.unwrap();
+
+        let read_only = super::get_read_only_postgres_repository(&dry_run(&url)).unwrap().unwrap();
+        assert!(read_only.add(new_task("Fix the door")).await.is_err());
+    }
+}
@@ -0,0 +1,93 @@
+/// Pulls every entry that still parses out of a damaged bracketed list, such as RON or JSON.
+///
+/// The list is split into its top-level entries by bracket matching, so a truncated
+/// file or a single bad entry only costs the entries that are actually unreadable.
+pub fn salvage_entries<T>(contents: &str, parse: impl Fn(&str) -> Option<T>) -> Vec<T> {
+    let body = match contents.find('[') {
+        Some(index) => &contents[index + 1..],
+        None => contents,
+    };
+
+    top_level_entries(body)
+        .into_iter()
+        .filter_map(|entry| {
+            let record = parse(entry);
+            if record.is_none() {
+                log::debug!("Discarding unreadable entry: {}", entry);
+            }
+            record
+        })
+        .collect()
+}
+
+fn top_level_entries(body: &str) -> Vec<&str> {
+    let mut entries = vec![];
+    let mut depth = 0usize;
+    let mut start = None;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (index, character) in body.char_indices() {
+        if in_string {
+            match character {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match character {
+            '"' => in_string = true,
+            '(' | '[' | '{' => {
+                if depth == 0 {
+                    start = Some(index);
+                }
+                depth += 1;
+            }
+            ')' | ']' | '}' => {
+                if depth == 0 {
+                    // The closing bracket of the list itself.
+                    break;
+                }
+                depth -= 1;
+                if depth == 0 && let Some(start) = start.take() {
+                    entries.push(&body[start..=index]);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::salvage_entries;
+    use crate::tasks::Task;
+
+    fn salvage_ron(contents: &str) -> Vec<Task> {
+        salvage_entries(contents, |entry| ron::de::from_str(entry).ok())
+    }
+
+    #[test]
+    fn salvages_entries_before_a_truncation() {
+        let contents = r#"[(id:1,name:"one",description:None,completed:false),(id:2,name:"tw"#;
+        let tasks = salvage_ron(contents);
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].name(), "one");
+    }
+
+    #[test]
+    fn skips_a_single_bad_entry() {
+        let contents = r#"[
+            (id:1,name:"one (with brackets]",description:None,completed:false),
+            (id:2,name:"two",description:None,completed:"maybe"),
+            (id:3,name:"three",description:Some("\"quoted\""),completed:true),
+        ]"#;
+        let names: Vec<_> = salvage_ron(contents).into_iter().map(|task| task.name).collect();
+        assert_eq!(names, vec!["one (with brackets]", "three"]);
+    }
+}
@@ -0,0 +1,100 @@
+use crate::error::SchemaTooNewError;
+use anyhow::Result;
+#[cfg(any(feature = "postgres", feature = "sqlite", test))]
+use sqlx::migrate::{AppliedMigration, Migrator};
+use std::fmt::{Display, Formatter};
+
+/// The state of a database schema compared to the migrations embedded in this binary.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaStatus {
+    pub current_version: Option<i64>,
+    pub latest_version: i64,
+    pub pending: Vec<(i64, String)>,
+}
+
+impl SchemaStatus {
+    #[cfg(any(feature = "postgres", feature = "sqlite", test))]
+    pub fn new(migrator: &Migrator, applied: &[AppliedMigration]) -> Self {
+        let current_version = applied.iter().map(|migration| migration.version).max();
+        let latest_version = migrator.iter()
+                                     .map(|migration| migration.version)
+                                     .max()
+                                     .unwrap_or(0);
+        let pending = migrator.iter()
+                              .filter(|migration| !applied.iter().any(|a| a.version == migration.version))
+                              .map(|migration| (migration.version, migration.description.to_string()))
+                              .collect();
+
+        Self {
+            current_version,
+            latest_version,
+            pending,
+        }
+    }
+
+    /// Fails if the database has been migrated by a newer build than this one.
+    pub fn ensure_supported(&self) -> Result<()> {
+        match self.current_version {
+            Some(found) if found > self.latest_version => Err(SchemaTooNewError {
+                found,
+                supported: self.latest_version,
+            }.into()),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn is_up_to_date(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+impl Display for SchemaStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.current_version {
+            Some(version) => writeln!(f, "Schema version: {}", version)?,
+            None => writeln!(f, "Schema version: none")?,
+        }
+        writeln!(f, "Latest version: {}", self.latest_version)?;
+
+        if self.is_up_to_date() {
+            writeln!(f, "Schema is up to date")
+        } else {
+            writeln!(f, "Pending migrations:")?;
+            for (version, description) in self.pending.iter() {
+                writeln!(f, "  {}. {}", version, description)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SchemaStatus;
+    use sqlx::migrate::{AppliedMigration, Migrator};
+    use std::borrow::Cow;
+
+    static MIGRATOR: Migrator = sqlx::migrate!("migrations/sqlite");
+
+    fn applied(version: i64) -> AppliedMigration {
+        AppliedMigration {
+            version,
+            checksum: Cow::Borrowed(&[]),
+        }
+    }
+
+    #[test]
+    fn fresh_database_has_everything_pending() {
+        let status = SchemaStatus::new(&MIGRATOR, &[]);
+        assert_eq!(status.current_version, None);
+        assert_eq!(status.pending.len(), MIGRATOR.iter().count());
+        assert!(status.ensure_supported().is_ok());
+    }
+
+    #[test]
+    fn newer_schema_is_rejected() {
+        let status = SchemaStatus::new(&MIGRATOR, &[applied(1), applied(9999)]);
+        assert_eq!(status.current_version, Some(9999));
+        assert!(status.ensure_supported().is_err());
+    }
+}
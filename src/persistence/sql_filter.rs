@@ -0,0 +1,257 @@
+use crate::dates::start_of_local_day;
+use crate::query::{Condition, Query};
+use crate::tasks::{TagSelector, TaskFilter};
+use chrono::{DateTime, Utc};
+
+/// A value to bind to one of the numbered parameters of a [`SqlFilter`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum SqlValue {
+    Integer(i32),
+    Text(String),
+    Timestamp(DateTime<Utc>),
+}
+
+/// A [`TaskFilter`] translated into a parameterised `where` clause over the `tasks` table.
+/// Tasks in the trash never match.
+///
+/// Parameters are numbered `$1`, `$2`, ... which both SQLite and Postgres accept.
+#[derive(Debug, Default)]
+pub struct SqlFilter {
+    conditions: Vec<String>,
+    pub values: Vec<SqlValue>,
+    /// A query that the database cannot be trusted to check, to be matched against the rows
+    /// it returns instead.
+    pub remaining_query: Option<Query>,
+}
+
+impl SqlFilter {
+    pub fn new(filter: &TaskFilter, now: DateTime<Utc>) -> Self {
+        let mut sql_filter = Self::default();
+        sql_filter.conditions.push("tasks.deleted_at is null".into());
+
+        if filter.overdue {
+            // Matches Task::is_overdue, which leaves tasks due at the start of today alone.
+            let midnight = start_of_local_day(now).unwrap_or(now);
+            let now = sql_filter.parameter(SqlValue::Timestamp(now));
+            let midnight = sql_filter.parameter(SqlValue::Timestamp(midnight));
+            sql_filter.conditions.push(format!(
+                "(tasks.status not in ('done', 'cancelled') and tasks.due < {} and tasks.due <> {})",
+                now, midnight
+            ));
+        }
+
+        if let Some(before) = filter.due_before {
+            let before = sql_filter.parameter(SqlValue::Timestamp(before));
+            sql_filter.conditions.push(format!("tasks.due < {}", before));
+        }
+
+        if let Some(after) = filter.due_after {
+            let after = sql_filter.parameter(SqlValue::Timestamp(after));
+            sql_filter.conditions.push(format!("tasks.due > {}", after));
+        }
+
+        for selector in filter.tags.iter() {
+            let (operator, tag) = match selector {
+                TagSelector::Include(tag) => ("exists", tag),
+                TagSelector::Exclude(tag) => ("not exists", tag),
+            };
+            let tag = sql_filter.parameter(SqlValue::Text(tag.clone()));
+            sql_filter.conditions.push(format!(
+                "{} (select 1 from task_tags join tags on tags.id = task_tags.tag_id where task_tags.task_id = tasks.id and tags.name = {})",
+                operator,
+                tag,
+            ));
+        }
+
+        if let Some(project_id) = filter.project_id {
+            let project_id = sql_filter.parameter(SqlValue::Integer(project_id));
+            sql_filter.conditions.push(format!("tasks.project_id = {}", project_id));
+        }
+
+        if filter.ready {
+            sql_filter.conditions.push(
+                "(tasks.status not in ('done', 'cancelled') and not exists (select 1 from task_dependencies join tasks as blockers on blockers.id = task_dependencies.depends_on_id where task_dependencies.task_id = tasks.id and blockers.status not in ('done', 'cancelled')))".into()
+            );
+        }
+
+        // `lower` only folds ASCII letters in SQLite, and in Postgres depending on the
+        // database's locale, so text beyond that is compared the way `Query::matches` does.
+        if let Some(query) = &filter.query {
+            if is_ascii(query) {
+                let condition = sql_filter.query(query);
+                sql_filter.conditions.push(condition);
+            } else {
+                sql_filter.remaining_query = Some(query.clone());
+            }
+        }
+
+        if filter.hides_archived() {
+            sql_filter.conditions.push(
+                "(tasks.project_id is null or tasks.project_id not in (select id from projects where archived = true))".into()
+            );
+        }
+
+        sql_filter
+    }
+
+    /// The `where` clause, with a leading space.
+    pub fn where_clause(&self) -> String {
+        format!(" where {}", self.conditions.join(" and "))
+    }
+
+    /// Translates a query into a condition. Columns that can be null are checked first, so
+    /// `not` matches tasks without the field, just as [`Query::matches`] does.
+    fn query(&mut self, query: &Query) -> String {
+        match query {
+            Query::And(left, right) => format!("({} and {})", self.query(left), self.query(right)),
+            Query::Or(left, right) => format!("({} or {})", self.query(left), self.query(right)),
+            Query::Not(query) => format!("not {}", self.query(query)),
+            Query::Condition(condition) => self.condition(condition),
+        }
+    }
+
+    fn condition(&mut self, condition: &Condition) -> String {
+        match condition {
+            Condition::Status(statuses) => {
+                let statuses: Vec<String> = statuses.iter()
+                                                    .map(|status| self.parameter(SqlValue::Text(status.as_str().into())))
+                                                    .collect();
+                format!("tasks.status in ({})", statuses.join(", "))
+            }
+            Condition::Priority(comparison, priority) => {
+                let priority = self.parameter(SqlValue::Integer(*priority as i32));
+                format!("tasks.priority {} {}", comparison.sql(), priority)
+            }
+            Condition::Date(field, comparison, date) => {
+                let date = self.parameter(SqlValue::Timestamp(*date));
+                format!("({column} is not null and {column} {} {})", comparison.sql(), date, column = field.column())
+            }
+            Condition::Missing(field) => format!("{} is null", field.column()),
+            Condition::Tag(tag) => {
+                let tag = self.parameter(SqlValue::Text(tag.clone()));
+                format!("exists (select 1 from task_tags join tags on tags.id = task_tags.tag_id where task_tags.task_id = tasks.id and tags.name = {})", tag)
+            }
+            Condition::NoTags => "not exists (select 1 from task_tags where task_tags.task_id = tasks.id)".into(),
+            Condition::Project(None) => "tasks.project_id is null".into(),
+            Condition::Project(Some(name)) => {
+                let name = self.parameter(SqlValue::Text(name.clone()));
+                format!("(tasks.project_id is not null and tasks.project_id in (select id from projects where name = {}))", name)
+            }
+            Condition::Contains(field, text) => {
+                let pattern = format!("%{}%", text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"));
+                let pattern = self.parameter(SqlValue::Text(pattern));
+                format!("({column} is not null and lower({column}) like {} escape '\\')", pattern, column = field.column())
+            }
+            Condition::Is(field, text) => {
+                let text = self.parameter(SqlValue::Text(text.clone()));
+                format!("({column} is not null and lower({column}) = {})", text, column = field.column())
+            }
+            Condition::Id(comparison, id) => {
+                let id = self.parameter(SqlValue::Integer(*id));
+                format!("tasks.id {} {}", comparison.sql(), id)
+            }
+            Condition::Parent(None) => "tasks.parent_id is null".into(),
+            Condition::Parent(Some(parent_id)) => {
+                let parent_id = self.parameter(SqlValue::Integer(*parent_id));
+                format!("(tasks.parent_id is not null and tasks.parent_id = {})", parent_id)
+            }
+        }
+    }
+
+    fn parameter(&mut self, value: SqlValue) -> String {
+        self.values.push(value);
+        format!("${}", self.values.len())
+    }
+}
+
+/// Whether all the text the query compares against is ASCII.
+fn is_ascii(query: &Query) -> bool {
+    match query {
+        Query::And(left, right) | Query::Or(left, right) => is_ascii(left) && is_ascii(right),
+        Query::Not(query) => is_ascii(query),
+        Query::Condition(Condition::Contains(_, text) | Condition::Is(_, text)) => text.is_ascii(),
+        Query::Condition(_) => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SqlFilter, SqlValue};
+    use crate::query::parse_query;
+    use crate::tasks::{TagSelector, TaskFilter};
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn empty_filter_only_leaves_out_the_trash() {
+        let sql_filter = SqlFilter::new(&TaskFilter { archived: true, ..TaskFilter::default() }, Utc::now());
+        assert_eq!(sql_filter.where_clause(), " where tasks.deleted_at is null");
+        assert!(sql_filter.values.is_empty());
+    }
+
+    #[test]
+    fn parameters_are_numbered_in_order() {
+        let before = Utc.with_ymd_and_hms(2026, 11, 1, 0, 0, 0).unwrap();
+        let filter = TaskFilter {
+            due_before: Some(before),
+            tags: vec![TagSelector::Include("ops".into()), TagSelector::Exclude("personal".into())],
+            archived: true,
+            ..TaskFilter::default()
+        };
+
+        let sql_filter = SqlFilter::new(&filter, Utc::now());
+        let where_clause = sql_filter.where_clause();
+        assert!(where_clause.starts_with(" where tasks.deleted_at is null and tasks.due < $1 and exists ("));
+        assert!(where_clause.contains("tags.name = $2) and not exists ("));
+        assert!(where_clause.ends_with("tags.name = $3)"));
+        assert_eq!(sql_filter.values, vec![
+            SqlValue::Timestamp(before),
+            SqlValue::Text("ops".into()),
+            SqlValue::Text("personal".into()),
+        ]);
+    }
+
+    #[test]
+    fn archived_projects_are_hidden_unless_a_project_is_chosen() {
+        let sql_filter = SqlFilter::new(&TaskFilter::default(), Utc::now());
+        assert!(sql_filter.where_clause().contains("archived = true"));
+
+        let filter = TaskFilter {
+            project_id: Some(3),
+            ..TaskFilter::default()
+        };
+        let sql_filter = SqlFilter::new(&filter, Utc::now());
+        assert_eq!(sql_filter.where_clause(), " where tasks.deleted_at is null and tasks.project_id = $1");
+        assert_eq!(sql_filter.values, vec![SqlValue::Integer(3)]);
+    }
+
+    #[test]
+    fn queries_become_parameterised_conditions() {
+        let filter = TaskFilter {
+            query: Some(parse_query("priority>=high or not name:\"50%\"", Utc::now()).unwrap()),
+            archived: true,
+            ..TaskFilter::default()
+        };
+
+        let sql_filter = SqlFilter::new(&filter, Utc::now());
+        assert_eq!(
+            sql_filter.where_clause(),
+            " where tasks.deleted_at is null and (tasks.priority >= $1 or not (tasks.name is not null and lower(tasks.name) like $2 escape '\\'))"
+        );
+        assert_eq!(sql_filter.values, vec![SqlValue::Integer(3), SqlValue::Text("%50\\%%".into())]);
+        assert!(sql_filter.remaining_query.is_none());
+    }
+
+    #[test]
+    fn queries_with_text_beyond_ascii_are_left_to_the_caller() {
+        let query = parse_query("priority>=high and name:ÉCOLE", Utc::now()).unwrap();
+        let filter = TaskFilter {
+            query: Some(query.clone()),
+            archived: true,
+            ..TaskFilter::default()
+        };
+
+        let sql_filter = SqlFilter::new(&filter, Utc::now());
+        assert_eq!(sql_filter.where_clause(), " where tasks.deleted_at is null");
+        assert_eq!(sql_filter.remaining_query, Some(query));
+    }
+}
@@ -1,11 +1,57 @@
 use crate::config::Config;
+use crate::error::{DependencyCycleError, TaskCycleError, TaskNotFoundError};
+use crate::journal::{JournalEntry, NewJournalEntry, KEPT_BATCHES};
+use crate::persistence::schema::SchemaStatus;
+use crate::persistence::sql_filter::{SqlFilter, SqlValue};
 use crate::persistence::Repository;
-use crate::tasks::{NewTask, Task};
-use anyhow::Result;
+use crate::projects::{NewProject, Project};
+use crate::search::{SearchHit, SearchTerms, HIGHLIGHT_END, HIGHLIGHT_START};
+use crate::tasks::{find_dependency_cycle, NewTask, Task, TaskFilter};
+use anyhow::{anyhow, Result};
 use async_trait::async_trait;
-use sqlx::SqlitePool;
+use chrono::{DateTime, Utc};
+use sqlx::migrate::{Migrate, Migrator};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteConnection};
+use sqlx::{FromRow, SqlitePool};
+use std::collections::HashMap;
+use std::str::FromStr;
 use url::Url;
 
+static MIGRATOR: Migrator = sqlx::migrate!("migrations/sqlite");
+
+/// A task found by a search, along with its score and snippet.
+#[derive(FromRow)]
+struct SearchRow {
+    #[sqlx(flatten)]
+    task: Task,
+    score: f64,
+    snippet: String,
+}
+
+/// A journal entry as stored, with its changes still in JSON.
+#[derive(FromRow)]
+struct JournalRow {
+    id: i64,
+    batch: i64,
+    command: String,
+    created_at: DateTime<Utc>,
+    undone: bool,
+    changes: String,
+}
+
+impl JournalRow {
+    fn into_entry(self) -> Result<JournalEntry> {
+        Ok(JournalEntry {
+            id: self.id,
+            batch: self.batch,
+            command: self.command,
+            created_at: self.created_at,
+            undone: self.undone,
+            changes: serde_json::from_str(&self.changes)?,
+        })
+    }
+}
+
 pub struct SqliteRepository {
     connection_pool: SqlitePool,
 }
@@ -13,17 +59,269 @@ pub struct SqliteRepository {
 impl SqliteRepository {
     pub fn new(connection_url: &Url) -> Result<Self> {
         log::trace!("Creating new SqliteRepository with URL: {}", connection_url);
+        let options = SqliteConnectOptions::from_str(connection_url.as_str())?
+            .create_if_missing(true);
+
         Ok(Self {
-            connection_pool: SqlitePool::connect_lazy((&connection_url).as_ref())?,
+            connection_pool: SqlitePool::connect_lazy_with(options),
         })
     }
+
+    /// Opens the database for reading only, or returns `None` if there is no database file
+    /// yet. Nothing is created.
+    pub fn read_only(connection_url: &Url) -> Result<Option<Self>> {
+        log::trace!("Creating read-only SqliteRepository with URL: {}", connection_url);
+        let options = SqliteConnectOptions::from_str(connection_url.as_str())?
+            .read_only(true);
+        if !options.get_filename().exists() {
+            return Ok(None);
+        }
+
+        Ok(Some(Self {
+            connection_pool: SqlitePool::connect_lazy_with(options),
+        }))
+    }
+
+    /// Reads which migrations have been applied without writing anything, so a database
+    /// that has never been migrated has none.
+    async fn read_schema_status(connection: &mut SqliteConnection) -> Result<SchemaStatus> {
+        log::trace!("Reading database schema status");
+        let migrations_table_exists = sqlx::query_scalar::<_, bool>("select exists (select 1 from sqlite_master where type = 'table' and name = '_sqlx_migrations')")
+            .fetch_one(&mut *connection)
+            .await?;
+        let applied = if migrations_table_exists {
+            connection.list_applied_migrations().await?
+        } else {
+            vec![]
+        };
+
+        Ok(SchemaStatus::new(&MIGRATOR, &applied))
+    }
+
+    /// Fills in the tags and dependencies of the given tasks from their tables.
+    async fn load_details(&self, tasks: &mut [Task]) -> Result<()> {
+        if tasks.is_empty() {
+            return Ok(());
+        }
+
+        // The ids go in as one JSON array, so there is no limit on how many can be asked for.
+        let ids = serde_json::to_string(&tasks.iter().map(|task| task.id).collect::<Vec<_>>())?;
+        let rows = sqlx::query_as::<_, (i32, String)>("select task_tags.task_id, tags.name from task_tags join tags on tags.id = task_tags.tag_id where task_tags.task_id in (select value from json_each($1)) order by tags.name")
+            .bind(&ids)
+            .fetch_all(&self.connection_pool)
+            .await?;
+
+        let mut tags_by_task = HashMap::<i32, Vec<String>>::new();
+        for (task_id, tag) in rows {
+            tags_by_task.entry(task_id).or_default().push(tag);
+        }
+
+        let rows = sqlx::query_as::<_, (i32, i32)>("select task_id, depends_on_id from task_dependencies where task_id in (select value from json_each($1)) order by depends_on_id")
+            .bind(&ids)
+            .fetch_all(&self.connection_pool)
+            .await?;
+
+        let mut dependencies_by_task = HashMap::<i32, Vec<i32>>::new();
+        for (task_id, depends_on) in rows {
+            dependencies_by_task.entry(task_id).or_default().push(depends_on);
+        }
+
+        for task in tasks.iter_mut() {
+            task.tags = tags_by_task.remove(&task.id).unwrap_or_default();
+            task.depends_on = dependencies_by_task.remove(&task.id).unwrap_or_default();
+        }
+
+        Ok(())
+    }
+
+    /// Makes sure a parent exists and that `task_id`, if it already exists, is not above it.
+    async fn check_parent(connection: &mut SqliteConnection, task_id: Option<i32>, parent_id: i32) -> Result<()> {
+        let parent_exists = sqlx::query_scalar::<_, bool>("select exists (select 1 from tasks where id = $1 and deleted_at is null)")
+            .bind(parent_id)
+            .fetch_one(&mut *connection)
+            .await?;
+        if !parent_exists {
+            return Err(TaskNotFoundError { id: parent_id }.into());
+        }
+
+        let Some(task_id) = task_id else {
+            return Ok(());
+        };
+
+        let creates_cycle = sqlx::query_scalar::<_, bool>(
+            "with recursive ancestors (id, parent_id) as ( \
+                 select id, parent_id from tasks where id = $1 \
+                 union \
+                 select tasks.id, tasks.parent_id from tasks join ancestors on tasks.id = ancestors.parent_id \
+             ) \
+             select exists (select 1 from ancestors where id = $2)"
+        )
+            .bind(parent_id)
+            .bind(task_id)
+            .fetch_one(&mut *connection)
+            .await?;
+        if creates_cycle {
+            return Err(TaskCycleError { task: task_id, parent: parent_id }.into());
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the dependencies of a task, refusing any that would close a loop.
+    async fn save_dependencies(connection: &mut SqliteConnection, task_id: i32, depends_on: &[i32]) -> Result<()> {
+        let dependencies = sqlx::query_as::<_, (i32, i32)>("select task_id, depends_on_id from task_dependencies")
+            .fetch_all(&mut *connection)
+            .await?;
+
+        for depends_on in depends_on.iter().copied() {
+            let exists = sqlx::query_scalar::<_, bool>("select exists (select 1 from tasks where id = $1 and deleted_at is null)")
+                .bind(depends_on)
+                .fetch_one(&mut *connection)
+                .await?;
+            if !exists {
+                return Err(TaskNotFoundError { id: depends_on }.into());
+            }
+
+            if let Some(cycle) = find_dependency_cycle(&dependencies, task_id, depends_on) {
+                return Err(DependencyCycleError { cycle }.into());
+            }
+        }
+
+        sqlx::query("delete from task_dependencies where task_id = $1")
+            .bind(task_id)
+            .execute(&mut *connection)
+            .await?;
+
+        for depends_on in depends_on {
+            sqlx::query("insert into task_dependencies (task_id, depends_on_id) values ($1, $2)")
+                .bind(task_id)
+                .bind(depends_on)
+                .execute(&mut *connection)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the tags of a task, dropping any tag that no task uses any more.
+    async fn save_tags(connection: &mut SqliteConnection, task_id: i32, tags: &[String]) -> Result<()> {
+        sqlx::query("delete from task_tags where task_id = $1")
+            .bind(task_id)
+            .execute(&mut *connection)
+            .await?;
+
+        for tag in tags {
+            sqlx::query("insert into tags (name) values ($1) on conflict (name) do nothing")
+                .bind(tag)
+                .execute(&mut *connection)
+                .await?;
+            sqlx::query("insert into task_tags (task_id, tag_id) select $1, id from tags where name = $2")
+                .bind(task_id)
+                .bind(tag)
+                .execute(&mut *connection)
+                .await?;
+        }
+
+        sqlx::query("delete from tags where id not in (select tag_id from task_tags)")
+            .execute(&mut *connection)
+            .await?;
+
+        Ok(())
+    }
 }
 
 #[async_trait]
 impl Repository for SqliteRepository {
+    async fn migrate(&self) -> Result<()> {
+        log::trace!("Migrating database schema");
+        // Check and migrate on one connection, other connections may hold a stale view of the schema.
+        let mut connection = self.connection_pool.acquire().await?;
+        Self::read_schema_status(&mut connection).await?.ensure_supported()?;
+
+        MIGRATOR.run_direct(&mut *connection).await?;
+        Ok(())
+    }
+
+    async fn schema_status(&self) -> Result<Option<SchemaStatus>> {
+        let mut connection = self.connection_pool.acquire().await?;
+        Ok(Some(Self::read_schema_status(&mut connection).await?))
+    }
+
     async fn get_all(&self) -> Result<Vec<Task>> {
         log::trace!("Fetching all tasks");
-        let rows = sqlx::query_as::<_, Task>("select * from tasks order by id")
+        let mut rows = sqlx::query_as::<_, Task>("select * from tasks where deleted_at is null order by id")
+            .fetch_all(&self.connection_pool)
+            .await?;
+
+        self.load_details(&mut rows).await?;
+        Ok(rows)
+    }
+
+    async fn get_related(&self, id: i32) -> Result<Vec<Task>> {
+        log::trace!("Fetching task {} with its subtasks and dependents", id);
+        let mut rows = sqlx::query_as::<_, Task>("select * from tasks where deleted_at is null and (id = $1 or parent_id = $1 or id in (select task_id from task_dependencies where depends_on_id = $1)) order by id")
+            .bind(id)
+            .fetch_all(&self.connection_pool)
+            .await?;
+
+        self.load_details(&mut rows).await?;
+        Ok(rows)
+    }
+
+    async fn find(&self, filter: &TaskFilter) -> Result<Vec<Task>> {
+        log::trace!("Finding tasks matching {:?}", filter);
+        let sql_filter = SqlFilter::new(filter, Utc::now());
+        let sql = format!("select * from tasks{} order by id", sql_filter.where_clause());
+
+        let mut query = sqlx::query_as::<_, Task>(&sql);
+        for value in sql_filter.values {
+            query = match value {
+                SqlValue::Integer(integer) => query.bind(integer),
+                SqlValue::Text(text) => query.bind(text),
+                SqlValue::Timestamp(timestamp) => query.bind(timestamp),
+            };
+        }
+
+        let mut rows = query.fetch_all(&self.connection_pool).await?;
+        self.load_details(&mut rows).await?;
+        if let Some(query) = sql_filter.remaining_query {
+            let projects = self.get_projects().await?;
+            rows.retain(|task| query.matches(task, &projects));
+        }
+        Ok(rows)
+    }
+
+    async fn search(&self, terms: &SearchTerms, limit: usize) -> Result<Vec<SearchHit>> {
+        log::trace!("Searching for {:?}", terms);
+        // bm25 gives better matches lower scores, so it is negated to put them first.
+        let rows = sqlx::query_as::<_, SearchRow>(
+            "select tasks.*, -bm25(tasks_search, 10.0, 1.0) as score, snippet(tasks_search, -1, $1, $2, '…', 12) as snippet \
+             from tasks_search join tasks on tasks.id = tasks_search.rowid \
+             where tasks_search match $3 and tasks.deleted_at is null \
+             order by score desc, tasks.id \
+             limit $4"
+        )
+            .bind(HIGHLIGHT_START.to_string())
+            .bind(HIGHLIGHT_END.to_string())
+            .bind(terms.to_fts5())
+            .bind(limit as i64)
+            .fetch_all(&self.connection_pool)
+            .await?;
+
+        let (mut tasks, scores): (Vec<Task>, Vec<(f64, String)>) = rows.into_iter()
+                                                                       .map(|row| (row.task, (row.score, row.snippet)))
+                                                                       .unzip();
+        self.load_details(&mut tasks).await?;
+
+        Ok(tasks.into_iter()
+                .zip(scores)
+                .map(|(task, (score, snippet))| SearchHit { task, score, snippet })
+                .collect())
+    }
+
+    async fn get_tags(&self) -> Result<Vec<(String, i64)>> {
+        log::trace!("Counting tags");
+        let rows = sqlx::query_as::<_, (String, i64)>("select tags.name, count(*) from tags join task_tags on task_tags.tag_id = tags.id join tasks on tasks.id = task_tags.task_id where tasks.deleted_at is null group by tags.name order by tags.name")
             .fetch_all(&self.connection_pool)
             .await?;
 
@@ -32,45 +330,282 @@ impl Repository for SqliteRepository {
 
     async fn get_by_id(&self, id: i32) -> Result<Task> {
         log::trace!("Fetching task with ID: {}", id);
-        let row = sqlx::query_as::<_, Task>("select * from tasks where id = $1")
+        let task = sqlx::query_as::<_, Task>("select * from tasks where id = $1 and deleted_at is null")
             .bind(id)
-            .fetch_one(&self.connection_pool)
+            .fetch_optional(&self.connection_pool)
             .await?;
 
-        Ok(row)
+        let mut tasks = [task.ok_or(TaskNotFoundError { id })?];
+        self.load_details(&mut tasks).await?;
+        let [task] = tasks;
+        Ok(task)
     }
 
-    async fn add(&self, task: NewTask) -> Result<()> {
+    async fn add(&self, task: NewTask) -> Result<i32> {
         log::trace!("Adding task: {:?}", task);
-        sqlx::query("insert into tasks (name, description) values ($1, $2)")
-            .bind(task.name)
-            .bind(task.description)
-            .execute(&self.connection_pool)
+        let now = Utc::now();
+        let mut transaction = self.connection_pool.begin().await?;
+        if let Some(parent_id) = task.parent_id {
+            Self::check_parent(&mut transaction, None, parent_id).await?;
+        }
+
+        let id = sqlx::query_scalar::<_, i32>("insert into tasks (name, description, created_at, updated_at, due, scheduled, priority, project_id, parent_id, recurrence) values ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) returning id")
+            .bind(&task.name)
+            .bind(&task.description)
+            .bind(now)
+            .bind(now)
+            .bind(task.due)
+            .bind(task.scheduled)
+            .bind(task.priority)
+            .bind(task.project_id)
+            .bind(task.parent_id)
+            .bind(&task.recurrence)
+            .fetch_one(&mut *transaction)
             .await?;
 
-        Ok(())
+        let mut tags = task.tags;
+        tags.sort();
+        tags.dedup();
+        Self::save_tags(&mut transaction, id, &tags).await?;
+
+        transaction.commit().await?;
+        Ok(id)
     }
 
     async fn remove(&self, id: i32) -> Result<()> {
         log::trace!("Removing task with ID: {}", id);
-        sqlx::query("delete from tasks where id = $1")
+        let mut transaction = self.connection_pool.begin().await?;
+        let result = sqlx::query("update tasks set deleted_at = $1 where id = $2 and deleted_at is null")
+            .bind(Utc::now())
+            .bind(id)
+            .execute(&mut *transaction)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(TaskNotFoundError { id }.into());
+        }
+
+        // The row stays, so what deleting it would do to its subtasks and dependents is done by hand.
+        sqlx::query("update tasks set parent_id = null where parent_id = $1")
+            .bind(id)
+            .execute(&mut *transaction)
+            .await?;
+        sqlx::query("delete from task_dependencies where depends_on_id = $1")
+            .bind(id)
+            .execute(&mut *transaction)
+            .await?;
+
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn get_trash(&self) -> Result<Vec<Task>> {
+        log::trace!("Fetching the trash");
+        let mut rows = sqlx::query_as::<_, Task>("select * from tasks where deleted_at is not null order by id")
+            .fetch_all(&self.connection_pool)
+            .await?;
+
+        self.load_details(&mut rows).await?;
+        Ok(rows)
+    }
+
+    async fn empty_trash(&self, deleted_before: DateTime<Utc>) -> Result<usize> {
+        log::trace!("Emptying the trash of tasks deleted before {}", deleted_before);
+        let result = sqlx::query("delete from tasks where deleted_at < $1")
+            .bind(deleted_before)
+            .execute(&self.connection_pool)
+            .await?;
+
+        Ok(result.rows_affected() as usize)
+    }
+
+    async fn delete(&self, id: i32) -> Result<()> {
+        log::trace!("Deleting task with ID: {}", id);
+        let result = sqlx::query("delete from tasks where id = $1")
             .bind(id)
             .execute(&self.connection_pool)
             .await?;
+        if result.rows_affected() == 0 {
+            return Err(TaskNotFoundError { id }.into());
+        }
 
         Ok(())
     }
 
-    async fn update(&self, task: Task) -> Result<()> {
+    async fn update(&self, mut task: Task) -> Result<()> {
         log::trace!("Updating task with ID: {}", task.id);
-        sqlx::query("update tasks set name = $1, description = $2, completed = $3 where id = $4")
-            .bind(task.name)
-            .bind(task.description)
-            .bind(task.completed)
+        task.sort_lists();
+        let mut transaction = self.connection_pool.begin().await?;
+        if let Some(parent_id) = task.parent_id {
+            Self::check_parent(&mut transaction, Some(task.id), parent_id).await?;
+        }
+
+        let result = sqlx::query("update tasks set name = $1, description = $2, status = $3, updated_at = $4, completed_at = $5, due = $6, scheduled = $7, priority = $8, project_id = $9, parent_id = $10, recurrence = $11 where id = $12 and deleted_at is null")
+            .bind(&task.name)
+            .bind(&task.description)
+            .bind(task.status)
+            .bind(Utc::now())
+            .bind(task.completed_at)
+            .bind(task.due)
+            .bind(task.scheduled)
+            .bind(task.priority)
+            .bind(task.project_id)
+            .bind(task.parent_id)
+            .bind(&task.recurrence)
             .bind(task.id)
+            .execute(&mut *transaction)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(TaskNotFoundError { id: task.id }.into());
+        }
+
+        Self::save_tags(&mut transaction, task.id, &task.tags).await?;
+        Self::save_dependencies(&mut transaction, task.id, &task.depends_on).await?;
+
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn get_projects(&self) -> Result<Vec<Project>> {
+        log::trace!("Fetching all projects");
+        let rows = sqlx::query_as::<_, Project>("select * from projects order by name")
+            .fetch_all(&self.connection_pool)
+            .await?;
+
+        Ok(rows)
+    }
+
+    async fn add_project(&self, project: NewProject) -> Result<()> {
+        log::trace!("Adding project: {:?}", project);
+        sqlx::query("insert into projects (name, description) values ($1, $2)")
+            .bind(&project.name)
+            .bind(&project.description)
+            .execute(&self.connection_pool)
+            .await
+            .map_err(|e| match e {
+                sqlx::Error::Database(e) if e.is_unique_violation() => {
+                    anyhow!("A project named \"{}\" already exists", project.name)
+                }
+                e => e.into(),
+            })?;
+
+        Ok(())
+    }
+
+    async fn archive_project(&self, id: i32, archived: bool) -> Result<()> {
+        log::trace!("Setting archived to {} on project with ID: {}", archived, id);
+        let result = sqlx::query("update projects set archived = $1 where id = $2")
+            .bind(archived)
+            .bind(id)
             .execute(&self.connection_pool)
             .await?;
+        if result.rows_affected() == 0 {
+            return Err(anyhow!("No project with id {} found", id));
+        }
+
+        Ok(())
+    }
+
+    async fn import_task(&self, mut task: Task) -> Result<()> {
+        log::trace!("Importing task with ID: {}", task.id);
+        task.sort_lists();
+        let mut transaction = self.connection_pool.begin().await?;
+        if let Some(parent_id) = task.parent_id {
+            Self::check_parent(&mut transaction, Some(task.id), parent_id).await?;
+        }
+
+        sqlx::query("insert into tasks (id, name, description, status, created_at, updated_at, completed_at, due, scheduled, priority, project_id, parent_id, recurrence, deleted_at) values ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14) \
+                     on conflict (id) do update set name = excluded.name, description = excluded.description, status = excluded.status, created_at = excluded.created_at, updated_at = excluded.updated_at, completed_at = excluded.completed_at, \
+                     due = excluded.due, scheduled = excluded.scheduled, priority = excluded.priority, project_id = excluded.project_id, parent_id = excluded.parent_id, recurrence = excluded.recurrence, deleted_at = excluded.deleted_at")
+            .bind(task.id)
+            .bind(&task.name)
+            .bind(&task.description)
+            .bind(task.status)
+            .bind(task.created_at)
+            .bind(task.updated_at)
+            .bind(task.completed_at)
+            .bind(task.due)
+            .bind(task.scheduled)
+            .bind(task.priority)
+            .bind(task.project_id)
+            .bind(task.parent_id)
+            .bind(&task.recurrence)
+            .bind(task.deleted_at)
+            .execute(&mut *transaction)
+            .await?;
+
+        Self::save_tags(&mut transaction, task.id, &task.tags).await?;
+        Self::save_dependencies(&mut transaction, task.id, &task.depends_on).await?;
+
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn import_project(&self, project: Project) -> Result<()> {
+        log::trace!("Importing project with ID: {}", project.id);
+        let mut transaction = self.connection_pool.begin().await?;
+        sqlx::query("insert into projects (id, name, description, archived) values ($1, $2, $3, $4) \
+                     on conflict (id) do update set name = excluded.name, description = excluded.description, archived = excluded.archived")
+            .bind(project.id)
+            .bind(&project.name)
+            .bind(&project.description)
+            .bind(project.archived)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|e| match e {
+                sqlx::Error::Database(e) if e.is_unique_violation() => {
+                    anyhow!("A project named \"{}\" already exists", project.name)
+                }
+                e => e.into(),
+            })?;
+
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn add_journal_entry(&self, entry: NewJournalEntry) -> Result<()> {
+        log::trace!("Adding journal entry for \"{}\"", entry.command);
+        let mut transaction = self.connection_pool.begin().await?;
+        sqlx::query("delete from journal where undone")
+            .execute(&mut *transaction)
+            .await?;
+        sqlx::query("insert into journal (batch, command, created_at, changes) values ($1, $2, $3, $4)")
+            .bind(entry.batch)
+            .bind(&entry.command)
+            .bind(entry.created_at)
+            .bind(serde_json::to_string(&entry.changes)?)
+            .execute(&mut *transaction)
+            .await?;
+        sqlx::query("delete from journal where batch not in (select distinct batch from journal order by batch desc limit $1)")
+            .bind(KEPT_BATCHES as i64)
+            .execute(&mut *transaction)
+            .await?;
 
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn get_journal(&self) -> Result<Vec<JournalEntry>> {
+        log::trace!("Fetching journal");
+        sqlx::query_as::<_, JournalRow>("select * from journal order by id")
+            .fetch_all(&self.connection_pool)
+            .await?
+            .into_iter()
+            .map(JournalRow::into_entry)
+            .collect()
+    }
+
+    async fn set_undone(&self, ids: &[i64], undone: bool) -> Result<()> {
+        log::trace!("Setting undone to {} on journal entries {:?}", undone, ids);
+        let mut transaction = self.connection_pool.begin().await?;
+        for id in ids {
+            sqlx::query("update journal set undone = $1 where id = $2")
+                .bind(undone)
+                .bind(id)
+                .execute(&mut *transaction)
+                .await?;
+        }
+
+        transaction.commit().await?;
         Ok(())
     }
 }
@@ -78,3 +613,50 @@ impl Repository for SqliteRepository {
 pub fn get_sqlite_repository(config: &Config) -> Result<Box<dyn Repository + Sync>> {
     Ok(Box::from(SqliteRepository::new(config.storage())?))
 }
+
+pub fn get_read_only_sqlite_repository(config: &Config) -> Result<Option<Box<dyn Repository + Sync>>> {
+    Ok(SqliteRepository::read_only(config.storage())?.map(|repository| Box::from(repository) as Box<dyn Repository + Sync>))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SqliteRepository;
+    use crate::persistence::Repository;
+    use crate::tasks::NewTask;
+    use url::Url;
+
+    #[tokio::test]
+    async fn recurrence_rules_survive_a_round_trip() {
+        let directory = tempfile::tempdir().unwrap();
+        let url = Url::parse(&format!("sqlite://{}", directory.path().join("tasks.db").display())).unwrap();
+        let repository = SqliteRepository::new(&url).unwrap();
+        repository.migrate().await.unwrap();
+
+        let recurrence = "every 2 weeks on mon,thu".parse().unwrap();
+        repository.add(NewTask {
+            name: "review".into(),
+            recurrence: Some(recurrence),
+            ..NewTask::default()
+        }).await.unwrap();
+
+        let task = repository.get_by_id(1).await.unwrap();
+        assert_eq!(task.recurrence.unwrap().to_string(), "every 2 weeks on mon,thu");
+    }
+
+    #[tokio::test]
+    async fn reading_the_schema_status_writes_nothing() {
+        let directory = tempfile::tempdir().unwrap();
+        let url = Url::parse(&format!("sqlite://{}", directory.path().join("tasks.db").display())).unwrap();
+        let repository = SqliteRepository::new(&url).unwrap();
+
+        let status = repository.schema_status().await.unwrap().unwrap();
+
+        assert_eq!(status.current_version, None);
+        assert!(!status.is_up_to_date());
+        let tables = sqlx::query_scalar::<_, i64>("select count(*) from sqlite_master where type = 'table'")
+            .fetch_one(&repository.connection_pool)
+            .await
+            .unwrap();
+        assert_eq!(tables, 0);
+    }
+}
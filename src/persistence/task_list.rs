@@ -0,0 +1,184 @@
+use crate::error::{DependencyCycleError, TaskCycleError, TaskNotFoundError};
+use crate::journal::{JournalEntry, NewJournalEntry, KEPT_BATCHES};
+use crate::projects::{NewProject, Project};
+use crate::tasks::{find_dependency_cycle, would_create_cycle, NewTask, Task};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+
+/// One more than the highest of `ids`, or 1 if there are none.
+pub fn next_id(ids: impl Iterator<Item = i32>) -> i32 {
+    ids.max().map(|max| max + 1).unwrap_or(1)
+}
+
+/// Adds a task to a list held in memory. These functions check the same things the
+/// databases do, and leave the list alone when a check fails.
+pub fn add_task(tasks: &mut Vec<Task>, id: i32, task: NewTask, now: DateTime<Utc>) -> Result<()> {
+    if let Some(parent_id) = task.parent_id {
+        check_parent(tasks, None, parent_id)?;
+    }
+
+    tasks.push(Task::from_new_task(id, task, now));
+    Ok(())
+}
+
+pub fn remove_task(tasks: &mut [Task], id: i32, now: DateTime<Utc>) -> Result<()> {
+    let task = tasks.iter_mut()
+                    .find(|task| task.id == id && task.deleted_at.is_none())
+                    .ok_or(TaskNotFoundError { id })?;
+    task.deleted_at = Some(now);
+
+    // Subtasks of a removed task move up to the top level, as they do in the databases.
+    detach(tasks, id);
+    Ok(())
+}
+
+pub fn delete_task(tasks: &mut Vec<Task>, id: i32) -> Result<()> {
+    if !tasks.iter().any(|task| task.id == id) {
+        return Err(TaskNotFoundError { id }.into());
+    }
+
+    tasks.retain(|task| task.id != id);
+    detach(tasks, id);
+    Ok(())
+}
+
+/// Deletes the tasks moved to the trash before `deleted_before` for good, returning how
+/// many there were.
+pub fn empty_trash(tasks: &mut Vec<Task>, deleted_before: DateTime<Utc>) -> usize {
+    let purged: Vec<i32> = tasks.iter()
+                                .filter(|task| task.deleted_at.is_some_and(|deleted_at| deleted_at < deleted_before))
+                                .map(|task| task.id)
+                                .collect();
+
+    tasks.retain(|task| !purged.contains(&task.id));
+    for id in purged.iter() {
+        detach(tasks, *id);
+    }
+    purged.len()
+}
+
+pub fn update_task(tasks: &mut [Task], mut task: Task, now: DateTime<Utc>) -> Result<()> {
+    let index = tasks.iter()
+                     .position(|a| a.id == task.id && a.deleted_at.is_none())
+                     .ok_or(TaskNotFoundError { id: task.id })?;
+    if let Some(parent_id) = task.parent_id {
+        check_parent(tasks, Some(task.id), parent_id)?;
+    }
+    check_dependencies(tasks, &task)?;
+
+    // When a task was created is not something an update can change.
+    task.created_at = tasks[index].created_at;
+    task.updated_at = now;
+    task.sort_lists();
+    tasks[index] = task;
+    Ok(())
+}
+
+pub fn add_project(projects: &mut Vec<Project>, id: i32, project: NewProject) -> Result<()> {
+    if projects.iter().any(|existing| existing.name == project.name) {
+        return Err(anyhow!("A project named \"{}\" already exists", project.name));
+    }
+
+    projects.push(Project::from_new_project(id, project));
+    Ok(())
+}
+
+pub fn archive_project(projects: &mut [Project], id: i32, archived: bool) -> Result<()> {
+    let project = projects.iter_mut()
+                          .find(|project| project.id == id)
+                          .ok_or_else(|| anyhow!("No project with id {} found", id))?;
+    project.archived = archived;
+    Ok(())
+}
+
+/// Stores a task copied from another backend as it is, replacing any task with the same id.
+pub fn import_task(tasks: &mut Vec<Task>, mut task: Task) -> Result<()> {
+    if let Some(parent_id) = task.parent_id {
+        check_parent(tasks, Some(task.id), parent_id)?;
+    }
+    check_dependencies(tasks, &task)?;
+
+    task.sort_lists();
+    match tasks.iter().position(|existing| existing.id == task.id) {
+        Some(index) => tasks[index] = task,
+        None => tasks.push(task),
+    }
+    Ok(())
+}
+
+pub fn import_project(projects: &mut Vec<Project>, project: Project) -> Result<()> {
+    if projects.iter().any(|existing| existing.name == project.name && existing.id != project.id) {
+        return Err(anyhow!("A project named \"{}\" already exists", project.name));
+    }
+
+    match projects.iter().position(|existing| existing.id == project.id) {
+        Some(index) => projects[index] = project,
+        None => projects.push(project),
+    }
+    Ok(())
+}
+
+/// Adds an entry to a journal held in memory. Undone entries are dropped first, since they
+/// can no longer be redone, and only the newest `KEPT_BATCHES` batches are kept.
+pub fn add_journal_entry(journal: &mut Vec<JournalEntry>, entry: NewJournalEntry) {
+    journal.retain(|existing| !existing.undone);
+    let id = journal.iter().map(|existing| existing.id).max().map_or(1, |max| max + 1);
+    journal.push(JournalEntry::from_new_entry(id, entry));
+
+    let mut batches: Vec<i64> = journal.iter().map(|existing| existing.batch).collect();
+    batches.sort_by(|a, b| b.cmp(a));
+    batches.dedup();
+    batches.truncate(KEPT_BATCHES);
+    journal.retain(|existing| batches.contains(&existing.batch));
+}
+
+pub fn set_undone(journal: &mut [JournalEntry], ids: &[i64], undone: bool) {
+    for entry in journal.iter_mut().filter(|entry| ids.contains(&entry.id)) {
+        entry.undone = undone;
+    }
+}
+
+/// Lets go of a task that has been removed: its subtasks move up to the top level and
+/// nothing depends on it any more.
+fn detach(tasks: &mut [Task], id: i32) {
+    for task in tasks.iter_mut() {
+        if task.parent_id == Some(id) {
+            task.parent_id = None;
+        }
+        task.depends_on.retain(|depends_on| *depends_on != id);
+    }
+}
+
+/// Makes sure a parent exists outside the trash and that `task_id`, if it already exists,
+/// is not above it.
+fn check_parent(tasks: &[Task], task_id: Option<i32>, parent_id: i32) -> Result<()> {
+    if !tasks.iter().any(|task| task.id == parent_id && task.deleted_at.is_none()) {
+        return Err(TaskNotFoundError { id: parent_id }.into());
+    }
+
+    if let Some(task_id) = task_id
+        && would_create_cycle(tasks, task_id, parent_id) {
+        return Err(TaskCycleError { task: task_id, parent: parent_id }.into());
+    }
+
+    Ok(())
+}
+
+/// Makes sure every dependency of `task` exists and that none of them wait for it in turn.
+fn check_dependencies(tasks: &[Task], task: &Task) -> Result<()> {
+    let dependencies: Vec<_> = tasks.iter()
+                                    .flat_map(|task| task.depends_on.iter().map(|depends_on| (task.id, *depends_on)))
+                                    .collect();
+
+    for depends_on in task.depends_on.iter().copied() {
+        if !tasks.iter().any(|task| task.id == depends_on && task.deleted_at.is_none()) {
+            return Err(TaskNotFoundError { id: depends_on }.into());
+        }
+
+        if let Some(cycle) = find_dependency_cycle(&dependencies, task.id, depends_on) {
+            return Err(DependencyCycleError { cycle }.into());
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,334 @@
+use crate::persistence::Repository;
+use crate::projects::Project;
+use crate::tasks::Task;
+use anyhow::{bail, Result};
+use chrono::SubsecRound;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+/// What `todo migrate` copied, and what it found already there from an earlier run.
+#[derive(Debug, Default)]
+pub struct TransferReport {
+    pub copied_tasks: usize,
+    pub copied_projects: usize,
+    pub existing_tasks: usize,
+    pub existing_projects: usize,
+}
+
+impl Display for TransferReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Copied {} task(s) and {} project(s)", self.copied_tasks, self.copied_projects)?;
+        if self.existing_tasks > 0 || self.existing_projects > 0 {
+            writeln!(f, "Skipped {} task(s) and {} project(s) already in the target",
+                     self.existing_tasks, self.existing_projects)?;
+        }
+        writeln!(f, "Verified {} task(s) and {} project(s) in the target",
+                 self.copied_tasks + self.existing_tasks, self.copied_projects + self.existing_projects)
+    }
+}
+
+/// Every task in a repository, the trash included, by id.
+pub async fn all_tasks(repository: &(dyn Repository + Sync)) -> Result<Vec<Task>> {
+    let mut tasks = repository.get_all().await?;
+    tasks.extend(repository.get_trash().await?);
+    tasks.sort_by_key(|task| task.id);
+    Ok(tasks)
+}
+
+/// Copies every project and task from `source` to `target`, keeping their ids, and checks
+/// afterwards that the target holds all of them. Tasks in the trash are copied into the
+/// target's trash. `progress` is told how many of the tasks have been dealt with after each one.
+///
+/// Anything already in the target that matches the source is left alone, so an interrupted
+/// copy can simply be run again. Anything that differs under the same id is refused before
+/// a single change is made.
+pub async fn transfer(
+    source: &(dyn Repository + Sync),
+    target: &(dyn Repository + Sync),
+    progress: &mut dyn FnMut(usize, usize),
+) -> Result<TransferReport> {
+    let tasks = all_tasks(source).await?;
+    let mut projects = source.get_projects().await?;
+    projects.sort_by_key(|project| project.id);
+
+    let target_tasks: HashMap<i32, Task> = all_tasks(target).await?.into_iter().map(|task| (task.id, task)).collect();
+    let target_projects: HashMap<i32, Project> = target.get_projects()
+                                                       .await?
+                                                       .into_iter()
+                                                       .map(|project| (project.id, project))
+                                                       .collect();
+
+    for project in projects.iter() {
+        if let Some(existing) = target_projects.get(&project.id)
+            && existing != project {
+            bail!("The target already has a different project with id {} (\"{}\"), refusing to overwrite it", project.id, existing.name);
+        }
+    }
+    for task in tasks.iter() {
+        if let Some(existing) = target_tasks.get(&task.id)
+            && without_dependencies(existing) != without_dependencies(task) {
+            bail!("The target already has a different task with id {} (\"{}\"), refusing to overwrite it", task.id, existing.name);
+        }
+    }
+
+    let mut report = TransferReport::default();
+    for project in projects.iter() {
+        if target_projects.contains_key(&project.id) {
+            report.existing_projects += 1;
+        } else {
+            target.import_project(project.clone()).await?;
+            report.copied_projects += 1;
+        }
+    }
+
+    // Parents go in before their subtasks, and dependencies once every task is there, since
+    // a task may depend on one further down the tree than itself.
+    let ordered = parents_first(&tasks)?;
+    for (done, task) in ordered.iter().enumerate() {
+        if target_tasks.contains_key(&task.id) {
+            report.existing_tasks += 1;
+        } else {
+            target.import_task(Task { depends_on: vec![], ..(*task).clone() }).await?;
+            report.copied_tasks += 1;
+        }
+        progress(done + 1, tasks.len());
+    }
+    for task in ordered.iter().filter(|task| !task.depends_on.is_empty()) {
+        if target_tasks.get(&task.id).is_none_or(|existing| existing.depends_on != task.depends_on) {
+            target.import_task((*task).clone()).await?;
+        }
+    }
+
+    verify(&tasks, &projects, target).await?;
+    Ok(report)
+}
+
+/// What `todo restore` put back.
+#[derive(Debug, Default)]
+pub struct RestoreReport {
+    pub restored_tasks: usize,
+    pub restored_projects: usize,
+    pub unchanged_tasks: usize,
+    pub removed_tasks: usize,
+}
+
+impl Display for RestoreReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Restored {} task(s) and {} project(s), {} task(s) were unchanged",
+                 self.restored_tasks, self.restored_projects, self.unchanged_tasks)?;
+        if self.removed_tasks > 0 {
+            writeln!(f, "Removed {} task(s) that were not in the backup", self.removed_tasks)?;
+        }
+        Ok(())
+    }
+}
+
+/// Puts `target` back the way it was when `tasks` and `projects` were read from it: tasks
+/// that were changed are overwritten, removed ones come back with their ids, and tasks added
+/// since are deleted for good. Tasks that were in the trash go back into it. Projects cannot
+/// be removed, so any added since are kept.
+pub async fn restore(target: &(dyn Repository + Sync), tasks: &[Task], projects: &[Project]) -> Result<RestoreReport> {
+    let _lock = target.hold_lock().await?;
+    let mut report = RestoreReport::default();
+    let ordered = parents_first(tasks)?;
+
+    let mut projects = projects.to_vec();
+    projects.sort_by_key(|project| project.id);
+    let current_projects = target.get_projects().await?;
+    for project in projects.iter().filter(|project| !current_projects.contains(project)) {
+        target.import_project(project.clone()).await?;
+        report.restored_projects += 1;
+    }
+
+    for task in all_tasks(target).await? {
+        if !tasks.iter().any(|restored| restored.id == task.id) {
+            target.delete(task.id).await?;
+            report.removed_tasks += 1;
+        }
+    }
+
+    // Tasks that changed lose their dependencies first, so ones added since the backup
+    // cannot get in the way of putting the old ones back.
+    let current: HashMap<i32, Task> = all_tasks(target).await?.into_iter().map(|task| (task.id, task)).collect();
+    let changed: Vec<&Task> = ordered.into_iter()
+                                     .filter(|task| current.get(&task.id).map(comparable) != Some(comparable(task)))
+                                     .collect();
+    report.unchanged_tasks = tasks.len() - changed.len();
+    for task in changed.iter() {
+        target.import_task(Task { depends_on: vec![], ..(*task).clone() }).await?;
+        report.restored_tasks += 1;
+    }
+    for task in changed.iter().filter(|task| !task.depends_on.is_empty()) {
+        target.import_task((*task).clone()).await?;
+    }
+
+    verify(tasks, &projects, target).await?;
+    Ok(report)
+}
+
+/// Checks that every task and project made it to the target unchanged.
+async fn verify(tasks: &[Task], projects: &[Project], target: &(dyn Repository + Sync)) -> Result<()> {
+    let copied_tasks: HashMap<i32, Task> = all_tasks(target).await?.into_iter().map(|task| (task.id, task)).collect();
+    let missing_tasks = tasks.iter()
+                             .filter(|task| copied_tasks.get(&task.id).map(comparable) != Some(comparable(task)))
+                             .count();
+
+    let copied_projects = target.get_projects().await?;
+    let missing_projects = projects.iter()
+                                   .filter(|project| !copied_projects.contains(project))
+                                   .count();
+
+    if missing_tasks > 0 || missing_projects > 0 {
+        bail!("{} of {} task(s) and {} of {} project(s) did not make it to the target unchanged",
+              missing_tasks, tasks.len(), missing_projects, projects.len());
+    }
+    Ok(())
+}
+
+/// The tasks in an order where every parent comes before its subtasks.
+pub fn parents_first(tasks: &[Task]) -> Result<Vec<&Task>> {
+    let parents: HashMap<i32, Option<i32>> = tasks.iter().map(|task| (task.id, task.parent_id)).collect();
+
+    let mut depths = HashMap::new();
+    for task in tasks {
+        let mut depth = 0;
+        let mut current = task.parent_id;
+        while let Some(parent_id) = current {
+            depth += 1;
+            if depth > tasks.len() {
+                bail!("Task {} is its own ancestor, run `todo doctor` on the source first", task.id);
+            }
+            current = parents.get(&parent_id).copied().flatten();
+        }
+        depths.insert(task.id, depth);
+    }
+
+    let mut ordered: Vec<&Task> = tasks.iter().collect();
+    ordered.sort_by_key(|task| (depths[&task.id], task.id));
+    Ok(ordered)
+}
+
+fn without_dependencies(task: &Task) -> Task {
+    Task {
+        depends_on: vec![],
+        ..comparable(task)
+    }
+}
+
+/// A task as it compares across backends. Postgres keeps timestamps to the microsecond,
+/// so anything finer is dropped.
+pub fn comparable(task: &Task) -> Task {
+    let mut task = task.clone();
+    task.created_at = task.created_at.trunc_subsecs(6);
+    task.updated_at = task.updated_at.trunc_subsecs(6);
+    task.completed_at = task.completed_at.map(|completed_at| completed_at.trunc_subsecs(6));
+    task.due = task.due.map(|due| due.trunc_subsecs(6));
+    task.scheduled = task.scheduled.map(|scheduled| scheduled.trunc_subsecs(6));
+    task.deleted_at = task.deleted_at.map(|deleted_at| deleted_at.trunc_subsecs(6));
+    task.sort_lists();
+    task
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{all_tasks, restore, transfer};
+    use crate::persistence::memory_repository::MemoryRepository;
+    use crate::persistence::Repository;
+    use crate::projects::{NewProject, Project};
+    use crate::tasks::{NewTask, Task};
+    use chrono::Utc;
+
+    /// A task moved under a later one, whose new parent waits for it, so neither id order
+    /// nor tree order alone would get everything in, and a task in the trash waiting for one.
+    async fn source() -> MemoryRepository {
+        let source = MemoryRepository::new(vec![], vec![]);
+        source.add_project(NewProject { name: "house".into(), ..NewProject::default() }).await.unwrap();
+        for name in ["Fix the door", "Buy hinges", "Plan the week", "Sweep up"] {
+            source.add(NewTask { name: name.into(), project_id: Some(1), ..NewTask::default() }).await.unwrap();
+        }
+
+        let mut door = source.get_by_id(1).await.unwrap();
+        door.parent_id = Some(3);
+        door.tags = vec!["home".into()];
+        source.update(door).await.unwrap();
+        let mut hinges = source.get_by_id(2).await.unwrap();
+        hinges.parent_id = Some(1);
+        source.update(hinges).await.unwrap();
+        let mut week = source.get_by_id(3).await.unwrap();
+        week.depends_on = vec![1];
+        source.update(week).await.unwrap();
+        source.add(NewTask { name: "Oil the lock".into(), ..NewTask::default() }).await.unwrap();
+        let mut lock = source.get_by_id(5).await.unwrap();
+        lock.depends_on = vec![1];
+        source.update(lock).await.unwrap();
+        source.remove(5).await.unwrap();
+        source
+    }
+
+    #[tokio::test]
+    async fn everything_is_copied_with_its_id() {
+        let source = source().await;
+        let target = MemoryRepository::new(vec![], vec![]);
+        let mut seen = vec![];
+
+        let report = transfer(&source, &target, &mut |done, total| seen.push((done, total))).await.unwrap();
+
+        assert_eq!((report.copied_tasks, report.copied_projects), (5, 1));
+        assert_eq!(seen, [(1, 5), (2, 5), (3, 5), (4, 5), (5, 5)]);
+        assert_eq!(target.get_all().await.unwrap(), source.get_all().await.unwrap());
+        assert_eq!(target.get_projects().await.unwrap(), source.get_projects().await.unwrap());
+        assert_eq!(target.get_trash().await.unwrap(), source.get_trash().await.unwrap());
+
+        target.add(NewTask { name: "Next".into(), ..NewTask::default() }).await.unwrap();
+        assert_eq!(target.get_all().await.unwrap().last().unwrap().id, 6);
+    }
+
+    #[tokio::test]
+    async fn an_interrupted_copy_picks_up_where_it_stopped() {
+        let source = source().await;
+        let mut week = source.get_by_id(3).await.unwrap();
+        week.depends_on.clear();
+        let target = MemoryRepository::new(vec![week], source.get_projects().await.unwrap());
+
+        let report = transfer(&source, &target, &mut |_, _| {}).await.unwrap();
+
+        assert_eq!((report.copied_tasks, report.existing_tasks, report.existing_projects), (4, 1, 1));
+        assert_eq!(target.get_all().await.unwrap(), source.get_all().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn different_tasks_under_the_same_id_are_not_overwritten() {
+        let source = source().await;
+        let other = Task::from_new_task(4, NewTask { name: "Something else".into(), ..NewTask::default() }, Utc::now());
+        let project = Project::from_new_project(1, NewProject { name: "house".into(), ..NewProject::default() });
+        let target = MemoryRepository::new(vec![other.clone()], vec![project]);
+
+        let error = transfer(&source, &target, &mut |_, _| {}).await.unwrap_err();
+
+        assert!(error.to_string().contains("different task with id 4"));
+        assert_eq!(target.get_all().await.unwrap(), [other]);
+    }
+
+    #[tokio::test]
+    async fn restoring_undoes_every_change_since() {
+        let repository = source().await;
+        let tasks = all_tasks(&repository).await.unwrap();
+        let projects = repository.get_projects().await.unwrap();
+
+        repository.remove(2).await.unwrap();
+        let mut door = repository.get_by_id(1).await.unwrap();
+        door.name = "Fix the gate".into();
+        door.parent_id = None;
+        repository.update(door).await.unwrap();
+        repository.add(NewTask { name: "Added since".into(), ..NewTask::default() }).await.unwrap();
+        let mut sweep = repository.get_by_id(4).await.unwrap();
+        sweep.depends_on = vec![3];
+        repository.update(sweep).await.unwrap();
+
+        let report = restore(&repository, &tasks, &projects).await.unwrap();
+
+        assert_eq!((report.restored_tasks, report.unchanged_tasks, report.removed_tasks), (3, 2, 1));
+        assert_eq!(all_tasks(&repository).await.unwrap(), tasks);
+        assert_eq!(repository.get_trash().await.unwrap().iter().map(|task| task.id).collect::<Vec<_>>(), [5]);
+    }
+}
@@ -0,0 +1,180 @@
+use crate::error::JournalConflictError;
+use crate::journal::Batch;
+use crate::persistence::transfer::{all_tasks, comparable, parents_first};
+use crate::persistence::Repository;
+use crate::tasks::Task;
+use anyhow::Result;
+use std::collections::HashMap;
+
+/// Puts back the tasks changed by the newest batch in the journal that has not been undone,
+/// and returns it, or `None` if there is nothing to undo.
+///
+/// Refuses if any of those tasks changed since outside the journal, such as by a restore,
+/// unless `force` is set. The storage stays locked throughout, so no other change can come
+/// between the check and the tasks being put back.
+pub async fn undo(repository: &(dyn Repository + Sync), force: bool) -> Result<Option<Batch>> {
+    let _lock = repository.hold_lock().await?;
+    let batches = Batch::group(repository.get_journal().await?);
+    let Some(batch) = Batch::next_to_undo(&batches) else {
+        return Ok(None);
+    };
+
+    log::debug!("Undoing `{}`", batch.command());
+    check(repository, batch, &batch.after(), force).await?;
+    apply(repository, batch.before()).await?;
+    repository.set_undone(&batch.entry_ids(), true).await?;
+    Ok(Some(batch.clone()))
+}
+
+/// Makes the changes of the batch undone most recently again, and returns it, or `None` if
+/// there is nothing to redo.
+pub async fn redo(repository: &(dyn Repository + Sync), force: bool) -> Result<Option<Batch>> {
+    let _lock = repository.hold_lock().await?;
+    let batches = Batch::group(repository.get_journal().await?);
+    let Some(batch) = Batch::next_to_redo(&batches) else {
+        return Ok(None);
+    };
+
+    log::debug!("Redoing `{}`", batch.command());
+    check(repository, batch, &batch.before(), force).await?;
+    apply(repository, batch.after()).await?;
+    repository.set_undone(&batch.entry_ids(), false).await?;
+    Ok(Some(batch.clone()))
+}
+
+/// Makes sure the tasks are still in the `expected` states, in the trash or out of it, so
+/// nothing made since is lost.
+async fn check(repository: &(dyn Repository + Sync), batch: &Batch, expected: &[(i32, Option<Task>)], force: bool) -> Result<()> {
+    let current: HashMap<i32, Task> = all_tasks(repository).await?.into_iter().map(|task| (task.id, task)).collect();
+    let ids: Vec<i32> = expected.iter()
+                                .filter(|(id, state)| current.get(id).map(comparable) != state.as_ref().map(comparable))
+                                .map(|(id, _)| *id)
+                                .collect();
+
+    if ids.is_empty() {
+        return Ok(());
+    }
+    if force {
+        log::warn!("Overwriting task(s) {:?}, which changed since `{}`", ids, batch.command());
+        return Ok(());
+    }
+    Err(JournalConflictError { command: batch.command().into(), ids }.into())
+}
+
+/// Puts every task in `states` into that state with its id and timestamps, moving it in or
+/// out of the trash as the state says. Those whose state is `None` never existed, so they
+/// are deleted for good rather than moved to the trash.
+async fn apply(repository: &(dyn Repository + Sync), states: Vec<(i32, Option<Task>)>) -> Result<()> {
+    let current = all_tasks(repository).await?;
+    let mut desired: Vec<Task> = current.iter()
+                                        .filter(|task| !states.iter().any(|(id, _)| *id == task.id))
+                                        .cloned()
+                                        .collect();
+    desired.extend(states.iter().filter_map(|(_, state)| state.clone()));
+
+    // Subtasks go before their parents, so no deletion has to move anything out of the way.
+    for task in parents_first(&current)?.into_iter().rev() {
+        if states.iter().any(|(id, state)| *id == task.id && state.is_none()) {
+            repository.delete(task.id).await?;
+        }
+    }
+
+    // As with a restore, dependencies go in once every task is back.
+    let current: HashMap<i32, Task> = all_tasks(repository).await?.into_iter().map(|task| (task.id, task)).collect();
+    let changed: Vec<&Task> = parents_first(&desired)?.into_iter()
+                                                      .filter(|task| states.iter().any(|(id, _)| *id == task.id))
+                                                      .filter(|task| current.get(&task.id).map(comparable) != Some(comparable(task)))
+                                                      .collect();
+    for task in changed.iter() {
+        repository.import_task(Task { depends_on: vec![], ..(*task).clone() }).await?;
+    }
+    for task in changed.iter().filter(|task| !task.depends_on.is_empty()) {
+        repository.import_task((*task).clone()).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{redo, undo};
+    use crate::error::JournalConflictError;
+    use crate::persistence::journaling_repository::JournalingRepository;
+    use crate::persistence::memory_repository::MemoryRepository;
+    use crate::persistence::Repository;
+    use crate::tasks::NewTask;
+
+    /// A run that adds a task with a subtask and one waiting for it, and a run that removes
+    /// the first task, detaching the other two.
+    async fn journaled() -> MemoryRepository {
+        let first_run = JournalingRepository::for_batch(Box::new(MemoryRepository::new(vec![], vec![])), 1, "add");
+        for name in ["Fix the door", "Buy hinges", "Sweep up"] {
+            first_run.add(NewTask { name: name.into(), ..NewTask::default() }).await.unwrap();
+        }
+        let mut hinges = first_run.get_by_id(2).await.unwrap();
+        hinges.parent_id = Some(1);
+        first_run.update(hinges).await.unwrap();
+        let mut sweep = first_run.get_by_id(3).await.unwrap();
+        sweep.depends_on = vec![1];
+        first_run.update(sweep).await.unwrap();
+
+        let second_run = JournalingRepository::for_batch(Box::new(MemoryRepository::copy_of(&first_run).await.unwrap()), 2, "remove 1");
+        second_run.remove(1).await.unwrap();
+        MemoryRepository::copy_of(&second_run).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn each_run_is_undone_and_redone_as_a_whole() {
+        let repository = journaled().await;
+        let removed = repository.get_all().await.unwrap();
+        assert!(removed.iter().all(|task| task.parent_id.is_none() && task.depends_on.is_empty()));
+
+        let undone = undo(&repository, false).await.unwrap().unwrap();
+        assert_eq!(undone.command(), "remove 1");
+        let tasks = repository.get_all().await.unwrap();
+        assert_eq!(tasks.len(), 3);
+        assert_eq!((tasks[1].parent_id, tasks[2].depends_on.clone()), (Some(1), vec![1]));
+
+        let undone = undo(&repository, false).await.unwrap().unwrap();
+        assert_eq!((undone.command(), undone.entries.len()), ("add", 5));
+        assert!(repository.get_all().await.unwrap().is_empty());
+        assert!(repository.get_trash().await.unwrap().is_empty());
+        assert!(undo(&repository, false).await.unwrap().is_none());
+
+        redo(&repository, false).await.unwrap().unwrap();
+        assert_eq!(repository.get_all().await.unwrap(), tasks);
+        redo(&repository, false).await.unwrap().unwrap();
+        assert_eq!(repository.get_all().await.unwrap(), removed);
+        assert_eq!(repository.get_trash().await.unwrap().iter().map(|task| task.id).collect::<Vec<_>>(), [1]);
+        assert!(redo(&repository, false).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn undoing_a_restore_from_the_trash_puts_the_task_back_there() {
+        let repository = journaled().await;
+        let trashed = repository.get_trash().await.unwrap();
+        let run = JournalingRepository::for_batch(Box::new(MemoryRepository::copy_of(&repository).await.unwrap()), 3, "trash restore 1");
+        run.restore_from_trash(1).await.unwrap();
+        let repository = MemoryRepository::copy_of(&run).await.unwrap();
+
+        undo(&repository, false).await.unwrap().unwrap();
+        assert_eq!(repository.get_trash().await.unwrap(), trashed);
+        redo(&repository, false).await.unwrap().unwrap();
+        assert!(repository.get_trash().await.unwrap().is_empty());
+        assert_eq!(repository.get_by_id(1).await.unwrap().name, "Fix the door");
+    }
+
+    #[tokio::test]
+    async fn changes_made_outside_the_journal_are_not_overwritten() {
+        let repository = journaled().await;
+        let mut sweep = repository.get_by_id(3).await.unwrap();
+        sweep.name = "Sweep the yard".into();
+        repository.import_task(sweep.clone()).await.unwrap();
+
+        let error = undo(&repository, false).await.unwrap_err();
+
+        assert_eq!(error.downcast_ref::<JournalConflictError>().unwrap().ids, [3]);
+        assert_eq!(repository.get_by_id(3).await.unwrap(), sweep);
+        undo(&repository, true).await.unwrap().unwrap();
+        assert_eq!(repository.get_all().await.unwrap().len(), 3);
+    }
+}
@@ -0,0 +1,8 @@
+mod project;
+pub use project::Project;
+
+mod new_project;
+pub use new_project::NewProject;
+
+mod project_groups;
+pub use project_groups::group_by_project;
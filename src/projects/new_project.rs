@@ -0,0 +1,5 @@
+#[derive(Debug, Hash, Clone, Default)]
+pub struct NewProject {
+    pub name: String,
+    pub description: Option<String>,
+}
@@ -0,0 +1,44 @@
+use crate::projects::NewProject;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::fmt::{Display, Error, Formatter};
+
+
+/// A named group of tasks. Archived projects keep their tasks but hide them from listings.
+#[derive(Debug, Hash, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct Project {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub archived: bool,
+}
+
+
+impl Project {
+    /// Builds the stored form of a new project once the backend has picked its id.
+    pub fn from_new_project(id: i32, project: NewProject) -> Self {
+        Self {
+            id,
+            name: project.name,
+            description: project.description,
+            archived: false,
+        }
+    }
+}
+
+impl Display for Project {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "{}", self.name)?;
+
+        if let Some(description) = &self.description {
+            write!(f, ": {}", description)?;
+        }
+
+        if self.archived {
+            write!(f, " (archived)")?;
+        }
+
+        Ok(())
+    }
+}
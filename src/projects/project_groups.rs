@@ -0,0 +1,63 @@
+use crate::projects::Project;
+use crate::tasks::Task;
+
+/// Splits a listing into one group per project, keeping the order of the tasks within
+/// each group. Tasks without a known project come first, then projects in the order given.
+pub fn group_by_project<'a>(tasks: &'a [Task], projects: &'a [Project]) -> Vec<(Option<&'a Project>, Vec<&'a Task>)> {
+    let mut groups = vec![];
+
+    let loose: Vec<_> = tasks.iter()
+                             .filter(|task| task.project_id.is_none_or(|id| !projects.iter().any(|project| project.id == id)))
+                             .collect();
+    if !loose.is_empty() {
+        groups.push((None, loose));
+    }
+
+    for project in projects {
+        let members: Vec<_> = tasks.iter()
+                                   .filter(|task| task.project_id == Some(project.id))
+                                   .collect();
+        if !members.is_empty() {
+            groups.push((Some(project), members));
+        }
+    }
+
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::group_by_project;
+    use crate::projects::{NewProject, Project};
+    use crate::tasks::{NewTask, Task};
+    use chrono::Utc;
+
+    fn task(id: i32, project_id: Option<i32>) -> Task {
+        Task::from_new_task(id, NewTask {
+            name: format!("task {}", id),
+            project_id,
+            ..NewTask::default()
+        }, Utc::now())
+    }
+
+    #[test]
+    fn loose_tasks_come_first_and_empty_projects_are_skipped() {
+        let projects = vec![
+            Project::from_new_project(2, NewProject { name: "garden".into(), ..NewProject::default() }),
+            Project::from_new_project(1, NewProject { name: "house".into(), ..NewProject::default() }),
+            Project::from_new_project(3, NewProject { name: "empty".into(), ..NewProject::default() }),
+        ];
+        let tasks = vec![task(1, Some(1)), task(2, None), task(3, Some(2)), task(4, Some(1)), task(5, Some(9))];
+
+        let groups: Vec<_> = group_by_project(&tasks, &projects)
+            .into_iter()
+            .map(|(project, tasks)| (project.map(|p| p.name.as_str()), tasks.iter().map(|t| t.id).collect::<Vec<_>>()))
+            .collect();
+
+        assert_eq!(groups, vec![
+            (None, vec![2, 5]),
+            (Some("garden"), vec![3]),
+            (Some("house"), vec![1, 4]),
+        ]);
+    }
+}
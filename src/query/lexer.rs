@@ -0,0 +1,165 @@
+use crate::error::QueryParseError;
+
+/// How a field is compared with a value, as in `due<friday` or `tag:ops`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    /// `:`, which means "has" or "contains" depending on the field.
+    Matches,
+    /// `=`
+    Equals,
+    /// `!=`
+    NotEquals,
+    /// `<`
+    Less,
+    /// `<=`
+    LessOrEqual,
+    /// `>`
+    Greater,
+    /// `>=`
+    GreaterOrEqual,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenKind {
+    OpenParen,
+    CloseParen,
+    Operator(Operator),
+    /// A bare word, which may be a keyword such as `and`.
+    Word(String),
+    /// A double quoted string, which is never a keyword.
+    Quoted(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    /// Where the token starts, counting characters from 1.
+    pub column: usize,
+}
+
+/// Splits a query into tokens.
+///
+/// A value straight after an operator runs to the next space or bracket, so it can
+/// contain `:` and `-` as in `due<2026-11-01T17:00`. Values with spaces need quotes.
+pub fn tokenise(input: &str) -> Result<Vec<Token>, QueryParseError> {
+    let characters: Vec<char> = input.chars().collect();
+    let mut tokens: Vec<Token> = vec![];
+    let mut index = 0;
+
+    while index < characters.len() {
+        let character = characters[index];
+        let column = index + 1;
+
+        if character.is_whitespace() {
+            index += 1;
+            continue;
+        }
+
+        let (kind, length) = match character {
+            '(' => (TokenKind::OpenParen, 1),
+            ')' => (TokenKind::CloseParen, 1),
+            ':' => (TokenKind::Operator(Operator::Matches), 1),
+            '=' => (TokenKind::Operator(Operator::Equals), 1),
+            '!' if characters.get(index + 1) == Some(&'=') => (TokenKind::Operator(Operator::NotEquals), 2),
+            '<' if characters.get(index + 1) == Some(&'=') => (TokenKind::Operator(Operator::LessOrEqual), 2),
+            '<' => (TokenKind::Operator(Operator::Less), 1),
+            '>' if characters.get(index + 1) == Some(&'=') => (TokenKind::Operator(Operator::GreaterOrEqual), 2),
+            '>' => (TokenKind::Operator(Operator::Greater), 1),
+            '"' => {
+                let (text, length) = quoted(&characters[index..]).ok_or_else(|| QueryParseError {
+                    column,
+                    message: "unterminated quote".into(),
+                })?;
+                (TokenKind::Quoted(text), length)
+            }
+            _ => {
+                let after_operator = matches!(tokens.last(), Some(Token { kind: TokenKind::Operator(_), .. }));
+                let length = characters[index..].iter()
+                                                .take_while(|c| !ends_word(**c, after_operator))
+                                                .count();
+                if length == 0 {
+                    return Err(QueryParseError {
+                        column,
+                        message: format!("unexpected \"{}\"", character),
+                    });
+                }
+                (TokenKind::Word(characters[index..index + length].iter().collect()), length)
+            }
+        };
+
+        tokens.push(Token { kind, column });
+        index += length;
+    }
+
+    Ok(tokens)
+}
+
+fn ends_word(character: char, after_operator: bool) -> bool {
+    character.is_whitespace()
+        || matches!(character, '(' | ')' | '"')
+        || (!after_operator && matches!(character, ':' | '=' | '!' | '<' | '>'))
+}
+
+/// Reads a quoted string starting at its opening quote, returning its text and how many
+/// characters it took up. `\"` and `\\` stand for a quote and a backslash.
+fn quoted(characters: &[char]) -> Option<(String, usize)> {
+    let mut text = String::new();
+    let mut escaped = false;
+
+    for (index, character) in characters.iter().enumerate().skip(1) {
+        match character {
+            _ if escaped => {
+                text.push(*character);
+                escaped = false;
+            }
+            '\\' => escaped = true,
+            '"' => return Some((text, index + 1)),
+            _ => text.push(*character),
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{tokenise, Operator, TokenKind};
+
+    fn kinds(input: &str) -> Vec<TokenKind> {
+        tokenise(input).unwrap().into_iter().map(|token| token.kind).collect()
+    }
+
+    #[test]
+    fn splits_conditions_and_brackets() {
+        assert_eq!(kinds("(tag:ops or priority>=high)"), vec![
+            TokenKind::OpenParen,
+            TokenKind::Word("tag".into()),
+            TokenKind::Operator(Operator::Matches),
+            TokenKind::Word("ops".into()),
+            TokenKind::Word("or".into()),
+            TokenKind::Word("priority".into()),
+            TokenKind::Operator(Operator::GreaterOrEqual),
+            TokenKind::Word("high".into()),
+            TokenKind::CloseParen,
+        ]);
+    }
+
+    #[test]
+    fn values_keep_colons_and_quotes_keep_spaces() {
+        assert_eq!(kinds(r#"due<2026-11-01T17:00 name="say \"hi\"""#), vec![
+            TokenKind::Word("due".into()),
+            TokenKind::Operator(Operator::Less),
+            TokenKind::Word("2026-11-01T17:00".into()),
+            TokenKind::Word("name".into()),
+            TokenKind::Operator(Operator::Equals),
+            TokenKind::Quoted("say \"hi\"".into()),
+        ]);
+    }
+
+    #[test]
+    fn errors_point_at_the_problem() {
+        let error = tokenise("tag:ops and \"open").unwrap_err();
+        assert_eq!(error.column, 13);
+        assert!(tokenise("status:open !done").is_err());
+    }
+}
@@ -0,0 +1,10 @@
+mod lexer;
+
+mod parser;
+pub use parser::parse_query;
+
+#[allow(clippy::module_inception)]
+mod query;
+pub use query::Query;
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+pub use query::Condition;
@@ -0,0 +1,331 @@
+use crate::dates::parse_date_expression;
+use crate::error::QueryParseError;
+use crate::query::lexer::{tokenise, Operator, Token, TokenKind};
+use crate::query::query::{Comparison, Condition, DateField, Query, TextField};
+use crate::tasks::{normalise_tag, Priority, Status};
+use chrono::{DateTime, Days, TimeZone, Timelike, Utc};
+
+const FIELDS: &str = "status, priority, due, scheduled, created, updated, completed, tag, project, name, description, id or parent";
+
+/// Parses a query, working out dates relative to `now` in the time zone of `now`.
+///
+/// ```text
+/// query     = or
+/// or        = and ("or" and)*
+/// and       = not (["and"] not)*
+/// not       = "not" not | primary
+/// primary   = "(" query ")" | field operator value | text
+/// ```
+///
+/// Text on its own matches task names, and conditions next to each other must all hold.
+pub fn parse_query<Tz: TimeZone>(input: &str, now: DateTime<Tz>) -> Result<Query, QueryParseError> {
+    let tokens = tokenise(input)?;
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+        end: input.chars().count() + 1,
+        now,
+    };
+
+    if parser.tokens.is_empty() {
+        return Err(parser.error_at(1, "the query is empty"));
+    }
+
+    let query = parser.or()?;
+    match parser.peek() {
+        None => Ok(query),
+        Some(token) => Err(parser.error_at(token.column, "expected \"and\", \"or\" or the end of the query")),
+    }
+}
+
+struct Parser<Tz: TimeZone> {
+    tokens: Vec<Token>,
+    position: usize,
+    /// The column just past the end of the input, for errors about missing tokens.
+    end: usize,
+    now: DateTime<Tz>,
+}
+
+impl<Tz: TimeZone> Parser<Tz> {
+    fn or(&mut self) -> Result<Query, QueryParseError> {
+        let mut query = self.and()?;
+        while self.next_is_keyword("or") {
+            self.position += 1;
+            query = Query::or(query, self.and()?);
+        }
+        Ok(query)
+    }
+
+    fn and(&mut self) -> Result<Query, QueryParseError> {
+        let mut query = self.not()?;
+        loop {
+            if self.next_is_keyword("and") {
+                self.position += 1;
+            } else if !self.next_starts_a_term() {
+                return Ok(query);
+            }
+            query = Query::and(query, self.not()?);
+        }
+    }
+
+    fn not(&mut self) -> Result<Query, QueryParseError> {
+        if self.next_is_keyword("not") {
+            self.position += 1;
+            return Ok(Query::not(self.not()?));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Query, QueryParseError> {
+        let Some(token) = self.next() else {
+            return Err(self.error_at(self.end, "expected a condition"));
+        };
+
+        match token.kind {
+            TokenKind::OpenParen => {
+                let query = self.or()?;
+                match self.next() {
+                    Some(Token { kind: TokenKind::CloseParen, .. }) => Ok(query),
+                    _ => Err(self.error_at(token.column, "this bracket is never closed")),
+                }
+            }
+            TokenKind::Word(word) if is_keyword(&word) => {
+                Err(self.error_at(token.column, &format!("expected a condition before \"{}\"", word)))
+            }
+            TokenKind::Word(word) | TokenKind::Quoted(word) => match self.peek() {
+                Some(Token { kind: TokenKind::Operator(operator), .. }) => {
+                    let operator = *operator;
+                    self.position += 1;
+                    self.condition(&word, token.column, operator)
+                }
+                _ => Ok(Query::Condition(Condition::Contains(TextField::Name, word.to_lowercase()))),
+            },
+            TokenKind::CloseParen => Err(self.error_at(token.column, "this bracket was never opened")),
+            TokenKind::Operator(_) => Err(self.error_at(token.column, "expected a field name before the operator")),
+        }
+    }
+
+    fn condition(&mut self, field: &str, field_column: usize, operator: Operator) -> Result<Query, QueryParseError> {
+        let (value, column) = match self.next() {
+            Some(Token { kind: TokenKind::Word(value) | TokenKind::Quoted(value), column }) => (value, column),
+            Some(token) => return Err(self.error_at(token.column, &format!("expected a value for {}", field))),
+            None => return Err(self.error_at(self.end, &format!("expected a value for {}", field))),
+        };
+        let fail = |message: String| QueryParseError { column, message };
+
+        // `!=` is always the opposite of `=`, so a task without the field matches it.
+        let (negate, operator) = match operator {
+            Operator::NotEquals => (true, Operator::Equals),
+            operator => (false, operator),
+        };
+
+        let query = match field.to_lowercase().as_str() {
+            "status" => {
+                let statuses = match value.to_lowercase().as_str() {
+                    "open" => Status::ALL.into_iter().filter(Status::is_open).collect(),
+                    "closed" => Status::ALL.into_iter().filter(|status| !status.is_open()).collect(),
+                    _ => vec![value.parse::<Status>().map_err(|e| fail(e.to_string()))?],
+                };
+                self.equality(field, operator, column)?;
+                Query::Condition(Condition::Status(statuses))
+            }
+            "priority" => {
+                let priority = value.parse::<Priority>().map_err(|e| fail(e.to_string()))?;
+                Query::Condition(Condition::Priority(comparison(operator), priority))
+            }
+            "due" => self.date(DateField::Due, operator, &value, column)?,
+            "scheduled" => self.date(DateField::Scheduled, operator, &value, column)?,
+            "created" => self.date(DateField::Created, operator, &value, column)?,
+            "updated" => self.date(DateField::Updated, operator, &value, column)?,
+            "completed" => self.date(DateField::Completed, operator, &value, column)?,
+            "tag" => {
+                self.equality(field, operator, column)?;
+                if value.eq_ignore_ascii_case("none") {
+                    Query::Condition(Condition::NoTags)
+                } else {
+                    Query::Condition(Condition::Tag(normalise_tag(&value).map_err(|e| fail(e.to_string()))?))
+                }
+            }
+            "project" => {
+                self.equality(field, operator, column)?;
+                let name = Some(value).filter(|name| !name.eq_ignore_ascii_case("none"));
+                Query::Condition(Condition::Project(name))
+            }
+            "name" | "description" => {
+                let text_field = if field.eq_ignore_ascii_case("name") { TextField::Name } else { TextField::Description };
+                match operator {
+                    Operator::Matches => Query::Condition(Condition::Contains(text_field, value.to_lowercase())),
+                    Operator::Equals => Query::Condition(Condition::Is(text_field, value.to_lowercase())),
+                    _ => return Err(self.error_at(column, &format!("{} can only be compared with \":\", \"=\" or \"!=\"", field))),
+                }
+            }
+            "id" => {
+                let id = value.parse().map_err(|_| fail(format!("\"{}\" is not a task number", value)))?;
+                Query::Condition(Condition::Id(comparison(operator), id))
+            }
+            "parent" => {
+                self.equality(field, operator, column)?;
+                let parent = if value.eq_ignore_ascii_case("none") {
+                    None
+                } else {
+                    Some(value.parse().map_err(|_| fail(format!("\"{}\" is not a task number", value)))?)
+                };
+                Query::Condition(Condition::Parent(parent))
+            }
+            _ => return Err(self.error_at(field_column, &format!("unknown field \"{}\", expected {}", field, FIELDS))),
+        };
+
+        Ok(if negate { Query::not(query) } else { query })
+    }
+
+    /// Dates without a time of day stand for the whole day, so `due:friday` is any time on
+    /// Friday and `due>friday` starts on Saturday.
+    fn date(&self, field: DateField, operator: Operator, value: &str, column: usize) -> Result<Query, QueryParseError> {
+        if value.eq_ignore_ascii_case("none") {
+            self.equality("a date", operator, column)?;
+            return Ok(Query::Condition(Condition::Missing(field)));
+        }
+
+        let start = parse_date_expression(value, self.now.clone()).map_err(|e| QueryParseError {
+            column,
+            message: e.to_string(),
+        })?;
+        let whole_day = start.hour() == 0 && start.minute() == 0 && start.second() == 0;
+        let end = if whole_day { start.clone().checked_add_days(Days::new(1)) } else { None };
+        let start = start.with_timezone(&Utc);
+        let condition = |comparison, date| Query::Condition(Condition::Date(field, comparison, date));
+
+        Ok(match (operator, end.map(|end| end.with_timezone(&Utc))) {
+            (Operator::Matches | Operator::Equals, Some(end)) => {
+                Query::and(condition(Comparison::GreaterOrEqual, start), condition(Comparison::Less, end))
+            }
+            (Operator::LessOrEqual, Some(end)) => condition(Comparison::Less, end),
+            (Operator::Greater, Some(end)) => condition(Comparison::GreaterOrEqual, end),
+            (operator, _) => condition(comparison(operator), start),
+        })
+    }
+
+    /// Fields that aren't ordered only take `:`, `=` and `!=`.
+    fn equality(&self, field: &str, operator: Operator, column: usize) -> Result<(), QueryParseError> {
+        match operator {
+            Operator::Matches | Operator::Equals => Ok(()),
+            _ => Err(self.error_at(column, &format!("{} can only be compared with \":\", \"=\" or \"!=\"", field))),
+        }
+    }
+
+    fn next_is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token { kind: TokenKind::Word(word), .. }) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn next_starts_a_term(&self) -> bool {
+        match self.peek().map(|token| &token.kind) {
+            Some(TokenKind::OpenParen | TokenKind::Quoted(_)) => true,
+            Some(TokenKind::Word(word)) => !word.eq_ignore_ascii_case("or"),
+            _ => false,
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn error_at(&self, column: usize, message: &str) -> QueryParseError {
+        QueryParseError {
+            column,
+            message: message.to_string(),
+        }
+    }
+}
+
+fn is_keyword(word: &str) -> bool {
+    ["and", "or", "not"].iter().any(|keyword| word.eq_ignore_ascii_case(keyword))
+}
+
+fn comparison(operator: Operator) -> Comparison {
+    match operator {
+        Operator::Matches | Operator::Equals | Operator::NotEquals => Comparison::Equal,
+        Operator::Less => Comparison::Less,
+        Operator::LessOrEqual => Comparison::LessOrEqual,
+        Operator::Greater => Comparison::Greater,
+        Operator::GreaterOrEqual => Comparison::GreaterOrEqual,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_query;
+    use crate::query::query::{Comparison, Condition, DateField, Query, TextField};
+    use crate::tasks::{Priority, Status};
+    use chrono::{FixedOffset, TimeZone, Utc};
+
+    fn parse(input: &str) -> Query {
+        // A Sunday, two hours ahead of UTC.
+        let now = FixedOffset::east_opt(2 * 3600).unwrap().with_ymd_and_hms(2026, 10, 18, 12, 0, 0).unwrap();
+        parse_query(input, now).unwrap()
+    }
+
+    fn condition(condition: Condition) -> Query {
+        Query::Condition(condition)
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let expected = Query::or(
+            condition(Condition::Tag("ops".into())),
+            Query::and(
+                condition(Condition::Priority(Comparison::GreaterOrEqual, Priority::High)),
+                Query::not(condition(Condition::Project(Some("house".into())))),
+            ),
+        );
+        assert_eq!(parse("tag:ops or priority>=high and project!=house"), expected);
+        assert_eq!(parse("tag:ops OR (priority>=high not project:house)"), expected);
+    }
+
+    #[test]
+    fn dates_without_a_time_cover_the_whole_day() {
+        let friday = Utc.with_ymd_and_hms(2026, 10, 22, 22, 0, 0).unwrap();
+        let saturday = Utc.with_ymd_and_hms(2026, 10, 23, 22, 0, 0).unwrap();
+
+        assert_eq!(parse("due:friday"), Query::and(
+            condition(Condition::Date(DateField::Due, Comparison::GreaterOrEqual, friday)),
+            condition(Condition::Date(DateField::Due, Comparison::Less, saturday)),
+        ));
+        assert_eq!(parse("due<friday"), condition(Condition::Date(DateField::Due, Comparison::Less, friday)));
+        assert_eq!(parse("due<=friday"), condition(Condition::Date(DateField::Due, Comparison::Less, saturday)));
+        assert_eq!(parse("due>friday"), condition(Condition::Date(DateField::Due, Comparison::GreaterOrEqual, saturday)));
+        assert_eq!(
+            parse("due<\"friday 17:00\""),
+            condition(Condition::Date(DateField::Due, Comparison::Less, Utc.with_ymd_and_hms(2026, 10, 23, 15, 0, 0).unwrap())),
+        );
+        assert_eq!(parse("completed!=none"), Query::not(condition(Condition::Missing(DateField::Completed))));
+    }
+
+    #[test]
+    fn bare_words_search_names() {
+        assert_eq!(parse("Report status:open"), Query::and(
+            condition(Condition::Contains(TextField::Name, "report".into())),
+            condition(Condition::Status(vec![Status::Todo, Status::InProgress, Status::Waiting, Status::Blocked])),
+        ));
+    }
+
+    #[test]
+    fn mistakes_are_reported_with_their_column() {
+        let now = Utc::now();
+        let error = |input: &str| parse_query(input, now).unwrap_err();
+
+        assert_eq!(error("colour:red").column, 1);
+        assert_eq!(error("status:open and").column, 16);
+        assert_eq!(error("(tag:ops or tag:dev").column, 1);
+        assert_eq!(error("tag:ops)").column, 8);
+        assert_eq!(error("status<done").column, 8);
+        assert_eq!(error("due:someday").column, 5);
+        assert!(error("priority:extreme").message.contains("Unknown priority"));
+        assert_eq!(error("").column, 1);
+    }
+}
@@ -0,0 +1,173 @@
+use crate::projects::Project;
+use crate::tasks::{Priority, Status, Task};
+use chrono::{DateTime, Utc};
+use std::cmp::Ordering;
+
+/// How an ordered field compares with a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Equal,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    pub fn holds(&self, ordering: Ordering) -> bool {
+        match self {
+            Comparison::Equal => ordering.is_eq(),
+            Comparison::Less => ordering.is_lt(),
+            Comparison::LessOrEqual => ordering.is_le(),
+            Comparison::Greater => ordering.is_gt(),
+            Comparison::GreaterOrEqual => ordering.is_ge(),
+        }
+    }
+
+    #[cfg(any(feature = "postgres", feature = "sqlite"))]
+    pub fn sql(&self) -> &'static str {
+        match self {
+            Comparison::Equal => "=",
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">=",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateField {
+    Due,
+    Scheduled,
+    Created,
+    Updated,
+    Completed,
+}
+
+impl DateField {
+    pub fn value(&self, task: &Task) -> Option<DateTime<Utc>> {
+        match self {
+            DateField::Due => task.due,
+            DateField::Scheduled => task.scheduled,
+            DateField::Created => Some(task.created_at),
+            DateField::Updated => Some(task.updated_at),
+            DateField::Completed => task.completed_at,
+        }
+    }
+
+    #[cfg(any(feature = "postgres", feature = "sqlite"))]
+    pub fn column(&self) -> &'static str {
+        match self {
+            DateField::Due => "tasks.due",
+            DateField::Scheduled => "tasks.scheduled",
+            DateField::Created => "tasks.created_at",
+            DateField::Updated => "tasks.updated_at",
+            DateField::Completed => "tasks.completed_at",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextField {
+    Name,
+    Description,
+}
+
+impl TextField {
+    pub fn value<'a>(&self, task: &'a Task) -> Option<&'a str> {
+        match self {
+            TextField::Name => Some(&task.name),
+            TextField::Description => task.description(),
+        }
+    }
+
+    #[cfg(any(feature = "postgres", feature = "sqlite"))]
+    pub fn column(&self) -> &'static str {
+        match self {
+            TextField::Name => "tasks.name",
+            TextField::Description => "tasks.description",
+        }
+    }
+}
+
+/// A single test of one field of a task. Tests of a field a task doesn't have, such as
+/// a due date, are false; only `Missing` and `NoTags` match those tasks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Condition {
+    /// The task has one of these statuses.
+    Status(Vec<Status>),
+    Priority(Comparison, Priority),
+    Date(DateField, Comparison, DateTime<Utc>),
+    /// The task has no date in this field.
+    Missing(DateField),
+    Tag(String),
+    NoTags,
+    /// The task is in the project with this name, or in no project.
+    Project(Option<String>),
+    /// The field contains the text, ignoring case. The text is kept in lowercase.
+    Contains(TextField, String),
+    /// The field is the text, ignoring case. The text is kept in lowercase.
+    Is(TextField, String),
+    Id(Comparison, i32),
+    /// The task is a subtask of this task, or a top level task.
+    Parent(Option<i32>),
+}
+
+impl Condition {
+    fn matches(&self, task: &Task, projects: &[Project]) -> bool {
+        match self {
+            Condition::Status(statuses) => statuses.contains(&task.status),
+            Condition::Priority(comparison, priority) => comparison.holds(task.priority.cmp(priority)),
+            Condition::Date(field, comparison, date) => field.value(task).is_some_and(|value| comparison.holds(value.cmp(date))),
+            Condition::Missing(field) => field.value(task).is_none(),
+            Condition::Tag(tag) => task.has_tag(tag),
+            Condition::NoTags => task.tags.is_empty(),
+            Condition::Project(None) => task.project_id.is_none(),
+            Condition::Project(Some(name)) => task.project_id.is_some_and(|id| {
+                projects.iter().any(|project| project.id == id && project.name == *name)
+            }),
+            Condition::Contains(field, text) => field.value(task).is_some_and(|value| value.to_lowercase().contains(text.as_str())),
+            Condition::Is(field, text) => field.value(task).is_some_and(|value| value.to_lowercase() == *text),
+            Condition::Id(comparison, id) => comparison.holds(task.id.cmp(id)),
+            Condition::Parent(parent_id) => task.parent_id == *parent_id,
+        }
+    }
+}
+
+/// A parsed `todo list` query, such as `status:open and (tag:ops or priority>=high)`.
+///
+/// Dates in the query are worked out when it is parsed, so every backend compares
+/// against the same instants.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Query {
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+    Not(Box<Query>),
+    Condition(Condition),
+}
+
+impl Query {
+    /// Whether `task` matches. `projects` is used to look up project names.
+    pub fn matches(&self, task: &Task, projects: &[Project]) -> bool {
+        match self {
+            Query::And(left, right) => left.matches(task, projects) && right.matches(task, projects),
+            Query::Or(left, right) => left.matches(task, projects) || right.matches(task, projects),
+            Query::Not(query) => !query.matches(task, projects),
+            Query::Condition(condition) => condition.matches(task, projects),
+        }
+    }
+
+    pub fn and(left: Query, right: Query) -> Query {
+        Query::And(Box::new(left), Box::new(right))
+    }
+
+    pub fn or(left: Query, right: Query) -> Query {
+        Query::Or(Box::new(left), Box::new(right))
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(query: Query) -> Query {
+        Query::Not(Box::new(query))
+    }
+}
@@ -0,0 +1,8 @@
+mod search_hit;
+pub use search_hit::{SearchHit, HIGHLIGHT_END, HIGHLIGHT_START};
+
+mod search_index;
+pub use search_index::SearchIndex;
+
+mod search_terms;
+pub use search_terms::SearchTerms;
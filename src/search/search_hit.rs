@@ -0,0 +1,65 @@
+use crate::tasks::Task;
+
+/// Marks the start of a matching word in a snippet. Control characters are used so the
+/// backends can mark matches without clashing with anything a task is likely to contain.
+pub const HIGHLIGHT_START: char = '\u{2}';
+
+/// Marks the end of a matching word in a snippet.
+pub const HIGHLIGHT_END: char = '\u{3}';
+
+/// A task found by `todo search`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    pub task: Task,
+    /// How well the task matches; higher is better. Scores are only comparable between hits
+    /// from the same search on the same backend.
+    pub score: f64,
+    /// The part of the name or description that matched, with each matching word between
+    /// `HIGHLIGHT_START` and `HIGHLIGHT_END`.
+    pub snippet: String,
+}
+
+impl SearchHit {
+    /// The snippet on one line, with each matching word wrapped in `start` and `end`.
+    pub fn highlight(&self, start: &str, end: &str) -> String {
+        self.snippet.split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ")
+                    .replace(HIGHLIGHT_START, start)
+                    .replace(HIGHLIGHT_END, end)
+    }
+
+    /// The snippet without any highlighting.
+    pub fn plain_snippet(&self) -> String {
+        self.highlight("", "")
+    }
+
+    /// The matching words in the snippet, in the order they appear.
+    pub fn matches(&self) -> Vec<String> {
+        self.snippet.split(HIGHLIGHT_START)
+                    .skip(1)
+                    .filter_map(|part| part.split_once(HIGHLIGHT_END))
+                    .map(|(word, _)| word.to_string())
+                    .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SearchHit;
+    use crate::tasks::{NewTask, Task};
+    use chrono::Utc;
+
+    #[test]
+    fn snippets_can_be_highlighted_or_plain() {
+        let hit = SearchHit {
+            task: Task::from_new_task(1, NewTask::default(), Utc::now()),
+            score: 1.0,
+            snippet: "Renew the \u{2}TLS\u{3}\n\u{2}certificate\u{3}".into(),
+        };
+
+        assert_eq!(hit.highlight("[", "]"), "Renew the [TLS] [certificate]");
+        assert_eq!(hit.plain_snippet(), "Renew the TLS certificate");
+        assert_eq!(hit.matches(), ["TLS", "certificate"]);
+    }
+}
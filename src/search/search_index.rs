@@ -0,0 +1,202 @@
+use crate::search::search_terms::word_spans;
+use crate::search::{SearchHit, SearchTerms, HIGHLIGHT_END, HIGHLIGHT_START};
+use crate::tasks::Task;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::Bound;
+
+// The usual BM25 constants, which are also what SQLite uses.
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+
+/// How much a match counts in each field, name first, the same as the SQL backends.
+const FIELD_WEIGHTS: [f64; 2] = [10.0, 1.0];
+
+/// The most words a snippet shows.
+const SNIPPET_WORDS: usize = 12;
+
+/// Where a word appears: which task, which field and which word of the field.
+#[derive(Debug, Clone, Copy)]
+struct Posting {
+    task: usize,
+    field: usize,
+    position: usize,
+}
+
+/// An inverted index over the names and descriptions of a set of tasks, for backends that
+/// have no search of their own. Results are ranked with BM25 the same way as SQLite's FTS5.
+pub struct SearchIndex<'a> {
+    tasks: &'a [Task],
+    /// Every lowercased word, kept in order so the words starting with a prefix sit together.
+    words: BTreeMap<String, Vec<Posting>>,
+    /// How many words are in the name and description of each task together.
+    lengths: Vec<usize>,
+}
+
+impl<'a> SearchIndex<'a> {
+    pub fn new(tasks: &'a [Task]) -> Self {
+        let mut words = BTreeMap::<String, Vec<Posting>>::new();
+        let mut lengths = Vec::with_capacity(tasks.len());
+
+        for (task_index, task) in tasks.iter().enumerate() {
+            let mut length = 0;
+            for (field, text) in fields(task).into_iter().enumerate() {
+                let spans = word_spans(text);
+                length += spans.len();
+                for (position, span) in spans.into_iter().enumerate() {
+                    words.entry(text[span].to_lowercase()).or_default().push(Posting {
+                        task: task_index,
+                        field,
+                        position,
+                    });
+                }
+            }
+            lengths.push(length);
+        }
+
+        Self { tasks, words, lengths }
+    }
+
+    /// Returns up to `limit` tasks containing every term, best match first.
+    pub fn search(&self, terms: &SearchTerms, limit: usize) -> Vec<SearchHit> {
+        let task_count = self.tasks.len() as f64;
+        let average_length = self.lengths.iter().sum::<usize>() as f64 / task_count.max(1.0);
+
+        let mut scores = HashMap::<usize, f64>::new();
+        let mut terms_found = HashMap::<usize, usize>::new();
+        let mut matched_positions = HashMap::<(usize, usize), HashSet<usize>>::new();
+
+        for word in terms.words() {
+            // As in FTS5, matches in each field are weighted and added up before scoring.
+            let mut frequencies = HashMap::<usize, f64>::new();
+            for posting in self.postings(word) {
+                *frequencies.entry(posting.task).or_default() += FIELD_WEIGHTS[posting.field];
+                matched_positions.entry((posting.task, posting.field)).or_default().insert(posting.position);
+            }
+
+            let matching = frequencies.len() as f64;
+            let idf = ((task_count - matching + 0.5) / (matching + 0.5)).ln().max(1e-6);
+
+            for (task, frequency) in frequencies {
+                let length = self.lengths[task] as f64;
+                let saturation = frequency + K1 * (1.0 - B + B * length / average_length);
+                *scores.entry(task).or_default() += idf * frequency * (K1 + 1.0) / saturation;
+                *terms_found.entry(task).or_default() += 1;
+            }
+        }
+
+        let mut hits: Vec<SearchHit> = terms_found.into_iter()
+                                                  .filter(|(_, found)| *found == terms.words().len())
+                                                  .map(|(task, _)| SearchHit {
+                                                      task: self.tasks[task].clone(),
+                                                      score: scores[&task],
+                                                      snippet: self.snippet(task, &matched_positions),
+                                                  })
+                                                  .collect();
+
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.task.id.cmp(&b.task.id)));
+        hits.truncate(limit);
+        hits
+    }
+
+    /// Every posting of every word starting with `prefix`.
+    fn postings(&self, prefix: &str) -> impl Iterator<Item = &Posting> {
+        self.words.range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+                  .take_while(move |(word, _)| word.starts_with(prefix))
+                  .flat_map(|(_, postings)| postings.iter())
+    }
+
+    /// Picks the field with the most matching words and shows up to `SNIPPET_WORDS` words of
+    /// it, starting a little before the first match.
+    fn snippet(&self, task: usize, matched_positions: &HashMap<(usize, usize), HashSet<usize>>) -> String {
+        let matches_in = |field: usize| matched_positions.get(&(task, field)).map_or(0, HashSet::len);
+        let field = if matches_in(1) > matches_in(0) { 1 } else { 0 };
+        let text = fields(&self.tasks[task])[field];
+        let spans = word_spans(text);
+        let empty = HashSet::new();
+        let matched = matched_positions.get(&(task, field)).unwrap_or(&empty);
+
+        let first_match = matched.iter().copied().min().unwrap_or(0);
+        let start = first_match.saturating_sub(SNIPPET_WORDS / 4)
+                               .min(spans.len().saturating_sub(SNIPPET_WORDS));
+        let end = (start + SNIPPET_WORDS).min(spans.len());
+
+        let mut snippet = String::new();
+        let mut cursor = 0;
+        if start > 0 {
+            snippet.push('…');
+            cursor = spans[start].start;
+        }
+
+        for (position, span) in spans.iter().enumerate().take(end).skip(start) {
+            snippet.push_str(&text[cursor..span.start]);
+            if matched.contains(&position) {
+                snippet.push(HIGHLIGHT_START);
+                snippet.push_str(&text[span.clone()]);
+                snippet.push(HIGHLIGHT_END);
+            } else {
+                snippet.push_str(&text[span.clone()]);
+            }
+            cursor = span.end;
+        }
+
+        if end < spans.len() {
+            snippet.push('…');
+        } else {
+            snippet.push_str(&text[cursor..]);
+        }
+        snippet
+    }
+}
+
+/// The text of a task that is searched, name first.
+fn fields(task: &Task) -> [&str; 2] {
+    [&task.name, task.description().unwrap_or_default()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SearchIndex;
+    use crate::search::SearchTerms;
+    use crate::tasks::{NewTask, Task};
+    use chrono::Utc;
+
+    fn task(id: i32, name: &str, description: Option<&str>) -> Task {
+        let new_task = NewTask {
+            name: name.into(),
+            description: description.map(String::from),
+            ..NewTask::default()
+        };
+        Task::from_new_task(id, new_task, Utc::now())
+    }
+
+    fn tasks() -> Vec<Task> {
+        vec![
+            task(1, "Buy milk", None),
+            task(2, "Check the load balancer", Some("The TLS certificate on the load balancer runs out in March, renew it with the new certificate authority before then")),
+            task(3, "Renew the TLS cert", None),
+            task(4, "Read about TLS", Some("Mostly certificate transparency")),
+        ]
+    }
+
+    #[test]
+    fn every_term_has_to_match_as_a_prefix() {
+        let tasks = tasks();
+        let hits = SearchIndex::new(&tasks).search(&SearchTerms::parse("tls cert").unwrap(), 10);
+        let ids: Vec<i32> = hits.iter().map(|hit| hit.task.id).collect();
+
+        assert_eq!(ids, [3, 4, 2]);
+        assert!(SearchIndex::new(&tasks).search(&SearchTerms::parse("milk tls").unwrap(), 10).is_empty());
+    }
+
+    #[test]
+    fn snippets_come_from_the_field_with_the_most_matches() {
+        let tasks = tasks();
+        let hits = SearchIndex::new(&tasks).search(&SearchTerms::parse("certificate").unwrap(), 10);
+        let snippets: Vec<String> = hits.iter().map(|hit| hit.highlight("[", "]")).collect();
+
+        assert_eq!(snippets, [
+            "Mostly [certificate] transparency",
+            "The TLS [certificate] on the load balancer runs out in March, renew…",
+        ]);
+    }
+}
@@ -0,0 +1,91 @@
+use anyhow::{bail, Result};
+use std::ops::Range;
+
+/// The words of a `todo search`, lowercased. A task matches when every word starts one of
+/// the words in its name or description, so `tls cert` finds "Renew the TLS certificate".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchTerms {
+    words: Vec<String>,
+}
+
+impl SearchTerms {
+    /// Splits `input` into words the way the backends split task text, on anything that is
+    /// not a letter or digit. Fails if there are no words at all.
+    pub fn parse(input: &str) -> Result<Self> {
+        let mut words: Vec<String> = vec![];
+        for span in word_spans(input) {
+            let word = input[span].to_lowercase();
+            if !words.contains(&word) {
+                words.push(word);
+            }
+        }
+
+        if words.is_empty() {
+            bail!("Nothing to search for in \"{}\"", input);
+        }
+
+        Ok(Self { words })
+    }
+
+    pub fn words(&self) -> &[String] {
+        &self.words
+    }
+
+    /// The terms as an SQLite FTS5 query of prefix phrases, such as `"tls"* "cert"*`.
+    #[cfg(any(feature = "sqlite", test))]
+    pub fn to_fts5(&self) -> String {
+        self.words.iter()
+                  .map(|word| format!("\"{}\"*", word))
+                  .collect::<Vec<_>>()
+                  .join(" ")
+    }
+
+    /// The terms as a Postgres `tsquery` of prefixes, such as `tls:* & cert:*`.
+    #[cfg(any(feature = "postgres", test))]
+    pub fn to_tsquery(&self) -> String {
+        self.words.iter()
+                  .map(|word| format!("{}:*", word))
+                  .collect::<Vec<_>>()
+                  .join(" & ")
+    }
+}
+
+/// Where each word of `text` starts and ends, in bytes.
+pub fn word_spans(text: &str) -> Vec<Range<usize>> {
+    let mut spans = vec![];
+    let mut start = None;
+
+    for (index, character) in text.char_indices() {
+        match (character.is_alphanumeric(), start) {
+            (true, None) => start = Some(index),
+            (false, Some(word_start)) => {
+                spans.push(word_start..index);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(word_start) = start {
+        spans.push(word_start..text.len());
+    }
+
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SearchTerms;
+
+    #[test]
+    fn words_are_split_lowercased_and_deduplicated() {
+        let terms = SearchTerms::parse("TLS cert, tls-Cert!").unwrap();
+        assert_eq!(terms.words(), ["tls", "cert"]);
+        assert_eq!(terms.to_fts5(), "\"tls\"* \"cert\"*");
+        assert_eq!(terms.to_tsquery(), "tls:* & cert:*");
+    }
+
+    #[test]
+    fn punctuation_alone_is_not_a_search() {
+        assert!(SearchTerms::parse(" -- ").is_err());
+    }
+}
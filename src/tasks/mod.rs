@@ -3,3 +3,36 @@ pub use task::Task;
 
 mod new_task;
 pub use new_task::NewTask;
+
+mod task_details;
+pub use task_details::{TaskDetails, TaskSummary};
+
+mod task_edit;
+pub use task_edit::TaskEdit;
+
+mod task_filter;
+pub use task_filter::TaskFilter;
+
+mod priority;
+pub use priority::Priority;
+
+mod task_sort;
+pub use task_sort::TaskSort;
+
+mod tag;
+pub use tag::{normalise_tag, TagSelector};
+
+mod task_tree;
+pub use task_tree::{descendants, flatten_tree, subtask_progress, would_create_cycle};
+
+mod task_dependencies;
+pub use task_dependencies::{blocked_tasks, find_dependency_cycle, open_blockers};
+
+mod recurrence;
+pub use recurrence::Recurrence;
+
+mod status;
+pub use status::Status;
+
+mod status_transitions;
+pub use status_transitions::StatusTransitions;
@@ -1,5 +1,15 @@
-#[derive(Debug, Hash, Clone)]
+use crate::tasks::{Priority, Recurrence};
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Hash, Clone, Default)]
 pub struct NewTask {
     pub name: String,
     pub description: Option<String>,
+    pub due: Option<DateTime<Utc>>,
+    pub scheduled: Option<DateTime<Utc>>,
+    pub priority: Priority,
+    pub project_id: Option<i32>,
+    pub parent_id: Option<i32>,
+    pub recurrence: Option<Recurrence>,
+    pub tags: Vec<String>,
 }
@@ -0,0 +1,45 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// How much a task matters, from `None` up to `Urgent`. Stored as 0 to 4 in the databases.
+#[derive(Debug, Default, Hash, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[repr(i32)]
+pub enum Priority {
+    #[default]
+    None = 0,
+    Low = 1,
+    Medium = 2,
+    High = 3,
+    Urgent = 4,
+}
+
+impl FromStr for Priority {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "none" | "0" => Ok(Priority::None),
+            "low" | "l" | "1" => Ok(Priority::Low),
+            "medium" | "m" | "2" => Ok(Priority::Medium),
+            "high" | "h" | "3" => Ok(Priority::High),
+            "urgent" | "u" | "4" => Ok(Priority::Urgent),
+            _ => Err(anyhow!("Unknown priority \"{}\", expected none, low, medium, high, urgent or 0 to 4", s)),
+        }
+    }
+}
+
+impl Display for Priority {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Priority::None => "none",
+            Priority::Low => "low",
+            Priority::Medium => "medium",
+            Priority::High => "high",
+            Priority::Urgent => "urgent",
+        };
+        f.write_str(name)
+    }
+}
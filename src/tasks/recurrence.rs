@@ -0,0 +1,334 @@
+use crate::dates::parse_weekday;
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Datelike, Days, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Weekday};
+use serde::{Deserialize, Serialize};
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::{Database, Decode, Encode, Type};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// Gives up on catching a late task up with its schedule after this many occurrences.
+const MAX_SKIPPED_OCCURRENCES: usize = 10_000;
+
+#[derive(Debug, Hash, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// How a task repeats once it is completed, such as `weekly`, `every 2 weeks on mon,thu`
+/// or `3 days after completion`.
+///
+/// Stored as that text in task files and databases, so rules stay readable and editable.
+#[derive(Debug, Hash, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Recurrence {
+    pub frequency: Frequency,
+    /// Repeats every this many days, weeks, months or years.
+    pub interval: u32,
+    /// For weekly rules, the days of the week it falls on. Empty means the day it was due.
+    pub weekdays: Vec<Weekday>,
+    /// Counts from when the task was completed instead of from when it was due.
+    pub after_completion: bool,
+}
+
+impl Recurrence {
+    /// Works out when the next occurrence is due, given when this one was due and when it
+    /// was completed. Occurrences that were missed while the task was late are skipped.
+    pub fn next_due<Tz: TimeZone>(&self, due: Option<DateTime<Tz>>, completed_at: DateTime<Tz>) -> Result<DateTime<Tz>> {
+        let zone = completed_at.timezone();
+        let completed = completed_at.naive_local();
+
+        let Some(due) = due.as_ref().filter(|_| !self.after_completion) else {
+            // Keep the time of day the task was due at, or start of day if it had none.
+            let time = due.map(|due| due.naive_local().time()).unwrap_or(NaiveTime::MIN);
+            let next = self.step(completed.date().and_time(time))?;
+            return to_zone(&zone, next);
+        };
+
+        let mut next = self.step(due.naive_local())?;
+        for _ in 0..MAX_SKIPPED_OCCURRENCES {
+            if next > completed {
+                return to_zone(&zone, next);
+            }
+            next = self.step(next)?;
+        }
+
+        bail!("Could not find the next occurrence after {}", completed)
+    }
+
+    /// The occurrence after `from`.
+    fn step(&self, from: NaiveDateTime) -> Result<NaiveDateTime> {
+        let date = from.date();
+        let next = match self.frequency {
+            Frequency::Daily => date.checked_add_days(Days::new(self.interval.into())),
+            Frequency::Weekly if self.weekdays.is_empty() => date.checked_add_days(Days::new(u64::from(self.interval) * 7)),
+            Frequency::Weekly => self.next_weekday(date),
+            Frequency::Monthly => date.checked_add_months(Months::new(self.interval)),
+            Frequency::Yearly => date.checked_add_months(Months::new(self.interval.saturating_mul(12))),
+        };
+
+        next.map(|date| date.and_time(from.time()))
+            .ok_or_else(|| anyhow!("Date is out of range"))
+    }
+
+    /// The next listed weekday after `date`, in a week that is a whole number of intervals away.
+    fn next_weekday(&self, date: NaiveDate) -> Option<NaiveDate> {
+        let week_start = date.week(Weekday::Mon).first_day();
+
+        (1..=u64::from(self.interval) * 7 + 7)
+            .filter_map(|days| date.checked_add_days(Days::new(days)))
+            .find(|candidate| {
+                let weeks_apart = (candidate.week(Weekday::Mon).first_day() - week_start).num_weeks();
+                weeks_apart % i64::from(self.interval) == 0 && self.weekdays.contains(&candidate.weekday())
+            })
+    }
+
+    fn unit(&self) -> &'static str {
+        match self.frequency {
+            Frequency::Daily => "day",
+            Frequency::Weekly => "week",
+            Frequency::Monthly => "month",
+            Frequency::Yearly => "year",
+        }
+    }
+}
+
+fn to_zone<Tz: TimeZone>(zone: &Tz, naive: NaiveDateTime) -> Result<DateTime<Tz>> {
+    // A time skipped by a daylight saving change falls an hour later instead.
+    zone.from_local_datetime(&naive)
+        .earliest()
+        .or_else(|| zone.from_local_datetime(&(naive + chrono::Duration::hours(1))).earliest())
+        .ok_or_else(|| anyhow!("{} does not exist in the local time zone", naive))
+}
+
+fn parse_unit(word: &str) -> Option<Frequency> {
+    match word {
+        "day" | "days" => Some(Frequency::Daily),
+        "week" | "weeks" => Some(Frequency::Weekly),
+        "month" | "months" => Some(Frequency::Monthly),
+        "year" | "years" => Some(Frequency::Yearly),
+        _ => None,
+    }
+}
+
+fn parse_weekdays(words: &[&str]) -> Result<Vec<Weekday>> {
+    let mut weekdays = vec![];
+    for word in words.iter().flat_map(|word| word.split(',')).filter(|word| !word.is_empty() && *word != "and") {
+        weekdays.push(parse_weekday(word).ok_or_else(|| anyhow!("Unknown day of the week \"{}\"", word))?);
+    }
+
+    weekdays.sort_by_key(|weekday| weekday.num_days_from_monday());
+    weekdays.dedup();
+    Ok(weekdays)
+}
+
+impl FromStr for Recurrence {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let lowercase = s.trim().to_lowercase();
+        let words: Vec<&str> = lowercase.split_whitespace().collect();
+
+        let (words, after_completion) = match words.strip_suffix(&["after", "completion"]) {
+            Some(words) => (words, true),
+            None => (words.as_slice(), false),
+        };
+
+        let rule = |frequency, interval| Recurrence {
+            frequency,
+            interval,
+            weekdays: vec![],
+            after_completion,
+        };
+
+        let recurrence = match words {
+            ["daily"] => rule(Frequency::Daily, 1),
+            ["weekly"] => rule(Frequency::Weekly, 1),
+            ["monthly"] => rule(Frequency::Monthly, 1),
+            ["yearly"] | ["annually"] => rule(Frequency::Yearly, 1),
+            ["weekdays"] | ["every", "weekday"] => Recurrence {
+                weekdays: vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri],
+                ..rule(Frequency::Weekly, 1)
+            },
+            ["every", unit] | ["every", unit, "on", ..] if parse_unit(unit).is_some() => Recurrence {
+                weekdays: parse_weekdays(words.get(3..).unwrap_or_default())?,
+                ..rule(parse_unit(unit).unwrap_or(Frequency::Daily), 1)
+            },
+            ["every", count, unit] | ["every", count, unit, "on", ..] | [count, unit]
+                if count.parse::<u32>().is_ok() && parse_unit(unit).is_some() => {
+                let on = words.iter().position(|word| *word == "on").map(|index| index + 1).unwrap_or(words.len());
+                Recurrence {
+                    weekdays: parse_weekdays(&words[on..])?,
+                    ..rule(parse_unit(unit).unwrap_or(Frequency::Daily), count.parse()?)
+                }
+            }
+            ["every", days @ ..] if !days.is_empty() => Recurrence {
+                weekdays: parse_weekdays(days)?,
+                ..rule(Frequency::Weekly, 1)
+            },
+            _ => bail!("Unrecognised repeat rule \"{}\", try \"weekly\", \"every 2 weeks on mon,thu\" or \"3 days after completion\"", s),
+        };
+
+        if recurrence.interval == 0 {
+            bail!("A task cannot repeat every 0 {}s", recurrence.unit());
+        }
+        if !recurrence.weekdays.is_empty() && recurrence.frequency != Frequency::Weekly {
+            bail!("Days of the week can only be given for weekly repeats");
+        }
+        if !recurrence.weekdays.is_empty() && recurrence.after_completion {
+            bail!("Repeats on days of the week cannot count from completion");
+        }
+
+        Ok(recurrence)
+    }
+}
+
+impl Display for Recurrence {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let plural = if self.interval == 1 { "" } else { "s" };
+
+        if self.after_completion {
+            return write!(f, "{} {}{} after completion", self.interval, self.unit(), plural);
+        }
+
+        if !self.weekdays.is_empty() {
+            let weekdays: Vec<_> = self.weekdays.iter().map(|weekday| weekday.to_string().to_lowercase()).collect();
+            return match self.interval {
+                1 => write!(f, "every {}", weekdays.join(",")),
+                interval => write!(f, "every {} weeks on {}", interval, weekdays.join(",")),
+            };
+        }
+
+        match (self.interval, self.frequency) {
+            (1, Frequency::Daily) => f.write_str("daily"),
+            (1, Frequency::Weekly) => f.write_str("weekly"),
+            (1, Frequency::Monthly) => f.write_str("monthly"),
+            (1, Frequency::Yearly) => f.write_str("yearly"),
+            (interval, _) => write!(f, "every {} {}s", interval, self.unit()),
+        }
+    }
+}
+
+impl TryFrom<String> for Recurrence {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        Self::from_str(&value)
+    }
+}
+
+impl From<Recurrence> for String {
+    fn from(recurrence: Recurrence) -> Self {
+        recurrence.to_string()
+    }
+}
+
+// The databases store the rule as text, the same as the task files.
+impl<DB: Database> Type<DB> for Recurrence
+where
+    String: Type<DB>,
+{
+    fn type_info() -> DB::TypeInfo {
+        <String as Type<DB>>::type_info()
+    }
+
+    fn compatible(ty: &DB::TypeInfo) -> bool {
+        <String as Type<DB>>::compatible(ty)
+    }
+}
+
+impl<'r, DB: Database> Decode<'r, DB> for Recurrence
+where
+    String: Decode<'r, DB>,
+{
+    fn decode(value: DB::ValueRef<'r>) -> Result<Self, BoxDynError> {
+        Ok(String::decode(value)?.parse()?)
+    }
+}
+
+impl<'q, DB: Database> Encode<'q, DB> for Recurrence
+where
+    String: Encode<'q, DB>,
+{
+    fn encode_by_ref(&self, buf: &mut DB::ArgumentBuffer<'q>) -> Result<IsNull, BoxDynError> {
+        self.to_string().encode(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Recurrence;
+    use chrono::{DateTime, TimeZone, Utc};
+    use std::str::FromStr;
+
+    fn at(input: &str) -> DateTime<Utc> {
+        Utc.from_utc_datetime(&chrono::NaiveDateTime::parse_from_str(input, "%Y-%m-%d %H:%M").unwrap())
+    }
+
+    #[test]
+    fn rules_round_trip_through_their_text_form() {
+        let cases = [
+            ("daily", "daily"),
+            ("Weekly", "weekly"),
+            ("every month", "monthly"),
+            ("annually", "yearly"),
+            ("every 3 days", "every 3 days"),
+            ("every 1 week", "weekly"),
+            ("every monday, thursday", "every mon,thu"),
+            ("every thu and mon", "every mon,thu"),
+            ("every 2 weeks on fri,mon", "every 2 weeks on mon,fri"),
+            ("weekdays", "every mon,tue,wed,thu,fri"),
+            ("3 days after completion", "3 days after completion"),
+            ("every week after completion", "1 week after completion"),
+        ];
+
+        for (input, expected) in cases {
+            let recurrence = Recurrence::from_str(input).unwrap_or_else(|e| panic!("\"{}\" failed to parse: {}", input, e));
+            assert_eq!(recurrence.to_string(), expected, "for \"{}\"", input);
+            assert_eq!(Recurrence::from_str(expected).unwrap(), recurrence, "for \"{}\"", expected);
+        }
+    }
+
+    #[test]
+    fn nonsense_is_rejected() {
+        let cases = ["", "sometimes", "every 0 days", "every blursday", "every month on monday", "every mon after completion"];
+
+        for input in cases {
+            assert!(Recurrence::from_str(input).is_err(), "\"{}\" should not parse", input);
+        }
+    }
+
+    #[test]
+    fn next_due_follows_the_schedule() {
+        // Due Wednesday 14 October 2026 at 09:00.
+        let due = at("2026-10-14 09:00");
+        let cases = [
+            ("daily", "2026-10-14 10:00", "2026-10-15 09:00"),
+            ("every 2 weeks", "2026-10-14 10:00", "2026-10-28 09:00"),
+            ("every mon,thu", "2026-10-14 10:00", "2026-10-15 09:00"),
+            ("every 2 weeks on mon", "2026-10-14 10:00", "2026-10-26 09:00"),
+            ("monthly", "2026-10-14 10:00", "2026-11-14 09:00"),
+            ("yearly", "2026-10-14 10:00", "2027-10-14 09:00"),
+            // Completed late, so the missed occurrences are skipped.
+            ("daily", "2026-10-20 12:00", "2026-10-21 09:00"),
+            ("weekly", "2026-10-13 12:00", "2026-10-21 09:00"),
+            // Counted from when it was completed.
+            ("3 days after completion", "2026-10-20 12:00", "2026-10-23 09:00"),
+        ];
+
+        for (rule, completed, expected) in cases {
+            let next = Recurrence::from_str(rule).unwrap().next_due(Some(due), at(completed)).unwrap();
+            assert_eq!(next, at(expected), "for \"{}\" completed at {}", rule, completed);
+        }
+    }
+
+    #[test]
+    fn tasks_without_a_due_date_repeat_from_completion() {
+        let next = Recurrence::from_str("weekly").unwrap().next_due(None, at("2026-10-14 16:20")).unwrap();
+        assert_eq!(next, at("2026-10-21 00:00"));
+    }
+}
@@ -0,0 +1,169 @@
+use anyhow::{anyhow, Result};
+use serde::de::{Error, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::{Database, Decode, Encode, Type};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// Where a task is in its workflow. Which statuses can follow which is set by
+/// [`crate::tasks::StatusTransitions`].
+#[derive(Debug, Default, Hash, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Status {
+    #[default]
+    Todo,
+    InProgress,
+    Waiting,
+    Blocked,
+    Done,
+    Cancelled,
+}
+
+impl Status {
+    pub const ALL: [Status; 6] = [
+        Status::Todo,
+        Status::InProgress,
+        Status::Waiting,
+        Status::Blocked,
+        Status::Done,
+        Status::Cancelled,
+    ];
+
+    /// Whether the task still needs doing. Done and cancelled tasks are closed.
+    pub fn is_open(&self) -> bool {
+        !matches!(self, Status::Done | Status::Cancelled)
+    }
+
+    /// The name the status is stored under.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Status::Todo => "todo",
+            Status::InProgress => "in_progress",
+            Status::Waiting => "waiting",
+            Status::Blocked => "blocked",
+            Status::Done => "done",
+            Status::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn glyph(&self) -> char {
+        match self {
+            Status::Todo => '☐',
+            Status::InProgress => '◐',
+            Status::Waiting => '⧗',
+            Status::Blocked => '⊘',
+            Status::Done => '☑',
+            Status::Cancelled => '☒',
+        }
+    }
+}
+
+impl FromStr for Status {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().replace(['-', ' '], "_").as_str() {
+            "todo" | "open" => Ok(Status::Todo),
+            "in_progress" | "started" | "doing" => Ok(Status::InProgress),
+            "waiting" => Ok(Status::Waiting),
+            "blocked" => Ok(Status::Blocked),
+            "done" | "completed" => Ok(Status::Done),
+            "cancelled" | "canceled" => Ok(Status::Cancelled),
+            _ => Err(anyhow!("Unknown status \"{}\", expected todo, in_progress, waiting, blocked, done or cancelled", s)),
+        }
+    }
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.as_str().replace('_', " "))
+    }
+}
+
+impl Serialize for Status {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+// Task files written before statuses existed have `completed: true/false` instead, which
+// `Task` maps onto this field, so booleans are read as done or todo.
+impl<'de> Deserialize<'de> for Status {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        struct StatusVisitor;
+
+        impl Visitor<'_> for StatusVisitor {
+            type Value = Status;
+
+            fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+                formatter.write_str("a task status, or whether the task is completed")
+            }
+
+            fn visit_bool<E: Error>(self, completed: bool) -> std::result::Result<Status, E> {
+                Ok(if completed { Status::Done } else { Status::Todo })
+            }
+
+            fn visit_str<E: Error>(self, value: &str) -> std::result::Result<Status, E> {
+                Status::from_str(value).map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(StatusVisitor)
+    }
+}
+
+// The databases store the status by name.
+impl<DB: Database> Type<DB> for Status
+where
+    String: Type<DB>,
+{
+    fn type_info() -> DB::TypeInfo {
+        <String as Type<DB>>::type_info()
+    }
+
+    fn compatible(ty: &DB::TypeInfo) -> bool {
+        <String as Type<DB>>::compatible(ty)
+    }
+}
+
+impl<'r, DB: Database> Decode<'r, DB> for Status
+where
+    String: Decode<'r, DB>,
+{
+    fn decode(value: DB::ValueRef<'r>) -> std::result::Result<Self, BoxDynError> {
+        Ok(String::decode(value)?.parse()?)
+    }
+}
+
+impl<'q, DB: Database> Encode<'q, DB> for Status
+where
+    String: Encode<'q, DB>,
+{
+    fn encode_by_ref(&self, buf: &mut DB::ArgumentBuffer<'q>) -> std::result::Result<IsNull, BoxDynError> {
+        self.as_str().to_string().encode(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Status;
+
+    #[test]
+    fn names_round_trip() {
+        for status in Status::ALL {
+            assert_eq!(status.as_str().parse::<Status>().unwrap(), status);
+            assert_eq!(status.to_string().parse::<Status>().unwrap(), status);
+        }
+        assert_eq!("In-Progress".parse::<Status>().unwrap(), Status::InProgress);
+        assert!("finished-ish".parse::<Status>().is_err());
+    }
+
+    #[test]
+    fn completed_flags_from_old_files_are_understood() {
+        assert_eq!(serde_json::from_str::<Status>("true").unwrap(), Status::Done);
+        assert_eq!(serde_json::from_str::<Status>("false").unwrap(), Status::Todo);
+        assert_eq!(serde_json::from_str::<Status>("\"waiting\"").unwrap(), Status::Waiting);
+        assert!(serde_json::from_str::<Status>("\"maybe\"").is_err());
+    }
+}
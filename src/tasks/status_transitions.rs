@@ -0,0 +1,71 @@
+use crate::error::StatusTransitionError;
+use crate::tasks::Status;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Which statuses a task may move to from each status.
+///
+/// Read from the `[transitions]` table of the config file, where each entry replaces the
+/// default list for one status:
+///
+/// ```toml
+/// [transitions]
+/// done = []
+/// waiting = ["in_progress", "cancelled"]
+/// ```
+#[derive(Debug, Default, Hash, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct StatusTransitions(BTreeMap<Status, Vec<Status>>);
+
+impl StatusTransitions {
+    pub fn is_default(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn allows(&self, from: Status, to: Status) -> bool {
+        match self.0.get(&from) {
+            Some(allowed) => allowed.contains(&to),
+            None => Self::default_targets(from).contains(&to),
+        }
+    }
+
+    pub fn check(&self, task: i32, from: Status, to: Status) -> Result<(), StatusTransitionError> {
+        if self.allows(from, to) {
+            Ok(())
+        } else {
+            Err(StatusTransitionError { task, from, to })
+        }
+    }
+
+    /// The statuses given their own transitions in the config file, in order.
+    pub fn configured(&self) -> impl Iterator<Item = (Status, &[Status])> {
+        self.0.iter().map(|(from, to)| (*from, to.as_slice()))
+    }
+
+    /// Open tasks can move to any other status, closed tasks can only be reopened.
+    fn default_targets(from: Status) -> Vec<Status> {
+        if from.is_open() {
+            Status::ALL.into_iter().filter(|to| *to != from).collect()
+        } else {
+            vec![Status::Todo]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StatusTransitions;
+    use crate::tasks::Status;
+
+    #[test]
+    fn configured_entries_replace_the_defaults() {
+        let transitions: StatusTransitions = toml::from_str("done = []\nwaiting = [\"cancelled\"]").unwrap();
+
+        assert!(!transitions.allows(Status::Done, Status::Todo));
+        assert!(transitions.allows(Status::Waiting, Status::Cancelled));
+        assert!(!transitions.allows(Status::Waiting, Status::Done));
+        assert!(transitions.allows(Status::Cancelled, Status::Todo));
+        assert!(transitions.allows(Status::Todo, Status::InProgress));
+        assert!(!StatusTransitions::default().allows(Status::Done, Status::Done));
+    }
+}
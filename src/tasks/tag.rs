@@ -0,0 +1,55 @@
+use anyhow::{bail, Result};
+use std::str::FromStr;
+
+/// Cleans up a tag given on the command line: tags are lowercase and contain no whitespace.
+pub fn normalise_tag(tag: &str) -> Result<String> {
+    let tag = tag.trim().to_lowercase();
+
+    if tag.is_empty() {
+        bail!("Tags cannot be empty");
+    }
+    if tag.starts_with('!') {
+        bail!("Tags cannot start with \"!\"");
+    }
+    if tag.contains(|c: char| c.is_whitespace() || c == ',' || c == ';') {
+        bail!("Tag \"{}\" cannot contain whitespace, commas or semicolons", tag);
+    }
+
+    Ok(tag)
+}
+
+/// A tag a task must have, or with a leading `!`, must not have.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TagSelector {
+    Include(String),
+    Exclude(String),
+}
+
+impl FromStr for TagSelector {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().strip_prefix('!') {
+            Some(tag) => Ok(TagSelector::Exclude(normalise_tag(tag)?)),
+            None => Ok(TagSelector::Include(normalise_tag(s)?)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{normalise_tag, TagSelector};
+    use std::str::FromStr;
+
+    #[test]
+    fn tags_are_normalised_and_validated() {
+        assert_eq!(normalise_tag(" Ops ").unwrap(), "ops");
+        assert!(normalise_tag("").is_err());
+        assert!(normalise_tag("two words").is_err());
+        assert!(normalise_tag("a,b").is_err());
+
+        assert_eq!(TagSelector::from_str("backend").unwrap(), TagSelector::Include("backend".into()));
+        assert_eq!(TagSelector::from_str("!Personal").unwrap(), TagSelector::Exclude("personal".into()));
+        assert!(TagSelector::from_str("!!x").is_err());
+    }
+}
@@ -1,18 +1,88 @@
+use crate::dates::{format_date_time, start_of_local_day};
+use crate::tasks::{NewTask, Priority, Recurrence, Status};
+use anyhow::{bail, Result};
+use chrono::{DateTime, Local, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use std::fmt::{Display, Error, Formatter};
 
 
-#[derive(Debug, Hash, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Hash, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
 pub struct Task {
     pub id: i32,
     pub name: String,
     pub description: Option<String>,
-    pub completed: bool,
+    // Task files written before statuses existed have a `completed` flag in its place.
+    #[serde(alias = "completed")]
+    pub status: Status,
+    // Task files written before timestamps existed are treated as created when first read.
+    #[serde(default = "Utc::now")]
+    pub created_at: DateTime<Utc>,
+    #[serde(default = "Utc::now")]
+    pub updated_at: DateTime<Utc>,
+    #[serde(default)]
+    pub completed_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub due: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub scheduled: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub priority: Priority,
+    #[serde(default)]
+    pub project_id: Option<i32>,
+    #[serde(default)]
+    pub parent_id: Option<i32>,
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>,
+    /// When the task was moved to the trash. Trashed tasks are left out of everything but
+    /// `todo trash` until they are restored or purged.
+    #[serde(default)]
+    pub deleted_at: Option<DateTime<Utc>>,
+    // The database backends keep tags and dependencies in their own tables and fill these in separately.
+    #[serde(default)]
+    #[sqlx(skip)]
+    pub tags: Vec<String>,
+    /// The tasks that have to be completed before this one can start.
+    #[serde(default)]
+    #[sqlx(skip)]
+    pub depends_on: Vec<i32>,
 }
 
 
 impl Task {
+    /// Builds the stored form of a new task once the backend has picked its id.
+    pub fn from_new_task(id: i32, task: NewTask, now: DateTime<Utc>) -> Self {
+        let mut new_task = Self {
+            id,
+            name: task.name,
+            description: task.description,
+            status: Status::Todo,
+            created_at: now,
+            updated_at: now,
+            completed_at: None,
+            due: task.due,
+            scheduled: task.scheduled,
+            priority: task.priority,
+            project_id: task.project_id,
+            parent_id: task.parent_id,
+            recurrence: task.recurrence,
+            deleted_at: None,
+            tags: task.tags,
+            depends_on: vec![],
+        };
+        new_task.sort_lists();
+        new_task
+    }
+
+    /// Sorts the tags and dependencies and drops any duplicates, which is how every backend
+    /// stores them.
+    pub fn sort_lists(&mut self) {
+        self.tags.sort();
+        self.tags.dedup();
+        self.depends_on.sort();
+        self.depends_on.dedup();
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
@@ -21,30 +91,135 @@ impl Task {
         self.description.as_deref()
     }
 
+    pub fn rename(&mut self, name: &str) -> Result<()> {
+        let name = name.trim();
+        if name.is_empty() {
+            bail!("Task names cannot be empty");
+        }
+
+        self.name = name.to_string();
+        Ok(())
+    }
+
     pub fn completed(&self) -> bool {
-        self.completed
+        self.status == Status::Done
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.status.is_open()
+    }
+
+    /// Moves the task to `status`, keeping track of when it was completed. Whether the
+    /// move is allowed is up to the caller, see [`crate::tasks::StatusTransitions`].
+    pub fn set_status(&mut self, status: Status) {
+        self.completed_at = match status {
+            Status::Done => Some(Utc::now()),
+            _ => None,
+        };
+        self.status = status;
     }
 
     pub fn complete(&mut self) {
-        self.completed = true;
+        self.set_status(Status::Done);
+    }
+
+    /// The task to add once this recurring task is completed, due at its next occurrence.
+    /// Returns `None` for tasks that do not repeat.
+    pub fn next_occurrence(&self, now: DateTime<Utc>) -> Result<Option<NewTask>> {
+        let Some(recurrence) = &self.recurrence else {
+            return Ok(None);
+        };
+
+        let completed_at = self.completed_at.unwrap_or(now).with_timezone(&Local);
+        let due = recurrence.next_due(self.due.map(|due| due.with_timezone(&Local)), completed_at)?
+                            .with_timezone(&Utc);
+
+        // The scheduled date keeps the same distance from the due date.
+        let scheduled = match (self.scheduled, self.due) {
+            (Some(scheduled), Some(previous_due)) => Some(due + (scheduled - previous_due)),
+            _ => None,
+        };
+
+        Ok(Some(NewTask {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            due: Some(due),
+            scheduled,
+            priority: self.priority,
+            tags: self.tags.clone(),
+            project_id: self.project_id,
+            parent_id: self.parent_id,
+            recurrence: self.recurrence.clone(),
+        }))
+    }
+
+    /// A task due at the midnight that started today was given a day without a time of day,
+    /// so it only becomes overdue once today is over.
+    pub fn is_overdue(&self, now: DateTime<Utc>) -> bool {
+        self.is_open() && self.due.is_some_and(|due| due < now && Some(due) != start_of_local_day(now))
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+
+    /// Adds a tag, keeping the list sorted. Returns false if the task already had it.
+    pub fn add_tag(&mut self, tag: String) -> bool {
+        match self.tags.binary_search(&tag) {
+            Ok(_) => false,
+            Err(index) => {
+                self.tags.insert(index, tag);
+                true
+            }
+        }
+    }
+
+    /// Removes a tag. Returns false if the task did not have it.
+    pub fn remove_tag(&mut self, tag: &str) -> bool {
+        let before = self.tags.len();
+        self.tags.retain(|t| t != tag);
+        self.tags.len() != before
     }
 }
 
 impl Display for Task {
-    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
-        let completed = if self.completed() { '☑' } else { '☐' };
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::result::Result<(), Error> {
+        let completed = self.status.glyph();
+        let overdue = self.is_overdue(Utc::now());
+        let marker = if overdue { '!' } else { ' ' };
+        let priority = match self.priority {
+            Priority::None => String::new(),
+            priority => format!("[{}] ", priority),
+        };
 
         if let Some(description) = self.description() {
             write!(
                 f,
-                "{}  - {}: {}",
+                "{} {} - {}{}: {}",
                 completed,
+                marker,
+                priority,
                 self.name(),
                 description,
-            )
+            )?;
         } else {
-            write!(f, "{}  - {}", completed, self.name())
+            write!(f, "{} {} - {}{}", completed, marker, priority, self.name())?;
+        }
+
+        if let Some(due) = &self.due {
+            let label = if overdue { "overdue since" } else { "due" };
+            write!(f, " ({} {})", label, format_date_time(due))?;
+        }
+
+        if let Some(recurrence) = &self.recurrence {
+            write!(f, " (repeats {})", recurrence)?;
+        }
+
+        for tag in self.tags.iter() {
+            write!(f, " +{}", tag)?;
         }
+
+        Ok(())
     }
 }
 
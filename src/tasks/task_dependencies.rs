@@ -0,0 +1,98 @@
+use crate::tasks::Task;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Looks for a loop that task `task_id` depending on `depends_on` would close, given the
+/// existing `(task, depends on)` pairs. Returns the loop from `task_id` back to itself.
+pub fn find_dependency_cycle(dependencies: &[(i32, i32)], task_id: i32, depends_on: i32) -> Option<Vec<i32>> {
+    let mut edges = HashMap::<i32, Vec<i32>>::new();
+    for (from, to) in dependencies.iter().filter(|(from, _)| *from != task_id) {
+        edges.entry(*from).or_default().push(*to);
+    }
+
+    // Breadth first, so the shortest loop is the one reported.
+    let mut came_from = HashMap::new();
+    let mut seen = HashSet::from([depends_on]);
+    let mut queue = VecDeque::from([depends_on]);
+
+    while let Some(current) = queue.pop_front() {
+        if current == task_id {
+            let mut path = vec![current];
+            while let Some(previous) = came_from.get(path.last().unwrap()) {
+                path.push(*previous);
+            }
+            path.push(task_id);
+            path.reverse();
+            return Some(path);
+        }
+
+        for next in edges.get(&current).into_iter().flatten() {
+            if seen.insert(*next) {
+                came_from.insert(*next, current);
+                queue.push_back(*next);
+            }
+        }
+    }
+
+    None
+}
+
+/// The dependencies of `task` that are still open.
+pub fn open_blockers(tasks: &[Task], task: &Task) -> Vec<i32> {
+    task.depends_on
+        .iter()
+        .copied()
+        .filter(|id| tasks.iter().any(|blocker| blocker.id == *id && blocker.is_open()))
+        .collect()
+}
+
+/// Every task in `completing` that still waits for an open task, with the ids it waits
+/// for. Tasks completed along with it do not count, so a whole tree can be completed in one
+/// go when only its own tasks wait for each other.
+pub fn blocked_tasks(tasks: &[Task], completing: &[Task]) -> Vec<(i32, Vec<i32>)> {
+    let completing_ids: HashSet<i32> = completing.iter().map(|task| task.id).collect();
+    completing.iter()
+              .map(|task| {
+                  let blockers: Vec<i32> = open_blockers(tasks, task).into_iter()
+                                                                     .filter(|id| !completing_ids.contains(id))
+                                                                     .collect();
+                  (task.id, blockers)
+              })
+              .filter(|(_, blockers)| !blockers.is_empty())
+              .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{blocked_tasks, find_dependency_cycle};
+    use crate::tasks::{NewTask, Task};
+    use chrono::Utc;
+
+    #[test]
+    fn cycles_are_reported_from_the_new_dependency() {
+        // 12 waits for 7, 7 waits for 9.
+        let dependencies = [(12, 7), (7, 9), (3, 12)];
+
+        assert_eq!(find_dependency_cycle(&dependencies, 9, 12), Some(vec![9, 12, 7, 9]));
+        assert_eq!(find_dependency_cycle(&dependencies, 9, 3), Some(vec![9, 3, 12, 7, 9]));
+        assert_eq!(find_dependency_cycle(&dependencies, 4, 4), Some(vec![4, 4]));
+        assert_eq!(find_dependency_cycle(&dependencies, 12, 3), Some(vec![12, 3, 12]));
+        assert_eq!(find_dependency_cycle(&dependencies, 3, 9), None);
+    }
+
+    #[test]
+    fn every_task_completed_in_one_go_is_checked_for_blockers() {
+        let mut tasks: Vec<Task> = (1..=4).map(|id| Task::from_new_task(id, NewTask { name: id.to_string(), ..NewTask::default() }, Utc::now()))
+                                          .collect();
+        // 3 is a subtask of 1 waiting for 2, 4 is a subtask of 1 waiting for 3.
+        tasks[2].parent_id = Some(1);
+        tasks[2].depends_on = vec![2];
+        tasks[3].parent_id = Some(1);
+        tasks[3].depends_on = vec![3];
+
+        let completing = [tasks[2].clone(), tasks[3].clone(), tasks[0].clone()];
+        assert_eq!(blocked_tasks(&tasks, &completing), [(3, vec![2])]);
+
+        tasks[1].complete();
+        assert!(blocked_tasks(&tasks, &completing).is_empty());
+    }
+}
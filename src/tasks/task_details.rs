@@ -0,0 +1,180 @@
+use crate::dates::format_date_time;
+use crate::tasks::{Status, Task};
+use chrono::{DateTime, Utc};
+use std::fmt::Write;
+
+const LABEL_WIDTH: usize = 13;
+
+/// Another task mentioned in a task's details.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskSummary {
+    pub id: i32,
+    pub name: String,
+    pub status: Status,
+}
+
+impl TaskSummary {
+    fn from_task(task: &Task) -> Self {
+        Self {
+            id: task.id,
+            name: task.name.clone(),
+            status: task.status,
+        }
+    }
+}
+
+/// A task together with the project and tasks it is related to, as shown by `todo show`.
+#[derive(Debug, Clone)]
+pub struct TaskDetails {
+    pub task: Task,
+    pub project: Option<String>,
+    pub parent: Option<TaskSummary>,
+    pub subtasks: Vec<TaskSummary>,
+    pub waiting_for: Vec<TaskSummary>,
+    pub blocking: Vec<TaskSummary>,
+}
+
+impl TaskDetails {
+    /// Looks up the relatives of `task` among `tasks`, which should hold every task.
+    pub fn new(task: Task, tasks: &[Task], project: Option<String>) -> Self {
+        let summaries = |include: &dyn Fn(&Task) -> bool| {
+            tasks.iter()
+                 .filter(|other| include(other))
+                 .map(TaskSummary::from_task)
+                 .collect::<Vec<_>>()
+        };
+
+        let parent = task.parent_id.and_then(|id| tasks.iter().find(|other| other.id == id))
+                                   .map(TaskSummary::from_task);
+        let subtasks = summaries(&|other| other.parent_id == Some(task.id));
+        let waiting_for = summaries(&|other| task.depends_on.contains(&other.id));
+        let blocking = summaries(&|other| other.depends_on.contains(&task.id));
+
+        Self {
+            task,
+            project,
+            parent,
+            subtasks,
+            waiting_for,
+            blocking,
+        }
+    }
+
+    /// Renders every field that is set, wrapping the description to fit in `width` columns.
+    pub fn render(&self, width: usize, now: DateTime<Utc>) -> String {
+        let task = &self.task;
+        let mut out = String::new();
+        let mut field = |label: &str, value: &str| {
+            let _ = writeln!(out, "{:<LABEL_WIDTH$}{}", format!("{}:", label), value);
+        };
+
+        field("Task", &format!("{}. {}", task.id, task.name));
+        field("Status", &format!("{} {}", task.status.glyph(), task.status));
+        field("Priority", &task.priority.to_string());
+        if let Some(project) = &self.project {
+            field("Project", project);
+        }
+        if let Some(due) = &task.due {
+            let overdue = if task.is_overdue(now) { " (overdue)" } else { "" };
+            field("Due", &format!("{}{}", format_date_time(due), overdue));
+        }
+        if let Some(scheduled) = &task.scheduled {
+            field("Scheduled", &format_date_time(scheduled));
+        }
+        if let Some(recurrence) = &task.recurrence {
+            field("Repeats", &recurrence.to_string());
+        }
+        if !task.tags.is_empty() {
+            field("Tags", &task.tags.join(", "));
+        }
+        field("Created", &format_date_time(&task.created_at));
+        field("Updated", &format_date_time(&task.updated_at));
+        if let Some(completed_at) = &task.completed_at {
+            field("Completed", &format_date_time(completed_at));
+        }
+        if let Some(parent) = &self.parent {
+            field("Parent", &summary_line(parent));
+        }
+        for (label, summaries) in [("Subtasks", &self.subtasks), ("Waiting for", &self.waiting_for), ("Blocking", &self.blocking)] {
+            for (index, summary) in summaries.iter().enumerate() {
+                field(if index == 0 { label } else { "" }, &summary_line(summary));
+            }
+        }
+
+        if let Some(description) = task.description() {
+            out.push('\n');
+            for line in wrap(description, width.saturating_sub(4)) {
+                let _ = writeln!(out, "    {}", line);
+            }
+        }
+
+        out
+    }
+}
+
+fn summary_line(summary: &TaskSummary) -> String {
+    format!("{}. {} {}", summary.id, summary.status.glyph(), summary.name)
+}
+
+/// Breaks text into lines of at most `width` characters at spaces, keeping its own line
+/// breaks. Words longer than a line are left whole.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(20);
+    let mut lines = vec![];
+
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+        lines.push(line);
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{wrap, TaskDetails};
+    use crate::tasks::{NewTask, Task};
+    use chrono::Utc;
+
+    fn task(id: i32, parent_id: Option<i32>, depends_on: Vec<i32>) -> Task {
+        let new_task = NewTask {
+            name: format!("Task {}", id),
+            parent_id,
+            ..NewTask::default()
+        };
+        let mut task = Task::from_new_task(id, new_task, Utc::now());
+        task.depends_on = depends_on;
+        task
+    }
+
+    #[test]
+    fn relatives_are_looked_up() {
+        let tasks = vec![task(1, None, vec![]), task(2, Some(1), vec![3]), task(3, None, vec![]), task(4, Some(2), vec![2])];
+        let details = TaskDetails::new(tasks[1].clone(), &tasks, None);
+
+        let ids = |summaries: &[super::TaskSummary]| summaries.iter().map(|summary| summary.id).collect::<Vec<_>>();
+        assert_eq!(details.parent.map(|parent| parent.id), Some(1));
+        assert_eq!(ids(&details.subtasks), vec![4]);
+        assert_eq!(ids(&details.waiting_for), vec![3]);
+        assert_eq!(ids(&details.blocking), vec![4]);
+
+        let rendered = TaskDetails::new(tasks[1].clone(), &tasks, Some("house".into())).render(80, Utc::now());
+        assert!(rendered.contains("Project:     house\n"));
+        assert!(rendered.contains("Waiting for: 3. ☐ Task 3\n"));
+    }
+
+    #[test]
+    fn descriptions_wrap_at_spaces() {
+        let text = "The quick brown fox jumps over the lazy dog\n\nagain";
+        assert_eq!(wrap(text, 20), vec!["The quick brown fox", "jumps over the lazy", "dog", "", "again"]);
+    }
+}
@@ -0,0 +1,154 @@
+use crate::dates::{format_date_time, parse_date_time, parse_due_date_time};
+use crate::tasks::{normalise_tag, Priority, Recurrence, Task};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// The parts of a task that can be changed by hand, as written out for `todo edit`.
+///
+/// Dates are kept as the expressions the user typed and only parsed when the edit is
+/// applied, so `due = "friday 5pm"` works as it does on the command line.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TaskEdit {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scheduled: Option<String>,
+    #[serde(default)]
+    pub priority: Priority,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repeat: Option<Recurrence>,
+}
+
+impl TaskEdit {
+    pub fn from_task(task: &Task) -> Self {
+        Self {
+            name: task.name.clone(),
+            description: task.description.clone(),
+            due: task.due.as_ref().map(format_date_time),
+            scheduled: task.scheduled.as_ref().map(format_date_time),
+            priority: task.priority,
+            tags: task.tags.clone(),
+            repeat: task.recurrence.clone(),
+        }
+    }
+
+    /// Writes the edit out as TOML, headed by comments explaining the fields.
+    pub fn to_toml(&self, id: i32) -> Result<String> {
+        let fields = toml::to_string(self)?;
+
+        Ok(format!(
+            "# Editing task {}. Save and quit to apply the changes, or empty the file to cancel.\n\
+             # Optional fields: description, due, scheduled and repeat. Remove a line to clear it.\n\
+             # Dates take the same forms as --due, such as \"friday 5pm\" or \"2026-11-01\".\n\
+             \n\
+             {}",
+            id, fields,
+        ))
+    }
+
+    pub fn from_toml(contents: &str) -> Result<Self> {
+        Ok(toml::from_str(contents)?)
+    }
+
+    /// Checks the edited fields and copies them onto `task`.
+    pub fn apply(self, task: &mut Task) -> Result<()> {
+        task.rename(&self.name)?;
+        task.description = self.description.filter(|description| !description.trim().is_empty());
+        task.due = apply_date(task.due, self.due, parse_due_date_time).context("Invalid due date")?;
+        task.scheduled = apply_date(task.scheduled, self.scheduled, parse_date_time).context("Invalid scheduled date")?;
+        task.priority = self.priority;
+        task.recurrence = self.repeat;
+
+        let mut tags = self.tags.iter()
+                                .map(|tag| normalise_tag(tag))
+                                .collect::<Result<Vec<_>>>()?;
+        tags.sort();
+        tags.dedup();
+        task.tags = tags;
+
+        Ok(())
+    }
+}
+
+/// Parses an edited date, keeping the current one when its text was left alone. Written
+/// out dates leave off the seconds, so parsing them again would move the date slightly.
+fn apply_date(
+    current: Option<DateTime<Utc>>,
+    edited: Option<String>,
+    parse: fn(&str) -> Result<DateTime<Utc>>,
+) -> Result<Option<DateTime<Utc>>> {
+    match edited {
+        None => Ok(None),
+        Some(edited) if current.as_ref().map(format_date_time).as_ref() == Some(&edited) => Ok(current),
+        Some(edited) if edited.trim().is_empty() => bail!("Dates cannot be empty, remove the line instead"),
+        Some(edited) => parse(&edited).map(Some),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TaskEdit;
+    use crate::tasks::{NewTask, Priority, Task};
+    use chrono::{TimeZone, Utc};
+
+    fn task() -> Task {
+        let new_task = NewTask {
+            name: "Write report".into(),
+            description: Some("For the board".into()),
+            ..NewTask::default()
+        };
+        let mut task = Task::from_new_task(7, new_task, Utc::now());
+        task.due = Some(Utc.with_ymd_and_hms(2026, 11, 6, 23, 59, 59).unwrap());
+        task.tags = vec!["work".into()];
+        task
+    }
+
+    #[test]
+    fn an_unchanged_edit_leaves_the_task_alone() {
+        let original = task();
+        let contents = TaskEdit::from_task(&original).to_toml(original.id).unwrap();
+
+        let mut edited = original.clone();
+        TaskEdit::from_toml(&contents).unwrap().apply(&mut edited).unwrap();
+        assert_eq!(edited, original);
+    }
+
+    #[test]
+    fn removed_lines_clear_optional_fields() {
+        let mut task = task();
+        let edit = TaskEdit::from_toml("name = \"Write the report\"\npriority = \"high\"\ntags = [\"Work\", \"q4\"]").unwrap();
+        edit.apply(&mut task).unwrap();
+
+        assert_eq!(task.name, "Write the report");
+        assert_eq!(task.description, None);
+        assert_eq!(task.due, None);
+        assert_eq!(task.priority, Priority::High);
+        assert_eq!(task.tags, vec!["q4", "work"]);
+    }
+
+    #[test]
+    fn an_empty_description_is_cleared() {
+        for description in ["", "  "] {
+            let mut task = task();
+            let mut edit = TaskEdit::from_task(&task);
+            edit.description = Some(description.into());
+            edit.apply(&mut task).unwrap();
+            assert_eq!(task.description, None);
+        }
+    }
+
+    #[test]
+    fn invalid_edits_are_rejected() {
+        assert!(TaskEdit::from_toml("name = \"x\"\ncolour = \"red\"").is_err());
+        assert!(TaskEdit::from_toml("name = \"x\"\nrepeat = \"sometimes\"").is_err());
+        assert!(TaskEdit::from_toml("name = \"   \"").unwrap().apply(&mut task()).is_err());
+        assert!(TaskEdit::from_toml("name = \"x\"\ndue = \"someday\"").unwrap().apply(&mut task()).is_err());
+    }
+}
@@ -0,0 +1,81 @@
+use crate::query::Query;
+use crate::tasks::{TagSelector, Task};
+use chrono::{DateTime, Utc};
+
+/// Narrows down which tasks a listing returns. An empty filter matches every task.
+#[derive(Debug, Default, Clone)]
+pub struct TaskFilter {
+    /// Only open tasks whose due date has passed.
+    pub overdue: bool,
+    pub due_before: Option<DateTime<Utc>>,
+    pub due_after: Option<DateTime<Utc>>,
+    pub tags: Vec<TagSelector>,
+    /// Only tasks in this project.
+    pub project_id: Option<i32>,
+    /// Also tasks in archived projects, which are otherwise left out unless asked for by project.
+    pub archived: bool,
+    /// Only open tasks whose dependencies are all complete.
+    pub ready: bool,
+    /// Only tasks matching a query. Backends evaluate it themselves, since it needs
+    /// project names.
+    pub query: Option<Query>,
+}
+
+impl TaskFilter {
+    pub fn matches(&self, task: &Task, now: DateTime<Utc>) -> bool {
+        let overdue = !self.overdue || task.is_overdue(now);
+        let due_before = self.due_before.is_none_or(|before| task.due.is_some_and(|due| due < before));
+        let due_after = self.due_after.is_none_or(|after| task.due.is_some_and(|due| due > after));
+        let tags = self.tags.iter().all(|selector| match selector {
+            TagSelector::Include(tag) => task.has_tag(tag),
+            TagSelector::Exclude(tag) => !task.has_tag(tag),
+        });
+
+        let project = self.project_id.is_none_or(|project_id| task.project_id == Some(project_id));
+
+        overdue && due_before && due_after && tags && project
+    }
+
+    /// Whether tasks in archived projects should be left out of the results.
+    pub fn hides_archived(&self) -> bool {
+        !self.archived && self.project_id.is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TaskFilter;
+    use crate::tasks::{NewTask, Task};
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn due_filters_skip_tasks_without_a_due_date() {
+        let now = Utc.with_ymd_and_hms(2026, 10, 18, 12, 0, 0).unwrap();
+        let mut overdue = Task::from_new_task(1, NewTask {
+            name: "overdue".into(),
+            due: Some(Utc.with_ymd_and_hms(2026, 10, 1, 0, 0, 0).unwrap()),
+            ..NewTask::default()
+        }, now);
+        let undated = Task::from_new_task(2, NewTask {
+            name: "undated".into(),
+            ..NewTask::default()
+        }, now);
+
+        let filter = TaskFilter {
+            overdue: true,
+            ..TaskFilter::default()
+        };
+        assert!(filter.matches(&overdue, now));
+        assert!(!filter.matches(&undated, now));
+
+        let filter = TaskFilter {
+            due_before: Some(now),
+            ..TaskFilter::default()
+        };
+        assert!(filter.matches(&overdue, now));
+        assert!(!filter.matches(&undated, now));
+
+        overdue.complete();
+        assert!(!TaskFilter { overdue: true, ..TaskFilter::default() }.matches(&overdue, now));
+    }
+}
@@ -0,0 +1,81 @@
+use crate::tasks::Task;
+use clap::ValueEnum;
+use std::cmp::{Ordering, Reverse};
+
+/// The orders `todo list` can show tasks in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TaskSort {
+    /// Most important first, then soonest due.
+    #[default]
+    Priority,
+    /// Soonest due first, then most important.
+    Due,
+    Id,
+    Name,
+    Created,
+}
+
+impl TaskSort {
+    pub fn sort(&self, tasks: &mut [Task]) {
+        match self {
+            TaskSort::Priority => tasks.sort_by(|a, b| {
+                Reverse(a.priority).cmp(&Reverse(b.priority))
+                                   .then_with(|| compare_due(a, b))
+                                   .then_with(|| a.id.cmp(&b.id))
+            }),
+            TaskSort::Due => tasks.sort_by(|a, b| {
+                compare_due(a, b).then_with(|| Reverse(a.priority).cmp(&Reverse(b.priority)))
+                                 .then_with(|| a.id.cmp(&b.id))
+            }),
+            TaskSort::Id => tasks.sort_by_key(|task| task.id),
+            TaskSort::Name => tasks.sort_by(|a, b| {
+                a.name.to_lowercase().cmp(&b.name.to_lowercase()).then_with(|| a.id.cmp(&b.id))
+            }),
+            TaskSort::Created => tasks.sort_by_key(|task| (task.created_at, task.id)),
+        }
+    }
+}
+
+/// Earliest due date first, with tasks that have no due date last.
+fn compare_due(a: &Task, b: &Task) -> Ordering {
+    match (a.due, b.due) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TaskSort;
+    use crate::tasks::{NewTask, Priority, Task};
+    use chrono::{Duration, TimeZone, Utc};
+
+    #[test]
+    fn priority_sort_breaks_ties_by_due_date() {
+        let now = Utc.with_ymd_and_hms(2026, 10, 18, 12, 0, 0).unwrap();
+        let task = |id: i32, priority: Priority, due_in_days: Option<i64>| Task::from_new_task(id, NewTask {
+            name: format!("task {}", id),
+            priority,
+            due: due_in_days.map(|days| now + Duration::days(days)),
+            ..NewTask::default()
+        }, now);
+
+        let mut tasks = vec![
+            task(1, Priority::None, Some(1)),
+            task(2, Priority::High, None),
+            task(3, Priority::High, Some(5)),
+            task(4, Priority::Urgent, None),
+            task(5, Priority::High, Some(2)),
+        ];
+
+        TaskSort::Priority.sort(&mut tasks);
+        let ids: Vec<_> = tasks.iter().map(|task| task.id).collect();
+        assert_eq!(ids, vec![4, 5, 3, 2, 1]);
+
+        TaskSort::Due.sort(&mut tasks);
+        let ids: Vec<_> = tasks.iter().map(|task| task.id).collect();
+        assert_eq!(ids, vec![1, 5, 3, 4, 2]);
+    }
+}
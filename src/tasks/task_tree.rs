@@ -0,0 +1,138 @@
+use crate::tasks::Task;
+use std::collections::HashSet;
+
+/// Whether putting task `task_id` below `parent_id` would make a task its own ancestor.
+pub fn would_create_cycle(tasks: &[Task], task_id: i32, parent_id: i32) -> bool {
+    let mut seen = HashSet::new();
+    let mut current = Some(parent_id);
+
+    while let Some(id) = current {
+        if id == task_id {
+            return true;
+        }
+        if !seen.insert(id) {
+            // An existing cycle that does not involve this task, leave it to `todo doctor`.
+            return false;
+        }
+        current = tasks.iter().find(|task| task.id == id).and_then(|task| task.parent_id);
+    }
+
+    false
+}
+
+/// Every task below `id`, children before grandchildren.
+pub fn descendants(tasks: &[Task], id: i32) -> Vec<&Task> {
+    let mut found: Vec<&Task> = vec![];
+    let mut seen = HashSet::from([id]);
+    let mut index = 0;
+    let mut parent = Some(id);
+
+    while let Some(parent_id) = parent {
+        for task in tasks.iter().filter(|task| task.parent_id == Some(parent_id)) {
+            if seen.insert(task.id) {
+                found.push(task);
+            }
+        }
+
+        parent = found.get(index).map(|task| task.id);
+        index += 1;
+    }
+
+    found
+}
+
+/// How many of the direct subtasks of `id` are closed, out of how many, if it has any.
+pub fn subtask_progress(tasks: &[Task], id: i32) -> Option<(usize, usize)> {
+    let children: Vec<_> = tasks.iter().filter(|task| task.parent_id == Some(id)).collect();
+    if children.is_empty() {
+        return None;
+    }
+
+    let done = children.iter().filter(|task| !task.is_open()).count();
+    Some((done, children.len()))
+}
+
+/// Orders a listing as a tree, pairing each task with its depth. Subtasks follow their
+/// parent in the order they were given; tasks whose parent is not in the listing are roots.
+pub fn flatten_tree<'a>(tasks: &[&'a Task]) -> Vec<(usize, &'a Task)> {
+    let listed: HashSet<_> = tasks.iter().map(|task| task.id).collect();
+    let mut flattened = Vec::with_capacity(tasks.len());
+    let mut visited = HashSet::new();
+
+    for root in tasks.iter().filter(|task| task.parent_id.is_none_or(|parent| !listed.contains(&parent))) {
+        push_subtree(tasks, root, 0, &mut visited, &mut flattened);
+    }
+
+    // Anything left over is caught in a cycle, show it rather than lose it.
+    for task in tasks.iter() {
+        push_subtree(tasks, task, 0, &mut visited, &mut flattened);
+    }
+
+    flattened
+}
+
+fn push_subtree<'a>(tasks: &[&'a Task], task: &'a Task, depth: usize, visited: &mut HashSet<i32>, flattened: &mut Vec<(usize, &'a Task)>) {
+    if !visited.insert(task.id) {
+        return;
+    }
+
+    flattened.push((depth, task));
+    for child in tasks.iter().filter(|child| child.parent_id == Some(task.id)) {
+        push_subtree(tasks, child, depth + 1, visited, flattened);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{descendants, flatten_tree, subtask_progress, would_create_cycle};
+    use crate::tasks::{NewTask, Task};
+    use chrono::Utc;
+
+    fn task(id: i32, parent_id: Option<i32>) -> Task {
+        Task::from_new_task(id, NewTask {
+            name: format!("task {}", id),
+            parent_id,
+            ..NewTask::default()
+        }, Utc::now())
+    }
+
+    // 1 ─┬─ 2 ── 4
+    //    └─ 3
+    // 5
+    fn sample() -> Vec<Task> {
+        vec![task(1, None), task(2, Some(1)), task(3, Some(1)), task(4, Some(2)), task(5, None)]
+    }
+
+    #[test]
+    fn cycles_are_detected() {
+        let tasks = sample();
+        assert!(would_create_cycle(&tasks, 1, 4));
+        assert!(would_create_cycle(&tasks, 2, 2));
+        assert!(!would_create_cycle(&tasks, 4, 3));
+        assert!(!would_create_cycle(&tasks, 1, 5));
+    }
+
+    #[test]
+    fn descendants_include_grandchildren() {
+        let tasks = sample();
+        let ids: Vec<_> = descendants(&tasks, 1).into_iter().map(|task| task.id).collect();
+        assert_eq!(ids, vec![2, 3, 4]);
+        assert!(descendants(&tasks, 5).is_empty());
+    }
+
+    #[test]
+    fn progress_counts_direct_subtasks() {
+        let mut tasks = sample();
+        tasks[2].complete();
+        assert_eq!(subtask_progress(&tasks, 1), Some((1, 2)));
+        assert_eq!(subtask_progress(&tasks, 5), None);
+    }
+
+    #[test]
+    fn tree_nests_subtasks_under_their_parent() {
+        let tasks = sample();
+        let listing: Vec<_> = [4, 5, 3, 1, 2].iter().map(|id| &tasks[id - 1]).collect();
+        let flattened: Vec<_> = flatten_tree(&listing).into_iter().map(|(depth, task)| (depth, task.id)).collect();
+        assert_eq!(flattened, vec![(0, 5), (0, 1), (1, 3), (1, 2), (2, 4)]);
+    }
+}